@@ -0,0 +1,118 @@
+//! Removes ANSI escape sequences from terminal output, for logs and searches, and detects the terminal bell.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum StripState {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    String,
+    StringEscape,
+}
+
+/// Removes ANSI escape sequences and control characters from terminal output.
+///
+/// Sequences can be split across stdout chunks, so the parser state is retained between calls.
+#[derive(Debug, Clone, Default)]
+pub struct AnsiStripper {
+    state: StripState,
+}
+
+/// A byte of terminal output, classified by the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token {
+    Text(u8),
+    Bell,
+    Ignored,
+}
+
+impl AnsiStripper {
+    pub fn strip(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len());
+
+        for byte in data.iter().copied() {
+            if let Token::Text(byte) = self.advance(byte) {
+                output.push(byte);
+            }
+        }
+
+        output
+    }
+
+    /// Parses the data without producing output.
+    /// Returns true if it contains a bell character (BEL), outside of an escape sequence.
+    pub fn contains_bell(&mut self, data: &[u8]) -> bool {
+        let mut bell = false;
+
+        for byte in data.iter().copied() {
+            if let Token::Bell = self.advance(byte) {
+                bell = true;
+            }
+        }
+
+        bell
+    }
+
+    fn advance(&mut self, byte: u8) -> Token {
+        let (state, token) = match self.state {
+            StripState::Ground => match byte {
+                0x1b => (StripState::Escape, Token::Ignored),
+                0x07 => (StripState::Ground, Token::Bell),
+                b'\n' | b'\t' => (StripState::Ground, Token::Text(byte)),
+                0x00..=0x1f | 0x7f => (StripState::Ground, Token::Ignored),
+                _ => (StripState::Ground, Token::Text(byte)),
+            },
+            StripState::Escape => match byte {
+                b'[' => (StripState::Csi, Token::Ignored),
+                b']' | b'P' | b'X' | b'^' | b'_' => (StripState::String, Token::Ignored),
+                0x20..=0x2f => (StripState::EscapeIntermediate, Token::Ignored),
+                _ => (StripState::Ground, Token::Ignored),
+            },
+            StripState::EscapeIntermediate => match byte {
+                0x20..=0x2f => (StripState::EscapeIntermediate, Token::Ignored),
+                _ => (StripState::Ground, Token::Ignored),
+            },
+            StripState::Csi => match byte {
+                0x40..=0x7e => (StripState::Ground, Token::Ignored),
+                _ => (StripState::Csi, Token::Ignored),
+            },
+            StripState::String => match byte {
+                // BEL terminates an OSC sequence, and does not ring the bell
+                0x07 => (StripState::Ground, Token::Ignored),
+                0x1b => (StripState::StringEscape, Token::Ignored),
+                _ => (StripState::String, Token::Ignored),
+            },
+            StripState::StringEscape => (StripState::Ground, Token::Ignored),
+        };
+
+        self.state = state;
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnsiStripper;
+
+    #[test]
+    fn strip_ansi() {
+        let mut stripper = AnsiStripper::default();
+        let stripped = stripper.strip(b"\x1b[1;32mgreen\x1b[0m\r\n\x1b]0;title\x07done\x1b(B\n");
+        assert_eq!(b"green\ndone\n".to_vec(), stripped);
+    }
+
+    #[test]
+    fn strip_split_sequence() {
+        let mut stripper = AnsiStripper::default();
+        assert_eq!(b"a".to_vec(), stripper.strip(b"a\x1b[3"));
+        assert_eq!(b"b".to_vec(), stripper.strip(b"1mb"));
+    }
+
+    #[test]
+    fn contains_bell() {
+        let mut stripper = AnsiStripper::default();
+        assert!(!stripper.contains_bell(b"\x1b]0;title\x07$ "));
+        assert!(stripper.contains_bell(b"done\x07"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Tab session recordings, in the asciicast v2 format.
+//!
+//! A recording is a JSON header line, followed by one JSON event per line.
+//! See: https://github.com/asciinema/asciinema/blob/develop/doc/asciicast-v2.md
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The first line of an asciicast v2 recording.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    /// Unix timestamp of the beginning of the recording session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u64>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+}
+
+impl Header {
+    /// Creates a header for a terminal with the given (cols, rows)
+    pub fn new(dimensions: (u16, u16), timestamp: u64) -> Self {
+        Self {
+            version: 2,
+            width: dimensions.0,
+            height: dimensions.1,
+            timestamp: Some(timestamp),
+            env: HashMap::new(),
+        }
+    }
+}
+
+/// A timestamped event in a recording.  The time is in seconds, relative to the start of the recording.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Data written to stdout
+    Output(f64, String),
+    /// The terminal was resized to the given (cols, rows)
+    Resize(f64, (u16, u16)),
+}
+
+impl Event {
+    /// The time of the event, in seconds since the start of the recording
+    pub fn time(&self) -> f64 {
+        match self {
+            Event::Output(time, _) => *time,
+            Event::Resize(time, _) => *time,
+        }
+    }
+
+    /// Serializes the event as an asciicast line, without the trailing newline
+    pub fn to_line(&self) -> serde_json::Result<String> {
+        match self {
+            Event::Output(time, data) => serde_json::to_string(&(time, "o", data)),
+            Event::Resize(time, (cols, rows)) => {
+                let size = format!("{}x{}", cols, rows);
+                serde_json::to_string(&(time, "r", size))
+            }
+        }
+    }
+
+    /// Parses an asciicast event line.  Returns None for event types which are not supported (e.g. input)
+    pub fn parse(line: &str) -> anyhow::Result<Option<Event>> {
+        let (time, code, data): (f64, String, String) = serde_json::from_str(line)?;
+
+        let event = match code.as_str() {
+            "o" => Some(Event::Output(time, data)),
+            "r" => {
+                let mut split = data.splitn(2, 'x');
+                let cols = split.next().unwrap_or("").parse()?;
+                let rows = split.next().unwrap_or("").parse()?;
+                Some(Event::Resize(time, (cols, rows)))
+            }
+            _ => None,
+        };
+
+        Ok(event)
+    }
+}
+
+/// Parses a complete asciicast v2 recording, returning the header and supported events.
+pub fn parse(recording: &str) -> anyhow::Result<(Header, Vec<Event>)> {
+    let mut lines = recording.lines().filter(|line| !line.trim().is_empty());
+
+    let header = lines
+        .next()
+        .ok_or_else(|| anyhow::Error::msg("the recording is empty"))?;
+    let header: Header = serde_json::from_str(header)?;
+
+    if header.version != 2 {
+        return Err(anyhow::format_err!(
+            "unsupported asciicast version: {}",
+            header.version
+        ));
+    }
+
+    let mut events = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let event = Event::parse(line)
+            .map_err(|err| anyhow::format_err!("invalid event on line {}: {}", index + 2, err))?;
+
+        if let Some(event) = event {
+            events.push(event);
+        }
+    }
+
+    Ok((header, events))
+}
+
+/// Decodes stdout chunks into UTF-8 strings, for output events.
+///
+/// Chunks can end in the middle of a multi-byte character,
+/// so incomplete sequences are retained until the next chunk arrives.
+#[derive(Debug, Clone, Default)]
+pub struct OutputDecoder {
+    pending: Vec<u8>,
+}
+
+impl OutputDecoder {
+    pub fn decode(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+
+        let mut output = String::new();
+        let mut bytes = self.pending.as_slice();
+
+        loop {
+            match std::str::from_utf8(bytes) {
+                Ok(valid) => {
+                    output.push_str(valid);
+                    bytes = &[];
+                    break;
+                }
+                Err(err) => {
+                    let (valid, rest) = bytes.split_at(err.valid_up_to());
+                    output.push_str(std::str::from_utf8(valid).unwrap());
+
+                    match err.error_len() {
+                        Some(len) => {
+                            output.push(char::REPLACEMENT_CHARACTER);
+                            bytes = &rest[len..];
+                        }
+                        None => {
+                            // an incomplete sequence at the end of the chunk
+                            bytes = rest;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending = bytes.to_vec();
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, Event, Header, OutputDecoder};
+
+    #[test]
+    fn event_roundtrip() -> anyhow::Result<()> {
+        let output = Event::Output(1.5, "hello\r\n".into());
+        let resize = Event::Resize(2.0, (80, 24));
+
+        assert_eq!(r#"[1.5,"o","hello\r\n"]"#, output.to_line()?);
+        assert_eq!(r#"[2.0,"r","80x24"]"#, resize.to_line()?);
+
+        assert_eq!(Some(output.clone()), Event::parse(&output.to_line()?)?);
+        assert_eq!(Some(resize.clone()), Event::parse(&resize.to_line()?)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_recording() -> anyhow::Result<()> {
+        let header = serde_json::to_string(&Header::new((80, 24), 1))?;
+        let recording = format!(
+            "{}\n{}\n[0.5, \"i\", \"ls\"]\n{}\n",
+            header,
+            Event::Output(0.25, "$ ".into()).to_line()?,
+            Event::Resize(1.0, (100, 30)).to_line()?
+        );
+
+        let (header, events) = parse(recording.as_str())?;
+        assert_eq!(Header::new((80, 24), 1), header);
+        assert_eq!(
+            vec![
+                Event::Output(0.25, "$ ".into()),
+                Event::Resize(1.0, (100, 30))
+            ],
+            events
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn decode_split_character() {
+        let mut decoder = OutputDecoder::default();
+        let bytes = "a→b".as_bytes();
+
+        assert_eq!("a", decoder.decode(&bytes[0..2]));
+        assert_eq!("→b", decoder.decode(&bytes[2..]));
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let mut decoder = OutputDecoder::default();
+        assert_eq!("a\u{FFFD}b", decoder.decode(&[b'a', 0xFF, b'b']));
+    }
+}
@@ -3,7 +3,7 @@
 use crate::chunk::OutputChunk;
 use crate::{
     chunk::InputChunk,
-    tab::{CreateTabMetadata, TabId, TabMetadata},
+    tab::{CloseOptions, CreateTabMetadata, TabActivity, TabId, TabMetadata, TabSignal},
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -25,20 +25,41 @@ pub enum Request {
     /// Terminates the shell on the given tab
     CreateTab(CreateTabMetadata),
 
+    /// Requests a copy of the scrollback buffer for the given tab, without subscribing.
+    /// The daemon replies with a `Response::Scrollback` message.
+    Scrollback(TabId),
+
     /// Resizes the given tab, to the provided (cols, rows)
     ResizeTab(TabId, (u16, u16)),
 
     /// Re-tasks clients with the tabid selected to the given tab
     Retask(TabId, TabId),
 
-    /// Terminates the shell on the given tab
-    CloseTab(TabId),
+    /// Terminates the shell on the given tab.
+    /// The daemon replies with `Response::TabClosing` messages as signals are sent.
+    CloseTab(TabId, CloseOptions),
+
+    /// Terminates the shell on the tabs matching the name, prefix (`proj/*`), or tag (`@backend`)
+    CloseNamedTab(String, CloseOptions),
+
+    /// Renames the given tab.  Ignored if a tab with the new name is already running.
+    RenameTab(TabId, String),
 
-    /// Terminates the shell on the given tab, by name
-    CloseNamedTab(String),
+    /// Records the output of the given tab to a file, in asciicast v2 format
+    RecordTab(TabId, String),
+
+    /// Appends the output of the given tab to a log file, with ANSI escape sequences removed
+    LogTab(TabId, String),
+
+    /// Delivers the signal to the foreground process group of the given tab
+    Signal(TabId, TabSignal),
 
     /// Shuts down all tab processes, including the daemon and all ptys
     GlobalShutdown,
+
+    /// Requests the state of the daemon, including connections and pty assignments.
+    /// The daemon replies with a `Response::Status` message.
+    Status,
 }
 
 /// A response, sent from the daemon process to a connected CLI
@@ -48,12 +69,20 @@ pub enum Response {
     Init(InitResponse),
     /// A raw output chunk, identified by a `TabId` and an index.
     Output(TabId, OutputChunk),
+    /// A copy of the scrollback buffer for the given tab, requested with `Request::Scrollback`
+    Scrollback(TabId, Vec<u8>),
     /// A notification that metadata about a running tab has changed.
     TabUpdate(TabMetadata),
+    /// A notification that the unread, bell, or silent flags of a running tab have changed.
+    TabActivity(TabId, TabActivity),
     /// A notification that the client is being re-tasks, and will now be serving the user on another tab.
     Retask(TabId),
+    /// A notification that the signal has been sent to the processes in a closing tab.
+    TabClosing(TabId, TabSignal),
     /// A notification that the tab has been terminated
     TabTerminated(TabId),
+    /// The state of the daemon, requested with `Request::Status`
+    Status(DaemonStatus),
 }
 
 /// An initialization message sent to CLI connections.
@@ -61,4 +90,52 @@ pub enum Response {
 pub struct InitResponse {
     /// A complete set of active tabs, identified by TabId values.
     pub tabs: HashMap<TabId, TabMetadata>,
+    /// The activity of running tabs, identified by TabId values.
+    pub activity: HashMap<TabId, TabActivity>,
+}
+
+/// The state of the daemon process, for `tab --status`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonStatus {
+    /// The process id of the daemon
+    pub pid: i32,
+    /// The version of the daemon
+    pub version: String,
+    /// The time the daemon started, in seconds since the unix epoch
+    pub started: u64,
+    /// The port of the websocket listener, bound to 127.0.0.1
+    pub port: u16,
+    /// The number of connected CLI clients, including the client which requested the status
+    pub cli_connections: usize,
+    /// The number of connected pty processes, including ptys which are waiting for a tab assignment
+    pub pty_connections: usize,
+    /// The number of connected pty processes which are waiting for a tab assignment
+    #[serde(default)]
+    pub idle_ptys: usize,
+    /// The number of idle pty processes which the daemon keeps ready, configured with `TAB_PTY_POOL`
+    #[serde(default)]
+    pub pty_pool: usize,
+    /// The running tabs, ordered by id
+    pub tabs: Vec<TabStatus>,
+    /// The bounded channels of the daemon, and their capacities
+    pub channels: Vec<ChannelStatus>,
+}
+
+/// The pty assignment of a running tab
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TabStatus {
+    pub id: TabId,
+    pub name: String,
+    /// Whether a pty has been assigned to the tab.  Tabs are unassigned while the pty process is launching.
+    pub assigned: bool,
+    /// The process id of the assigned pty, if it was reported
+    pub pty: Option<u32>,
+}
+
+/// The capacity of a bounded channel, identified by the bus and message type
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChannelStatus {
+    pub bus: String,
+    pub message: String,
+    pub capacity: usize,
 }
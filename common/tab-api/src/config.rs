@@ -1,8 +1,16 @@
+use crate::tab::TabId;
 use anyhow::Result;
 use lifeline::impl_storage_clone;
 use serde::Deserialize;
 use serde::Serialize;
-use std::{env, fs::File, io::BufReader, path::PathBuf};
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::BufReader,
+    path::PathBuf,
+};
 use sysinfo::{RefreshKind, SystemExt};
 
 /// Config created for each daemon process
@@ -11,6 +19,10 @@ pub struct DaemonConfig {
     pub pid: i32,
     pub port: u16,
     pub auth_token: String,
+    /// The version of the daemon, which may differ from the CLI after an upgrade.
+    /// Empty if the daemon was launched by a version of tab which did not record it.
+    #[serde(default)]
+    pub version: String,
 }
 
 impl_storage_clone!(DaemonConfig);
@@ -35,6 +47,20 @@ pub fn data_path() -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// The full path to tab's configuration directory, which can contain a global `tab.yml` workspace.
+pub fn config_path() -> Result<PathBuf> {
+    if let Ok(var) = env::var("TAB_CONFIG_DIR") {
+        return Ok(PathBuf::from(var));
+    }
+
+    let mut dir =
+        dirs::config_dir().ok_or_else(|| anyhow::Error::msg("tab config dir not found"))?;
+
+    dir.push("tab");
+
+    Ok(dir)
+}
+
 /// The full path to the daemon's pidfile, used to identify the running process, and the available websocket port.
 /// Also stores an auth token that is required (in the Authorization header) to connect to the daemon.
 pub fn daemon_file() -> Result<PathBuf> {
@@ -58,6 +84,46 @@ pub fn daemon_log() -> Result<PathBuf> {
     Ok(dir)
 }
 
+/// Returns the path to the logfile of the pty process with the given pid.
+pub fn pty_log(pid: u32) -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("logs");
+    dir.push(format!("pty-{}.log", pid));
+    Ok(dir)
+}
+
+/// Returns the path to the tab activity summary, which is written by the daemon, and read by statusline integrations.
+pub fn activity_path() -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("activity.txt");
+    Ok(dir)
+}
+
+/// Returns the path to the list of recently selected tabs, which is shared by all of the user's clients.
+pub fn recent_path() -> Result<PathBuf> {
+    let mut dir = data_path()?;
+    dir.push("recent.txt");
+    Ok(dir)
+}
+
+/// Returns the path to the current name of a running tab, which is written by the pty when the tab is renamed.
+/// Read by shell integrations, which update `$TAB` at the next prompt.
+pub fn tab_name_path(id: TabId) -> Result<PathBuf> {
+    let mut path = data_path()?;
+    path.push("names");
+    path.push(format!("tab-{}.txt", id.0));
+    Ok(path)
+}
+
+/// Returns the fish history session for the given tab name, which is set in the `fish_history` env var.
+pub fn fish_history(name: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    let id = hasher.finish();
+
+    format!("tab_{}", id)
+}
+
 /// Returns the path to a unique logfile fro the given shell process, and tab name.
 pub fn history_path(shell: &str, name: &str) -> Result<PathBuf> {
     let mut path = data_path()?;
@@ -89,7 +155,8 @@ pub fn load_daemon_file() -> anyhow::Result<Option<DaemonConfig>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{daemon_file, data_path};
+    use super::{config_path, daemon_file, data_path, fish_history, tab_name_path};
+    use crate::tab::TabId;
 
     #[test]
     fn data_path_matches() {
@@ -101,6 +168,16 @@ mod tests {
         assert_eq!(expected, path.unwrap());
     }
 
+    #[test]
+    fn config_path_matches() {
+        let mut expected = dirs::config_dir().expect("home dir required");
+        expected.push("tab");
+
+        let path = config_path();
+        assert!(path.is_ok());
+        assert_eq!(expected, path.unwrap());
+    }
+
     #[test]
     fn daemonfile_path_matches() {
         let mut expected = dirs::data_dir().expect("home dir required");
@@ -111,4 +188,23 @@ mod tests {
         assert!(path.is_ok());
         assert_eq!(expected, path.unwrap());
     }
+
+    #[test]
+    fn tab_name_path_matches() {
+        let mut expected = dirs::data_dir().expect("home dir required");
+        expected.push("tab");
+        expected.push("names");
+        expected.push("tab-3.txt");
+
+        let path = tab_name_path(TabId(3));
+        assert!(path.is_ok());
+        assert_eq!(expected, path.unwrap());
+    }
+
+    #[test]
+    fn fish_history_is_stable() {
+        assert_eq!(fish_history("foo/"), fish_history("foo/"));
+        assert_ne!(fish_history("foo/"), fish_history("bar/"));
+        assert!(fish_history("foo/").starts_with("tab_"));
+    }
 }
@@ -16,6 +16,14 @@ pub const FORWARD_ENV_VARS: &[&str] = &[
     "TAB_RUNTIME_DIR", // The daemon & pty should inherit the runtime directory of the command client
     "TAB_RAW_MODE", // Raw mode controls stderr forwarding.  When disabled, the command stderr pipe is inherited by the daemon/client
     "TAB_BIN", // path to the initiating tab binary (where the command was launched).  used by integration tests to launch tab within a shell
+    "TAB_NOTIFY_COMMAND", // a shell command, executed by the daemon when a background tab rings the bell or goes silent
+    "TAB_SILENCE_SECS", // the number of seconds without output, before a background tab is considered silent
+    "TAB_IDLE_TIMEOUT", // the default idle timeout of tabs, e.g. `2h`.  idle tabs are closed, or marked stale
+    "TAB_IDLE_ACTION",  // the default action for idle tabs, `close` or `stale`
+    "TAB_LOG_FORMAT",   // the format of daemon & pty log files, `text` or `json`
+    "TAB_LOG_MAX_SIZE", // the size in bytes at which log files are rotated
+    "TAB_LOG_FILES",    // the number of rotated log files to retain
+    "TAB_PTY_POOL", // the number of idle pty processes which the daemon keeps ready for new tabs
 ];
 
 /// Forwards the environment variables required by tab from the current process, to the child
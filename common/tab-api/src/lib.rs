@@ -2,11 +2,14 @@
 //!
 //! All inter-process communication is described in this crate.
 
+pub mod ansi;
+pub mod asciicast;
 pub mod chunk;
 pub mod client;
 pub mod config;
 pub mod env;
 pub mod launch;
 pub mod log;
+pub mod log_file;
 pub mod pty;
 pub mod tab;
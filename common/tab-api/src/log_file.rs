@@ -0,0 +1,439 @@
+//! Log files for the daemon and pty processes.
+//!
+//! Log files are appended to, and rotated once they exceed `TAB_LOG_MAX_SIZE` bytes (default 10 MiB).
+//! `TAB_LOG_FILES` rotated files are retained (default 3), as `daemon.log.1`, `daemon.log.2`, etc.
+//!
+//! If `TAB_LOG_FORMAT` is `json`, each record is written as a JSON line,
+//! which includes the connection name and `TabId` of the current log context.
+//! Daemon connections run their tasks within a `LogScope`, so all of their records carry the context.
+
+use crate::tab::TabId;
+use lifeline::impl_storage_clone;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use simplelog::{Config, SharedLogger, WriteLogger};
+use std::{
+    cell::RefCell,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+static DEFAULT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+static DEFAULT_FILES: usize = 3;
+
+/// The format of log file records, configured with `TAB_LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// The same format as the terminal logger, e.g. `12:01:02.003 DAE [INFO] Daemon started.`
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        match std::env::var("TAB_LOG_FORMAT") {
+            Ok(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+/// A file which is appended to, and rotated when it exceeds the size limit
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    /// Opens the log file, with the limits configured by `TAB_LOG_MAX_SIZE` and `TAB_LOG_FILES`
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        let max_size = std::env::var("TAB_LOG_MAX_SIZE")
+            .ok()
+            .and_then(|size| size.parse().ok())
+            .unwrap_or(DEFAULT_MAX_SIZE);
+
+        let files = std::env::var("TAB_LOG_FILES")
+            .ok()
+            .and_then(|files| files.parse().ok())
+            .unwrap_or(DEFAULT_FILES);
+
+        Self::with_limits(path, max_size, files)
+    }
+
+    /// Opens the log file, which is rotated when it exceeds `max_size` bytes.  `files` rotated files are retained.
+    pub fn with_limits(path: PathBuf, max_size: u64, files: usize) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_size,
+            files,
+            file,
+            size,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.files == 0 {
+            self.file = File::create(&self.path)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        std::fs::remove_file(rotated_path(&self.path, self.files)).ok();
+        for index in (1..self.files).rev() {
+            std::fs::rename(
+                rotated_path(&self.path, index),
+                rotated_path(&self.path, index + 1),
+            )
+            .ok();
+        }
+
+        std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// The path of a rotated log file, e.g. `daemon.log.1`
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", index));
+    PathBuf::from(rotated)
+}
+
+/// Creates a logger which writes to the rotating log file, in the format configured by `TAB_LOG_FORMAT`.
+/// The process name (e.g. `daemon`) is included in JSON records.
+pub fn file_logger(
+    level: LevelFilter,
+    config: Config,
+    process: &'static str,
+    path: PathBuf,
+) -> io::Result<Box<dyn SharedLogger>> {
+    let file = RotatingFile::open(path)?;
+
+    let logger: Box<dyn SharedLogger> = match LogFormat::from_env() {
+        LogFormat::Text => WriteLogger::new(level, config, file),
+        LogFormat::Json => Box::new(JsonLogger {
+            level,
+            process,
+            file: Mutex::new(file),
+        }),
+    };
+
+    Ok(logger)
+}
+
+/// The connection and tab which log records relate to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogContext {
+    pub connection: Option<String>,
+    pub tab: Option<TabId>,
+}
+
+static CONTEXT: Mutex<Option<LogContext>> = Mutex::new(None);
+
+thread_local! {
+    static SCOPED_CONTEXT: RefCell<Option<LogContext>> = const { RefCell::new(None) };
+}
+
+/// Sets the context of all records logged by the process.  Used by the pty, which serves a single tab.
+pub fn set_context(context: LogContext) {
+    if let Ok(mut global) = CONTEXT.lock() {
+        *global = Some(context);
+    }
+}
+
+/// Runs the function with the log context, which overrides the process context.
+/// Used to log records about a specific connection or tab, from a process which serves many of them.
+pub fn with_context<T>(context: LogContext, f: impl FnOnce() -> T) -> T {
+    let previous = SCOPED_CONTEXT.with(|scoped| scoped.replace(Some(context)));
+    let result = f();
+    SCOPED_CONTEXT.with(|scoped| scoped.replace(previous));
+    result
+}
+
+/// The log context of a connection, which is shared by the tasks that serve it.
+/// The context can be updated, e.g. when a pty connection is assigned a tab.
+#[derive(Debug, Clone, Default)]
+pub struct LogScope {
+    context: Arc<Mutex<LogContext>>,
+}
+
+impl_storage_clone!(LogScope);
+
+impl LogScope {
+    pub fn new(context: LogContext) -> Self {
+        Self {
+            context: Arc::new(Mutex::new(context)),
+        }
+    }
+
+    /// Sets the tab of the context, for the records which are logged after the call
+    pub fn set_tab(&self, tab: TabId) {
+        if let Ok(mut context) = self.context.lock() {
+            context.tab = Some(tab);
+        }
+    }
+
+    pub fn context(&self) -> LogContext {
+        self.context
+            .lock()
+            .map(|context| context.clone())
+            .unwrap_or_default()
+    }
+
+    /// Wraps the future, so the records it logs carry the context of the scope
+    pub fn scope<F>(&self, future: F) -> Scoped<F::Output>
+    where
+        F: Future + Send + 'static,
+    {
+        Scoped {
+            scope: self.clone(),
+            future: Box::pin(future),
+        }
+    }
+}
+
+/// A future which runs within a log scope.  Created by `LogScope::scope`.
+pub struct Scoped<T> {
+    scope: LogScope,
+    future: Pin<Box<dyn Future<Output = T> + Send>>,
+}
+
+impl<T> Future for Scoped<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        // the task can move between threads, so the context is set each time it is polled
+        let context = self.scope.context();
+        with_context(context, || self.future.as_mut().poll(cx))
+    }
+}
+
+fn current_context() -> LogContext {
+    if let Some(context) = SCOPED_CONTEXT.with(|scoped| scoped.borrow().clone()) {
+        return context;
+    }
+
+    CONTEXT
+        .lock()
+        .ok()
+        .and_then(|context| context.clone())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    time: String,
+    level: String,
+    process: &'a str,
+    pid: u32,
+    target: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    connection: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tab: Option<u16>,
+    message: String,
+}
+
+/// Formats the record as a JSON line, including the trailing newline
+fn json_line(record: &Record, process: &str, context: &LogContext, time: String) -> String {
+    let line = JsonRecord {
+        time,
+        level: record.level().to_string(),
+        process,
+        pid: std::process::id(),
+        target: record.target(),
+        connection: context.connection.as_deref(),
+        tab: context.tab.map(|id| id.0),
+        message: record.args().to_string(),
+    };
+
+    let mut line = serde_json::to_string(&line).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// Writes records as JSON lines, with the log context
+struct JsonLogger {
+    level: LevelFilter,
+    process: &'static str,
+    file: Mutex<RotatingFile>,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let time = chrono::Utc::now().to_rfc3339();
+        let line = json_line(record, self.process, &current_context(), time);
+        if let Ok(mut file) = self.file.lock() {
+            file.write_all(line.as_bytes()).ok();
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            file.flush().ok();
+        }
+    }
+}
+
+impl SharedLogger for JsonLogger {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        current_context, json_line, rotated_path, with_context, LogContext, LogScope, RotatingFile,
+    };
+    use crate::tab::TabId;
+    use log::{Level, Record};
+    use std::io::Write;
+
+    #[test]
+    fn rotation() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("tab-log-test-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        let path = dir.join("test.log");
+
+        let mut file = RotatingFile::with_limits(path.clone(), 10, 2)?;
+        file.write_all(b"first\n")?;
+        file.write_all(b"second\n")?;
+        file.write_all(b"third\n")?;
+        file.write_all(b"fourth\n")?;
+        drop(file);
+
+        // reopening appends to the existing file
+        let mut file = RotatingFile::with_limits(path.clone(), 100, 2)?;
+        file.write_all(b"fifth\n")?;
+        drop(file);
+
+        let read = |path| std::fs::read_to_string(path).unwrap_or_default();
+        assert_eq!("fourth\nfifth\n", read(path.clone()));
+        assert_eq!("third\n", read(rotated_path(&path, 1)));
+        assert_eq!("second\n", read(rotated_path(&path, 2)));
+        assert!(!rotated_path(&path, 3).exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn json_record() {
+        let context = LogContext {
+            connection: Some("connection_2".to_string()),
+            tab: Some(TabId(4)),
+        };
+
+        let line = json_line(
+            &Record::builder()
+                .args(format_args!("tab \"started\""))
+                .level(Level::Info)
+                .target("tab_daemon::test")
+                .build(),
+            "daemon",
+            &context,
+            "2020-01-01T00:00:00+00:00".to_string(),
+        );
+
+        let expected = format!(
+            "{{\"time\":\"2020-01-01T00:00:00+00:00\",\"level\":\"INFO\",\"process\":\"daemon\",\"pid\":{},\"target\":\"tab_daemon::test\",\"connection\":\"connection_2\",\"tab\":4,\"message\":\"tab \\\"started\\\"\"}}\n",
+            std::process::id()
+        );
+        assert_eq!(expected, line);
+    }
+
+    #[test]
+    fn scoped_context() {
+        let context = LogContext {
+            connection: None,
+            tab: Some(TabId(1)),
+        };
+
+        let scoped = with_context(context.clone(), current_context);
+        assert_eq!(context, scoped);
+        assert_ne!(Some(TabId(1)), current_context().tab);
+    }
+
+    #[tokio::test]
+    async fn log_scope() {
+        let scope = LogScope::new(LogContext {
+            connection: Some("connection_1".to_string()),
+            tab: None,
+        });
+
+        let (tx_before, rx_before) = tokio::sync::oneshot::channel();
+        let (tx_assigned, rx_assigned) = tokio::sync::oneshot::channel::<()>();
+        let task = tokio::spawn(scope.scope(async move {
+            tx_before.send(current_context()).ok();
+            rx_assigned.await.ok();
+            current_context()
+        }));
+
+        // the tab is assigned while the task is running
+        let before = rx_before.await.unwrap();
+        scope.set_tab(TabId(2));
+        tx_assigned.send(()).ok();
+
+        let after = task.await.unwrap();
+        assert_eq!(Some("connection_1".to_string()), before.connection);
+        assert_eq!(None, before.tab);
+        assert_eq!(Some("connection_1".to_string()), after.connection);
+        assert_eq!(Some(TabId(2)), after.tab);
+        assert_eq!(LogContext::default(), current_context());
+    }
+}
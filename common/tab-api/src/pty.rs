@@ -2,7 +2,7 @@
 
 use crate::{
     chunk::{InputChunk, OutputChunk},
-    tab::TabMetadata,
+    tab::{CloseOptions, TabMetadata, TabProcess, TabSignal, TabStats},
 };
 use serde::{Deserialize, Serialize};
 
@@ -10,7 +10,21 @@ use serde::{Deserialize, Serialize};
 pub enum PtyWebsocketResponse {
     Started(TabMetadata),
     Output(OutputChunk),
+    /// The foreground process or working directory of the shell has changed
+    Process(TabProcess),
+    /// The resource usage of the processes in the tab has changed
+    Stats(TabStats),
+    /// The signal has been sent to the shell and the foreground process group, as the tab closes
+    Closing(TabSignal),
     Stopped,
+    /// Re-registers a running tab with a restarted daemon.
+    /// The daemon acknowledges with an `Init` message for the same tab.
+    Resume(TabMetadata),
+    /// The process id of the pty, sent when the pty connects to the daemon
+    Pid(u32),
+    /// Output which was sent to a previous daemon, and is replayed into the scrollback when the tab resumes.
+    /// Unlike `Output`, it is not appended to the tab log again.
+    Replay(OutputChunk),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -18,5 +32,14 @@ pub enum PtyWebsocketRequest {
     Init(TabMetadata),
     Input(InputChunk),
     Resize((u16, u16)),
+    /// Delivers the signal to the foreground process group of the pty
+    Signal(TabSignal),
+    /// Renames the tab, and migrates the shell history
+    Rename(String),
+    /// Closes the tab, escalating from SIGHUP to SIGTERM to SIGKILL
+    Close(CloseOptions),
     Terminate,
+    /// The tab output is being appended to the log file.  The path is retained in the tab metadata,
+    /// so the log continues when the tab resumes with a new daemon.
+    Log(String),
 }
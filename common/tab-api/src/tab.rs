@@ -28,6 +28,55 @@ impl Display for TabId {
     }
 }
 
+/// Selects a group of tabs by name, by name prefix (`proj/*`), or by tag (`@backend`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TabSelector {
+    /// The tab with the normalized name
+    Name(String),
+    /// Tabs with names that begin with the prefix
+    Prefix(String),
+    /// Tabs with the tag
+    Tag(String),
+}
+
+impl TabSelector {
+    pub fn parse(selector: &str) -> TabSelector {
+        let selector = selector.trim();
+
+        if let Some(tag) = selector.strip_prefix('@') {
+            TabSelector::Tag(tag.to_string())
+        } else if let Some(prefix) = selector.strip_suffix('*') {
+            TabSelector::Prefix(prefix.to_string())
+        } else {
+            TabSelector::Name(normalize_name(selector))
+        }
+    }
+
+    /// Whether the selector matches a tab with the name & tags
+    pub fn matches(&self, name: &str, tags: &[String]) -> bool {
+        match self {
+            TabSelector::Name(selected) => name == selected,
+            TabSelector::Prefix(prefix) => name.starts_with(prefix.as_str()),
+            TabSelector::Tag(tag) => tags.iter().any(|t| t == tag),
+        }
+    }
+
+    /// Whether the selector can match more than one tab
+    pub fn is_group(&self) -> bool {
+        !matches!(self, TabSelector::Name(_))
+    }
+}
+
+impl Display for TabSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TabSelector::Name(name) => f.write_str(name),
+            TabSelector::Prefix(prefix) => write!(f, "{}*", prefix),
+            TabSelector::Tag(tag) => write!(f, "@{}", tag),
+        }
+    }
+}
+
 /// Tracked information about a running tab.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct TabMetadata {
@@ -36,6 +85,22 @@ pub struct TabMetadata {
     pub dimensions: (u16, u16),
     pub shell: String,
     pub dir: String,
+    /// If provided, the tab output is appended to this log file, with ANSI escape sequences removed
+    pub log: Option<String>,
+    /// Shell commands which the daemon executes on tab lifecycle events.  Boxed, as they are rarely configured.
+    #[serde(default)]
+    pub hooks: Box<TabHooks>,
+    /// Tags which select the tab, with a `@tag` selector
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Closes, or marks stale, the tab when it is idle.  Boxed, as it is rarely configured.
+    #[serde(default)]
+    pub idle: Box<TabIdle>,
+    /// The foreground process of the tab, reported by the pty
+    pub process: Option<TabProcess>,
+    /// The resource usage of the processes in the tab, sampled by the pty.  Boxed, to keep the metadata small.
+    #[serde(default)]
+    pub stats: Option<Box<TabStats>>,
 }
 
 impl TabMetadata {
@@ -46,6 +111,240 @@ impl TabMetadata {
             dimensions: create.dimensions,
             shell: create.shell,
             dir: create.dir,
+            log: create.log,
+            hooks: create.hooks,
+            tags: create.tags,
+            idle: create.idle,
+            process: None,
+            stats: None,
+        }
+    }
+
+    /// The name of the foreground process, if it is not the shell of the tab
+    pub fn foreground_process(&self) -> Option<&str> {
+        let process = self.process.as_ref()?;
+        let shell = self.shell.split_whitespace().next().unwrap_or("");
+        let shell = shell.rsplit('/').next().unwrap_or(shell);
+
+        if process.name == shell {
+            None
+        } else {
+            Some(process.name.as_str())
+        }
+    }
+}
+
+/// The foreground process of a running tab.
+/// The working directory is live, and changes as the user navigates within the shell.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TabProcess {
+    /// The command name of the foreground process (e.g. `bash`, or `cargo`)
+    pub name: String,
+    /// The current working directory of the foreground process
+    pub cwd: String,
+}
+
+/// The resource usage of the processes in a running tab, including the shell and its descendants.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TabStats {
+    /// The CPU usage of the processes, as a percentage of a single core
+    pub cpu: u32,
+    /// The resident memory of the processes, in kibibytes
+    pub memory: u64,
+    /// The number of processes
+    pub processes: u32,
+}
+
+impl TabStats {
+    /// Returns true if the usage has changed enough to be reported:
+    /// CPU by 5 percentage points, memory by 5%, or the number of processes.
+    pub fn is_significant_change(&self, last: &TabStats) -> bool {
+        let cpu = (self.cpu as i64 - last.cpu as i64).abs() >= 5;
+        let memory = self.memory.max(last.memory) - self.memory.min(last.memory);
+        let memory = memory * 20 >= last.memory && memory > 0;
+
+        cpu || memory || self.processes != last.processes
+    }
+}
+
+/// A signal which can be delivered to the foreground process group of a tab.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TabSignal {
+    /// SIGHUP, which reloads many servers
+    Hangup,
+    /// SIGINT, which is equivalent to Ctrl-C
+    Interrupt,
+    /// SIGTERM, which requests that the process exits
+    Terminate,
+    /// SIGKILL, which cannot be caught or ignored
+    Kill,
+}
+
+impl TabSignal {
+    /// Parses the signal name, with or without the `SIG` prefix (e.g. `INT`, or `SIGINT`)
+    pub fn parse(name: &str) -> Option<TabSignal> {
+        let name = name.trim().to_ascii_uppercase();
+        let name = name.strip_prefix("SIG").unwrap_or(name.as_str());
+
+        match name {
+            "HUP" => Some(TabSignal::Hangup),
+            "INT" => Some(TabSignal::Interrupt),
+            "TERM" => Some(TabSignal::Terminate),
+            "KILL" => Some(TabSignal::Kill),
+            _ => None,
+        }
+    }
+}
+
+impl Display for TabSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TabSignal::Hangup => f.write_str("SIGHUP"),
+            TabSignal::Interrupt => f.write_str("SIGINT"),
+            TabSignal::Terminate => f.write_str("SIGTERM"),
+            TabSignal::Kill => f.write_str("SIGKILL"),
+        }
+    }
+}
+
+/// Options for closing a tab.
+/// Processes in the tab receive SIGHUP, then SIGTERM after the grace period, then SIGKILL after another grace period.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CloseOptions {
+    /// Skips the grace period, and sends SIGKILL immediately
+    pub force: bool,
+    /// The number of seconds to wait for the processes to exit, before escalating to the next signal
+    pub grace_period: u64,
+}
+
+impl Default for CloseOptions {
+    fn default() -> Self {
+        Self {
+            force: false,
+            grace_period: 5,
+        }
+    }
+}
+
+/// Activity on a running tab, which is tracked by the daemon.
+/// Unread output, bells, and silence are reset when a client attaches to the tab.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TabActivity {
+    /// The unix timestamp of the most recent output, in seconds
+    pub last_output: Option<u64>,
+    /// The tab has produced output, while no client was attached
+    pub unread: bool,
+    /// The tab has rung the terminal bell, while no client was attached
+    pub bell: bool,
+    /// The tab has unread output, and has been silent since
+    pub silent: bool,
+    /// The tab has been idle for longer than its idle timeout, and can be closed with `tab --prune`
+    #[serde(default)]
+    pub stale: bool,
+}
+
+impl TabActivity {
+    /// Whether the unread, bell, silent, or stale flags differ.  Ignores the last output time.
+    pub fn flags_differ(&self, other: &TabActivity) -> bool {
+        self.unread != other.unread
+            || self.bell != other.bell
+            || self.silent != other.silent
+            || self.stale != other.stale
+    }
+}
+
+/// The action taken by the daemon, when a tab has been idle for the timeout.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Closes the tab
+    #[default]
+    Close,
+    /// Marks the tab as stale in `tab -l`, until it is closed with `tab --prune`
+    Stale,
+}
+
+impl IdleAction {
+    pub fn parse(action: &str) -> Option<IdleAction> {
+        match action.trim().to_ascii_lowercase().as_str() {
+            "close" => Some(IdleAction::Close),
+            "stale" => Some(IdleAction::Stale),
+            _ => None,
+        }
+    }
+}
+
+/// The idle policy of a tab.
+/// A tab is idle when it has no input, output, or attached clients, and the shell is the foreground process.
+/// Unset fields fall back to `TAB_IDLE_TIMEOUT` and `TAB_IDLE_ACTION`, in the daemon environment.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TabIdle {
+    /// The number of idle seconds before the action is taken.  Zero disables the policy.
+    pub timeout: Option<u64>,
+    pub action: Option<IdleAction>,
+}
+
+/// Parses a number of seconds, with an optional `s`, `m`, `h`, or `d` unit (e.g. `90`, `30m`, or `2h`)
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    let duration = duration.trim();
+    let (number, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => duration.split_at(index),
+        None => (duration, "s"),
+    };
+
+    let multiplier = match unit.trim() {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+/// A tab lifecycle event, which can trigger a hook command.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TabHook {
+    /// The tab was created by the daemon
+    Create,
+    /// A client attached to the tab
+    Attach,
+    /// A client detached from the tab
+    Detach,
+    /// The tab was closed, and its processes have stopped
+    Close,
+}
+
+impl TabHook {
+    /// The name of the event, provided to the hook command as `TAB_EVENT`
+    pub fn event(&self) -> &'static str {
+        match self {
+            TabHook::Create => "create",
+            TabHook::Attach => "attach",
+            TabHook::Detach => "detach",
+            TabHook::Close => "close",
+        }
+    }
+}
+
+/// Shell commands which are executed by the daemon on tab lifecycle events.
+/// The commands run in the tab directory, with `TAB`, `TAB_ID`, `TAB_DIR` and `TAB_EVENT` in the environment.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TabHooks {
+    pub on_create: Option<String>,
+    pub on_attach: Option<String>,
+    pub on_detach: Option<String>,
+    pub on_close: Option<String>,
+}
+
+impl TabHooks {
+    /// The command configured for the event, if any
+    pub fn command(&self, hook: TabHook) -> Option<&String> {
+        match hook {
+            TabHook::Create => self.on_create.as_ref(),
+            TabHook::Attach => self.on_attach.as_ref(),
+            TabHook::Detach => self.on_detach.as_ref(),
+            TabHook::Close => self.on_close.as_ref(),
         }
     }
 }
@@ -57,4 +356,102 @@ pub struct CreateTabMetadata {
     pub dimensions: (u16, u16),
     pub shell: String,
     pub dir: String,
+    /// If provided, the tab session is recorded to this file, in asciicast v2 format
+    pub record: Option<String>,
+    /// If provided, the tab output is appended to this log file, with ANSI escape sequences removed
+    pub log: Option<String>,
+    /// Shell commands which the daemon executes on tab lifecycle events.  Boxed, as they are rarely configured.
+    #[serde(default)]
+    pub hooks: Box<TabHooks>,
+    /// Tags which select the tab, with a `@tag` selector
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Closes, or marks stale, the tab when it is idle.  Boxed, as it is rarely configured.
+    #[serde(default)]
+    pub idle: Box<TabIdle>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_duration, TabSelector, TabSignal, TabStats};
+
+    #[test]
+    fn parse_signal() {
+        assert_eq!(Some(TabSignal::Interrupt), TabSignal::parse("INT"));
+        assert_eq!(Some(TabSignal::Interrupt), TabSignal::parse("sigint"));
+        assert_eq!(Some(TabSignal::Hangup), TabSignal::parse("HUP"));
+        assert_eq!(Some(TabSignal::Terminate), TabSignal::parse("SIGTERM"));
+        assert_eq!(Some(TabSignal::Kill), TabSignal::parse("KILL"));
+        assert_eq!(None, TabSignal::parse("USR1"));
+    }
+
+    #[test]
+    fn parse_selector() {
+        assert_eq!(
+            TabSelector::Name("proj/".into()),
+            TabSelector::parse("proj")
+        );
+        assert_eq!(
+            TabSelector::Prefix("proj/".into()),
+            TabSelector::parse("proj/*")
+        );
+        assert_eq!(
+            TabSelector::Tag("backend".into()),
+            TabSelector::parse("@backend")
+        );
+        assert_eq!("proj/*", TabSelector::parse("proj/*").to_string());
+        assert_eq!("@backend", TabSelector::parse("@backend").to_string());
+    }
+
+    #[test]
+    fn selector_matches() {
+        let tags = vec!["backend".to_string()];
+
+        assert!(TabSelector::parse("proj/run").matches("proj/run/", &[]));
+        assert!(!TabSelector::parse("proj").matches("proj/run/", &[]));
+
+        let prefix = TabSelector::parse("proj/*");
+        assert!(prefix.matches("proj/", &[]));
+        assert!(prefix.matches("proj/run/", &[]));
+        assert!(!prefix.matches("project/", &[]));
+
+        let tag = TabSelector::parse("@backend");
+        assert!(tag.matches("api/", &tags));
+        assert!(!tag.matches("web/", &[]));
+    }
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(Some(90), parse_duration("90"));
+        assert_eq!(Some(90), parse_duration("90s"));
+        assert_eq!(Some(30 * 60), parse_duration("30m"));
+        assert_eq!(Some(2 * 60 * 60), parse_duration(" 2h "));
+        assert_eq!(Some(24 * 60 * 60), parse_duration("1d"));
+        assert_eq!(None, parse_duration("2 weeks"));
+        assert_eq!(None, parse_duration("h"));
+    }
+
+    #[test]
+    fn significant_stats() {
+        let last = TabStats {
+            cpu: 10,
+            memory: 1000,
+            processes: 2,
+        };
+
+        let stats = |cpu, memory, processes| TabStats {
+            cpu,
+            memory,
+            processes,
+        };
+
+        assert!(!stats(10, 1000, 2).is_significant_change(&last));
+        assert!(!stats(14, 1049, 2).is_significant_change(&last));
+        assert!(!stats(6, 951, 2).is_significant_change(&last));
+        assert!(stats(15, 1000, 2).is_significant_change(&last));
+        assert!(stats(5, 1000, 2).is_significant_change(&last));
+        assert!(stats(10, 1050, 2).is_significant_change(&last));
+        assert!(stats(10, 950, 2).is_significant_change(&last));
+        assert!(stats(10, 1000, 3).is_significant_change(&last));
+    }
 }
@@ -1,27 +1,47 @@
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
+    grep, logs,
     message::{
-        client::TabTerminated,
+        client::{TabClosing, TabScrollback, TabTerminated},
         main::{MainRecv, MainShutdown},
         tabs::{CreateTabRequest, TabShutdown, TabsRecv},
     },
     prelude::*,
+    recent,
     state::{
         tab::{SelectTab, TabState},
         tabs::TabsState,
         terminal::TerminalSizeState,
-        workspace::{WorkspaceState, WorkspaceTab},
+        workspace::{WorkspaceLayout, WorkspaceState, WorkspaceTab},
     },
 };
 use anyhow::Context;
+use regex::Regex;
 
-use tab_api::tab::{normalize_name, TabId, TabMetadata};
+use tab_api::{
+    chunk::InputChunk,
+    client::DaemonStatus,
+    config::pty_log,
+    tab::{normalize_name, CloseOptions, TabActivity, TabId, TabMetadata, TabSelector},
+};
 use tokio::{
+    select,
     sync::{broadcast, mpsc, watch},
-    time,
+    task, time,
 };
 
+/// Added to the grace periods, to allow time for the daemon to remove the closed tabs
+static CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+static TOP_INTERVAL: Duration = Duration::from_millis(500);
+/// The time `tab --up` waits for each layout tab to start
+static CREATE_TIMEOUT: Duration = Duration::from_secs(10);
+
 lifeline_bus!(pub struct TabBus);
 
 impl Message<TabBus> for Request {
@@ -44,6 +64,18 @@ impl Message<TabBus> for TabTerminated {
     type Channel = mpsc::Sender<Self>;
 }
 
+impl Message<TabBus> for TabClosing {
+    type Channel = broadcast::Sender<Self>;
+}
+
+impl Message<TabBus> for TabScrollback {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<TabBus> for DaemonStatus {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Message<TabBus> for TerminalSizeState {
     type Channel = watch::Sender<Self>;
 }
@@ -116,7 +148,10 @@ impl CarryFrom<MainBus> for TabBus {
             let mut tx_tabs = self.tx::<TabsRecv>()?;
             let mut tx_tab_metadata = self.tx::<TabMetadata>()?;
             let mut tx_tab_terminated = self.tx::<TabTerminated>()?;
+            let mut tx_tab_scrollback = self.tx::<TabScrollback>()?;
+            let mut tx_tab_closing = self.tx::<TabClosing>()?;
             let mut tx_select_tab = self.tx::<SelectTab>()?;
+            let mut tx_status = self.tx::<DaemonStatus>()?;
 
             let mut tx_shutdown = from.tx::<MainShutdown>()?;
 
@@ -125,7 +160,7 @@ impl CarryFrom<MainBus> for TabBus {
                     match response {
                         Response::Init(init) => {
                             tx_tabs
-                                .send(TabsRecv::Init(init.tabs.clone()))
+                                .send(TabsRecv::Init(init.tabs.clone(), init.activity.clone()))
                                 .await
                                 .context("tx TabsRecv::Init")?;
                         }
@@ -140,6 +175,9 @@ impl CarryFrom<MainBus> for TabBus {
                                 .await
                                 .context("tx TabsRecv::Update")?;
                         }
+                        Response::TabActivity(id, activity) => {
+                            tx_tabs.send(TabsRecv::Activity(id, activity)).await?;
+                        }
                         Response::TabTerminated(id) => {
                             tx_tabs.send(TabsRecv::Terminated(id)).await?;
 
@@ -157,10 +195,19 @@ impl CarryFrom<MainBus> for TabBus {
                                     .context("tx MainShutdown")?;
                             }
                         }
+                        Response::TabClosing(id, signal) => {
+                            tx_tab_closing.send(TabClosing(id, signal)).await.ok();
+                        }
+                        Response::Scrollback(id, data) => {
+                            tx_tab_scrollback.send(TabScrollback(id, data)).await?;
+                        }
                         Response::Retask(to_id) => {
                             let state = SelectTab::Tab(to_id);
                             tx_select_tab.send(state).await?;
                         }
+                        Response::Status(status) => {
+                            tx_status.send(status).await?;
+                        }
                         _ => {}
                     }
                 }
@@ -188,6 +235,10 @@ impl CarryFrom<MainBus> for TabBus {
             let mut rx_tabs_state = self.rx::<TabsState>()?.into_inner();
             let mut rx_workspace = self.rx::<WorkspaceState>()?.into_inner();
             let mut rx_main = from.rx::<MainRecv>()?;
+            let mut rx_scrollback = self.rx::<TabScrollback>()?;
+            let mut rx_closing = self.rx::<TabClosing>()?;
+            let mut rx_status = self.rx::<DaemonStatus>()?;
+            let mut tx_main = from.tx::<MainRecv>()?;
             let mut tx_shutdown = from.tx::<MainShutdown>()?;
             let mut tx_create = self.tx::<CreateTabRequest>()?;
             let mut tx_select = self.tx::<SelectTab>()?;
@@ -219,11 +270,14 @@ impl CarryFrom<MainBus> for TabBus {
 
                                     debug!("retask - waiting for creation on tab {}", id);
                                     let metadata =
-                                        Self::await_created(name, &mut rx_tabs_state).await;
+                                        Self::await_created(name, &mut rx_tabs_state).await?;
 
                                     debug!("retask - sending retask to tab {}", id);
                                     let request = Request::Retask(id, metadata.id);
                                     tx_websocket.send(request).await?;
+                                    let name = metadata.name.clone();
+                                    task::spawn_blocking(move || recent::push(name.as_str()))
+                                        .await?;
 
                                     // if we quit too early, the carrier is cancelled and our message doesn't get through.
                                     // this sleep is not visible to the user, as the outer terminal session will emit new stdout
@@ -243,37 +297,421 @@ impl CarryFrom<MainBus> for TabBus {
                                 .await
                                 .context("send TabStateSelect")?;
                         }
-                        MainRecv::CloseTabs(tabs) => {
+                        MainRecv::Signal(name, signal) => {
+                            let name = normalize_name(name.as_str());
                             let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let tab = running_tabs
+                                .as_ref()
+                                .and_then(|tabs| tabs.find_name(name.as_str()));
+
+                            if let Some(tab) = tab {
+                                eprintln!("Sending {} to tab: {}", signal, name);
+                                tx_websocket.send(Request::Signal(tab.id, signal)).await?;
+                            } else {
+                                eprintln!("Tab not running: {}", name);
+                            }
 
-                            for tab in tabs {
-                                let name = normalize_name(tab.as_str());
+                            time::delay_for(Duration::from_millis(5)).await;
 
-                                if running_tabs.is_some()
-                                    && running_tabs
-                                        .as_ref()
-                                        .unwrap()
-                                        .find_name(name.as_str())
-                                        .is_some()
-                                {
-                                    eprintln!("Closing tab: {}", name);
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::SendTabs(selector, text) => {
+                            let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let mut selected: Vec<&TabMetadata> = running_tabs
+                                .iter()
+                                .flat_map(|tabs| tabs.tabs.values())
+                                .filter(|tab| selector.matches(tab.name.as_str(), &tab.tags))
+                                .collect();
+                            selected.sort_by(|a, b| a.name.cmp(&b.name));
+
+                            if selected.is_empty() {
+                                eprintln!("Tab not running: {}", selector);
+                            }
+
+                            for tab in selected {
+                                eprintln!("Sending to tab: {}", tab.name);
+                                let data = format!("{}\n", text).into_bytes();
+                                let request = Request::Input(tab.id, InputChunk { data });
+                                tx_websocket.send(request).await?;
+                            }
+
+                            time::delay_for(Duration::from_millis(5)).await;
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::CloseTabs(tabs, options) => {
+                            let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let mut closing = HashMap::new();
+
+                            for selector in tabs {
+                                let mut running: Vec<&TabMetadata> = running_tabs
+                                    .iter()
+                                    .flat_map(|tabs| tabs.tabs.values())
+                                    .filter(|tab| selector.matches(tab.name.as_str(), &tab.tags))
+                                    .collect();
+                                running.sort_by(|a, b| a.name.cmp(&b.name));
+
+                                for running in running.iter() {
+                                    let name = running.name.as_str();
+                                    if let Some(process) = running.foreground_process() {
+                                        eprintln!(
+                                            "Warning: tab {} is running {}, which may lose unsaved work",
+                                            name, process
+                                        );
+                                    }
+
+                                    if options.force {
+                                        eprintln!("Killing tab: {}", name);
+                                    } else {
+                                        eprintln!("Closing tab: {}", name);
+                                    }
+
+                                    closing.insert(running.id, name.to_string());
+                                }
+
+                                if running.is_empty() {
+                                    if selector.is_group() {
+                                        eprintln!("No running tabs match: {}", selector);
+                                    } else {
+                                        eprintln!("Tab not running: {}", selector);
+                                    }
+                                }
+
+                                if selector.is_group() {
+                                    // the daemon resolves the selector, and closes the matching tabs together
+                                    let selector = selector.to_string();
+                                    tx_websocket
+                                        .send(Request::CloseNamedTab(selector, options))
+                                        .await?;
                                 } else {
-                                    eprintln!("Tab not running: {}", name);
+                                    // names are closed by id, as the daemon would parse a name like `@name` as a selector
+                                    for running in running {
+                                        tx_websocket
+                                            .send(Request::CloseTab(running.id, options))
+                                            .await?;
+                                    }
                                 }
+                            }
 
-                                tx_websocket.send(Request::CloseNamedTab(name)).await?;
+                            if !options.force {
+                                Self::await_closed(
+                                    closing,
+                                    options,
+                                    &mut rx_tabs_state,
+                                    &mut rx_closing,
+                                )
+                                .await;
                             }
 
                             time::delay_for(Duration::from_millis(5)).await;
 
                             tx_shutdown.send(MainShutdown {}).await?;
                         }
-                        MainRecv::ListTabs => {
+                        MainRecv::Up(layout, select) => {
                             let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
-                            let workspace_tabs = Self::await_workspace(&mut rx_workspace).await;
-                            let tabs = Self::merge_tabs(running_tabs, workspace_tabs);
+                            let layouts = Self::await_layouts(&mut rx_workspace).await;
+                            let layout = match Self::find_layout(layouts, layout.as_str()) {
+                                Some(layout) => layout,
+                                None => {
+                                    tx_shutdown.send(MainShutdown {}).await?;
+                                    continue;
+                                }
+                            };
+
+                            // request all the tabs, so they start in parallel
+                            let mut created = Vec::new();
+                            for tab in layout.tabs {
+                                let running = running_tabs
+                                    .as_ref()
+                                    .and_then(|tabs| tabs.find_name(tab.tab.as_str()));
+
+                                if running.is_some() {
+                                    eprintln!("Tab already running: {}", tab.tab);
+                                    continue;
+                                }
+
+                                tx_create
+                                    .send(CreateTabRequest::Named(tab.tab.clone()))
+                                    .await?;
+                                created.push(tab);
+                            }
+
+                            let mut failed = Vec::new();
+                            for tab in created {
+                                let metadata = time::timeout(
+                                    CREATE_TIMEOUT,
+                                    Self::await_created(tab.tab.clone(), &mut rx_tabs_state),
+                                )
+                                .await;
+
+                                let metadata = match metadata {
+                                    Ok(metadata) => metadata?,
+                                    Err(_) => {
+                                        failed.push(tab.tab);
+                                        continue;
+                                    }
+                                };
+
+                                eprintln!("Started tab: {}", tab.tab);
+
+                                // the shell reads the command when it starts
+                                if let Some(command) = tab.command {
+                                    let data = (command + "\n").into_bytes();
+                                    let request = Request::Input(metadata.id, InputChunk { data });
+                                    tx_websocket.send(request).await?;
+                                }
+                            }
+
+                            if !failed.is_empty() {
+                                eprintln!("Tabs failed to start: {}", failed.join(", "));
+                            } else if let Some(select) = select {
+                                tx_main.send(MainRecv::SelectTab(select)).await?;
+                                continue;
+                            }
+
+                            time::delay_for(Duration::from_millis(5)).await;
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::Down(layout, options) => {
+                            let layouts = Self::await_layouts(&mut rx_workspace).await;
+                            match Self::find_layout(layouts, layout.as_str()) {
+                                Some(layout) => {
+                                    // close the tabs in the reverse of the startup order
+                                    let tabs = layout.tabs.into_iter().rev();
+                                    let tabs = tabs.map(|tab| TabSelector::Name(tab.tab)).collect();
+                                    tx_main.send(MainRecv::CloseTabs(tabs, options)).await?;
+                                }
+                                None => {
+                                    tx_shutdown.send(MainShutdown {}).await?;
+                                }
+                            }
+                        }
+                        MainRecv::Prune(options) => {
+                            let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let mut stale: Vec<TabMetadata> = running_tabs
+                                .iter()
+                                .flat_map(|tabs| {
+                                    tabs.tabs.values().filter(move |tab| {
+                                        let activity = tabs.activity.get(&tab.id);
+                                        let stale = activity.map(|a| a.stale).unwrap_or(false);
+                                        stale && tab.foreground_process().is_none()
+                                    })
+                                })
+                                .cloned()
+                                .collect();
+                            stale.sort_by(|a, b| a.name.cmp(&b.name));
+
+                            if stale.is_empty() {
+                                eprintln!("No stale tabs.");
+                                tx_shutdown.send(MainShutdown {}).await?;
+                                continue;
+                            }
+
+                            let stale = stale
+                                .into_iter()
+                                .map(|tab| TabSelector::Name(tab.name))
+                                .collect();
+                            tx_main.send(MainRecv::CloseTabs(stale, options)).await?;
+                        }
+                        MainRecv::RenameTab(name, new_name) => {
+                            let name = normalize_name(name.as_str());
+                            let new_name = normalize_name(new_name.as_str());
+                            let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let find = |name: &str| {
+                                running_tabs
+                                    .as_ref()
+                                    .and_then(|tabs| tabs.find_name(name))
+                                    .map(|tab| tab.id)
+                            };
+
+                            match (find(name.as_str()), find(new_name.as_str())) {
+                                (None, _) => eprintln!("Tab not running: {}", name),
+                                (Some(_), Some(_)) => {
+                                    eprintln!("Tab already running: {}", new_name)
+                                }
+                                (Some(id), None) => {
+                                    eprintln!("Renaming tab {} to {}", name, new_name);
+                                    let (from, to) = (name.clone(), new_name.clone());
+                                    task::spawn_blocking(move || {
+                                        recent::rename(from.as_str(), to.as_str())
+                                    })
+                                    .await?;
+                                    tx_websocket.send(Request::RenameTab(id, new_name)).await?;
+                                }
+                            }
+
+                            time::delay_for(Duration::from_millis(5)).await;
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::RecordTab(name, path) => {
+                            Self::write_to_file(
+                                name.as_str(),
+                                path.as_path(),
+                                "Recording",
+                                Request::RecordTab,
+                                &mut rx_tabs_state,
+                                &mut tx_create,
+                                &mut tx_websocket,
+                            )
+                            .await?;
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::LogTab(name, path) => {
+                            Self::write_to_file(
+                                name.as_str(),
+                                path.as_path(),
+                                "Logging",
+                                Request::LogTab,
+                                &mut rx_tabs_state,
+                                &mut tx_create,
+                                &mut tx_websocket,
+                            )
+                            .await?;
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::Grep(pattern, interactive) => {
+                            let pattern = match Regex::new(pattern.as_str()) {
+                                Ok(pattern) => pattern,
+                                Err(err) => {
+                                    eprintln!("Invalid pattern: {}", err);
+                                    tx_shutdown.send(MainShutdown {}).await?;
+                                    continue;
+                                }
+                            };
+
+                            let running_tabs = Self::await_initialized(&mut rx_tabs_state).await;
+                            let mut tabs: Vec<TabMetadata> = running_tabs
+                                .map(|state| state.tabs.into_values().collect())
+                                .unwrap_or_default();
+                            tabs.sort_by(|a, b| a.name.cmp(&b.name));
+
+                            let mut scrollback = Self::request_scrollback(
+                                &tabs,
+                                &mut tx_websocket,
+                                &mut rx_scrollback,
+                            )
+                            .await?;
+
+                            let mut matches = Vec::new();
+                            for tab in tabs.iter() {
+                                if let Some(data) = scrollback.remove(&tab.id) {
+                                    let mut found =
+                                        grep::search(&pattern, tab.name.as_str(), data.as_slice());
+                                    matches.append(&mut found);
+                                }
+                            }
+
+                            if matches.is_empty() {
+                                eprintln!("No matches.");
+                                tx_shutdown.send(MainShutdown {}).await?;
+                                continue;
+                            }
+
+                            grep::print_matches(matches.as_slice(), interactive)?;
+
+                            if interactive {
+                                if let Some(selected) =
+                                    grep::prompt_selection(matches.as_slice()).await?
+                                {
+                                    let name = selected.tab.clone();
+                                    tx_main.send(MainRecv::SelectTab(name)).await?;
+                                    continue;
+                                }
+                            }
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::ListTabs(recent, stats, selectors) => {
+                            let mut running_tabs =
+                                Self::await_initialized(&mut rx_tabs_state).await;
+                            let mut workspace_tabs = Self::await_workspace(&mut rx_workspace).await;
+
+                            if !selectors.is_empty() {
+                                let selectors: Vec<TabSelector> = selectors
+                                    .iter()
+                                    .map(|selector| TabSelector::parse(selector.as_str()))
+                                    .collect();
+                                let selected = |name: &str, tags: &[String]| {
+                                    selectors.iter().any(|s| s.matches(name, tags))
+                                };
+
+                                if let Some(ref mut running) = running_tabs {
+                                    running.tabs.retain(|_, tab| selected(&tab.name, &tab.tags));
+                                }
+
+                                if let Some(ref mut workspace) = workspace_tabs {
+                                    workspace.retain(|tab| selected(&tab.name, &tab.tags));
+                                }
+                            }
+
+                            let status = Self::running_status(&running_tabs, stats);
+                            let mut tabs = Self::merge_tabs(running_tabs, workspace_tabs);
+
+                            if recent {
+                                recent::sort(&mut tabs, |(name, _doc)| name.as_str());
+                            }
+
+                            Self::echo_tabs(&tabs, &status);
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::Top => {
+                            let mut running_tabs =
+                                Self::await_initialized(&mut rx_tabs_state).await;
+
+                            while let Some(running) = running_tabs {
+                                Self::echo_top(&running);
+
+                                // redraw at most a few times per second, as the stats of each tab arrive separately
+                                time::delay_for(TOP_INTERVAL).await;
+                                running_tabs = rx_tabs_state.recv().await;
+                            }
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::Status => {
+                            tx_websocket.send(Request::Status).await?;
+
+                            match time::timeout(Duration::from_secs(5), rx_status.recv()).await {
+                                Ok(Some(status)) => Self::echo_status(&status),
+                                _ => eprintln!("The daemon did not respond to the status request."),
+                            }
+
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+                        MainRecv::Logs(name, follow) => {
+                            tx_websocket.send(Request::Status).await?;
+
+                            let name = normalize_name(name.as_str());
+                            let status =
+                                time::timeout(Duration::from_secs(5), rx_status.recv()).await;
+                            let tab = match status {
+                                Ok(Some(status)) => {
+                                    status.tabs.into_iter().find(|tab| tab.name == name)
+                                }
+                                _ => {
+                                    eprintln!("The daemon did not respond to the status request.");
+                                    tx_shutdown.send(MainShutdown {}).await?;
+                                    continue;
+                                }
+                            };
+
+                            match tab.map(|tab| tab.pty) {
+                                Some(Some(pid)) => {
+                                    let path = pty_log(pid)?;
+                                    if let Err(e) = logs::print(path.as_path(), follow).await {
+                                        eprintln!("{}", e);
+                                    }
+                                }
+                                Some(None) => {
+                                    eprintln!("Tab {} has not been started by a pty.", name)
+                                }
+                                None => eprintln!("Tab {} is not running.", name),
+                            }
 
-                            Self::echo_tabs(&tabs);
                             tx_shutdown.send(MainShutdown {}).await?;
                         }
                         MainRecv::AutocompleteTab => {
@@ -284,7 +722,9 @@ impl CarryFrom<MainBus> for TabBus {
                             let workspace_tabs = Self::await_workspace(&mut rx_workspace).await;
                             debug!("printing tabs");
                             let tabs = Self::merge_tabs(running_tabs, workspace_tabs);
-                            let tabs = tabs.into_iter().map(|(name, _doc)| name).collect();
+                            let mut tabs: Vec<String> =
+                                tabs.into_iter().map(|(name, _doc)| name).collect();
+                            recent::sort(&mut tabs, String::as_str);
                             Self::echo_completion(&tabs);
                             tx_shutdown.send(MainShutdown {}).await?;
                             debug!("shutdown sent");
@@ -302,11 +742,25 @@ impl CarryFrom<MainBus> for TabBus {
 
                             tabs.sort();
                             tabs.dedup();
+                            recent::sort(&mut tabs, String::as_str);
 
                             Self::echo_completion(&tabs);
                             tx_shutdown.send(MainShutdown {}).await?;
                         }
 
+                        MainRecv::AutocompleteLayout => {
+                            let layouts = Self::await_layouts(&mut rx_workspace).await;
+                            let mut names: Vec<String> = layouts
+                                .unwrap_or_default()
+                                .into_iter()
+                                .map(|layout| layout.name)
+                                .collect();
+
+                            names.sort();
+                            Self::echo_completion(&names);
+                            tx_shutdown.send(MainShutdown {}).await?;
+                        }
+
                         MainRecv::GlobalShutdown => {}
                     }
                 }
@@ -355,7 +809,96 @@ impl TabBus {
         tabs
     }
 
-    fn echo_tabs(tabs: &Vec<(String, String)>) {
+    /// Describes the running tabs, with the foreground process, working directory, and output activity.
+    /// e.g. `cargo  ~/ws/proj/src  [bell, unread, last output 12s ago]`
+    fn running_status(running: &Option<TabsState>, stats: bool) -> HashMap<String, String> {
+        let mut status = HashMap::new();
+
+        let running = match running {
+            Some(running) => running,
+            None => return status,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+
+        for (id, metadata) in running.tabs.iter() {
+            let mut parts = Vec::new();
+
+            if let Some(ref process) = metadata.process {
+                parts.push(process.name.clone());
+                parts.push(Self::abbreviate_home(process.cwd.as_str()));
+            }
+
+            if stats {
+                if let Some(ref stats) = metadata.stats {
+                    parts.push(format!(
+                        "cpu {}%  mem {}  {} procs",
+                        stats.cpu,
+                        Self::format_memory(stats.memory),
+                        stats.processes
+                    ));
+                }
+            }
+
+            if let Some(activity) = running.activity.get(id) {
+                if let Some(flags) = Self::activity_flags(activity, now) {
+                    parts.push(format!("[{}]", flags));
+                }
+            }
+
+            if !parts.is_empty() {
+                status.insert(metadata.name.clone(), parts.join("  "));
+            }
+        }
+
+        status
+    }
+
+    /// Describes the output activity of a running tab, e.g. `bell, unread, last output 12s ago`
+    fn activity_flags(activity: &TabActivity, now: u64) -> Option<String> {
+        let mut flags = Vec::new();
+        if activity.bell {
+            flags.push("bell".to_string());
+        }
+
+        if activity.unread {
+            flags.push("unread".to_string());
+        }
+
+        if activity.silent {
+            flags.push("silent".to_string());
+        }
+
+        if activity.stale {
+            flags.push("stale".to_string());
+        }
+
+        if let Some(last_output) = activity.last_output {
+            let elapsed = now.saturating_sub(last_output);
+            flags.push(format!("last output {}s ago", elapsed));
+        }
+
+        if flags.is_empty() {
+            None
+        } else {
+            Some(flags.join(", "))
+        }
+    }
+
+    fn abbreviate_home(dir: &str) -> String {
+        if let Ok(home) = std::env::var("HOME") {
+            if !home.is_empty() && (dir == home || dir.starts_with((home.clone() + "/").as_str())) {
+                return format!("~{}", &dir[home.len()..]);
+            }
+        }
+
+        dir.to_string()
+    }
+
+    fn echo_tabs(tabs: &Vec<(String, String)>, status: &HashMap<String, String>) {
         debug!("echo tabs: {:?}", tabs);
 
         if tabs.len() == 0 {
@@ -368,14 +911,165 @@ impl TabBus {
         println!("Available tabs:");
         for (name, doc) in tabs.iter() {
             print!("    {}", name);
-            if doc.len() > 0 {
+
+            let mut details = Vec::new();
+            if !doc.is_empty() {
+                details.push(format!("({})", doc));
+            }
+
+            if let Some(status) = status.get(name) {
+                details.push(status.clone());
+            }
+
+            if !details.is_empty() {
                 for _ in name.len()..target_len {
                     print!(" ");
                 }
-                println!("({})", doc);
-            } else {
-                println!("");
+
+                print!("{}", details.join("  "));
             }
+
+            println!();
+        }
+    }
+
+    /// Clears the terminal, and prints the running tabs with their resource usage, ordered by memory usage
+    fn echo_top(running: &TabsState) {
+        let mut tabs: Vec<&TabMetadata> = running.tabs.values().collect();
+        tabs.sort_by(|a, b| {
+            let memory =
+                |tab: &TabMetadata| tab.stats.as_ref().map(|stats| stats.memory).unwrap_or(0);
+            memory(b).cmp(&memory(a)).then_with(|| a.name.cmp(&b.name))
+        });
+
+        let len = tabs
+            .iter()
+            .map(|tab| tab.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(3);
+
+        let mut screen = String::new();
+        screen += "\x1b[H\x1b[2J";
+        screen += format!(
+            "{:len$}  {:>5}  {:>7}  {:>5}  PROCESS\n",
+            "TAB",
+            "CPU",
+            "MEM",
+            "PROCS",
+            len = len
+        )
+        .as_str();
+
+        for tab in tabs {
+            let (cpu, memory, processes) = match tab.stats.as_deref() {
+                Some(stats) => (
+                    format!("{}%", stats.cpu),
+                    Self::format_memory(stats.memory),
+                    stats.processes.to_string(),
+                ),
+                None => ("-".to_string(), "-".to_string(), "-".to_string()),
+            };
+
+            let process = tab
+                .process
+                .as_ref()
+                .map(|process| process.name.as_str())
+                .unwrap_or("");
+
+            screen += format!(
+                "{:len$}  {:>5}  {:>7}  {:>5}  {}\n",
+                tab.name,
+                cpu,
+                memory,
+                processes,
+                process,
+                len = len
+            )
+            .as_str();
+        }
+
+        print!("{}", screen);
+        std::io::stdout().flush().ok();
+    }
+
+    /// Prints the daemon process, connections, pty assignments, and channel capacities
+    fn echo_status(status: &DaemonStatus) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let uptime = now.saturating_sub(status.started);
+
+        println!("Daemon:");
+        println!("    pid          {}", status.pid);
+        println!("    version      {}", status.version);
+        println!("    uptime       {}", Self::format_uptime(uptime));
+        println!("    port         127.0.0.1:{}", status.port);
+        println!("Connections:");
+        println!("    cli          {}", status.cli_connections);
+        println!("    pty          {}", status.pty_connections);
+        println!(
+            "    idle pty     {} (pool of {})",
+            status.idle_ptys, status.pty_pool
+        );
+
+        println!("Tabs:");
+        if status.tabs.is_empty() {
+            println!("    No active tabs.");
+        }
+
+        let len = status
+            .tabs
+            .iter()
+            .map(|tab| tab.name.len())
+            .max()
+            .unwrap_or(0);
+        for tab in status.tabs.iter() {
+            let assignment = match (tab.assigned, tab.pty) {
+                (true, Some(pid)) => format!("pty pid {}", pid),
+                (true, None) => "pty assigned".to_string(),
+                (false, _) => "waiting for pty".to_string(),
+            };
+
+            println!(
+                "    {:>3}  {:len$}  {}",
+                tab.id.0,
+                tab.name,
+                assignment,
+                len = len
+            );
+        }
+
+        println!("Channels:");
+        for channel in status.channels.iter() {
+            let name = format!("{}/{}", channel.bus, channel.message);
+            println!("    {:20} {}", name, channel.capacity);
+        }
+    }
+
+    /// Formats a duration in seconds, e.g. `45s`, `12m 5s`, or `3d 4h`
+    fn format_uptime(secs: u64) -> String {
+        let (days, hours, minutes) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60);
+        if days > 0 {
+            format!("{}d {}h", days, hours)
+        } else if hours > 0 {
+            format!("{}h {}m", hours, minutes)
+        } else if minutes > 0 {
+            format!("{}m {}s", minutes, secs % 60)
+        } else {
+            format!("{}s", secs)
+        }
+    }
+
+    /// Formats a memory size in kibibytes, e.g. `812K`, or `1.2G`
+    fn format_memory(kib: u64) -> String {
+        if kib < 1024 {
+            format!("{}K", kib)
+        } else if kib < 1024 * 1024 {
+            format!("{:.1}M", kib as f64 / 1024.0)
+        } else {
+            format!("{:.1}G", kib as f64 / (1024.0 * 1024.0))
         }
     }
 
@@ -387,6 +1081,127 @@ impl TabBus {
         }
     }
 
+    /// Finds the layout with the given name, or prints the available layouts
+    fn find_layout(layouts: Option<Vec<WorkspaceLayout>>, name: &str) -> Option<WorkspaceLayout> {
+        let layouts = layouts.unwrap_or_default();
+        if let Some(layout) = layouts.iter().find(|layout| layout.name == name) {
+            return Some(layout.clone());
+        }
+
+        eprintln!("Unknown layout: {}", name);
+        if !layouts.is_empty() {
+            let mut names: Vec<&str> = layouts.iter().map(|layout| layout.name.as_str()).collect();
+            names.sort();
+            eprintln!("Available layouts: {}", names.join(", "));
+        }
+
+        None
+    }
+
+    /// Creates the tab if it isn't running, and requests that the daemon writes its output to the file.
+    /// Used by `--record` and `--log-output`, with the `Request::RecordTab` or `Request::LogTab` constructor.
+    async fn write_to_file(
+        name: &str,
+        path: &Path,
+        action: &str,
+        request: fn(TabId, String) -> Request,
+        rx_tabs_state: &mut watch::Receiver<TabsState>,
+        tx_create: &mut impl Sender<CreateTabRequest>,
+        tx_websocket: &mut impl Sender<Request>,
+    ) -> anyhow::Result<()> {
+        let name = normalize_name(name);
+        Self::await_initialized(rx_tabs_state).await;
+
+        tx_create
+            .send(CreateTabRequest::Named(name.clone()))
+            .await?;
+
+        let metadata = Self::await_created(name.clone(), rx_tabs_state).await?;
+
+        eprintln!("{} tab {} to {}", action, name, path.to_string_lossy());
+        let path = path.to_string_lossy().to_string();
+        tx_websocket.send(request(metadata.id, path)).await?;
+
+        time::delay_for(Duration::from_millis(5)).await;
+
+        Ok(())
+    }
+
+    /// Requests a copy of the scrollback for each tab, and waits for the responses.
+    /// Tabs which do not respond within the timeout are omitted.
+    async fn request_scrollback(
+        tabs: &[TabMetadata],
+        tx: &mut impl Sender<Request>,
+        rx: &mut impl Receiver<TabScrollback>,
+    ) -> anyhow::Result<HashMap<TabId, Vec<u8>>> {
+        for tab in tabs {
+            tx.send(Request::Scrollback(tab.id)).await?;
+        }
+
+        let mut scrollback = HashMap::new();
+        while scrollback.len() < tabs.len() {
+            match time::timeout(Duration::from_millis(2000), rx.recv()).await {
+                Ok(Some(TabScrollback(id, data))) => {
+                    scrollback.insert(id, data);
+                }
+                Ok(None) => break,
+                Err(_) => {
+                    warn!("timed out waiting for tab scrollback");
+                    break;
+                }
+            }
+        }
+
+        Ok(scrollback)
+    }
+
+    /// Waits for the closing tabs to exit, and prints the signals which are sent to them
+    async fn await_closed(
+        mut closing: HashMap<TabId, String>,
+        options: CloseOptions,
+        rx_tabs_state: &mut watch::Receiver<TabsState>,
+        rx_closing: &mut impl Receiver<TabClosing>,
+    ) {
+        // SIGKILL is sent after two grace periods
+        let timeout = Duration::from_secs(2 * options.grace_period) + CLOSE_TIMEOUT;
+
+        let wait = async {
+            while !closing.is_empty() {
+                select! {
+                    state = rx_tabs_state.recv() => {
+                        let state = match state {
+                            Some(state) => state,
+                            None => break,
+                        };
+
+                        closing.retain(|id, name| {
+                            let running = state.tabs.contains_key(id);
+                            if !running {
+                                eprintln!("Closed tab: {}", name);
+                            }
+
+                            running
+                        });
+                    }
+                    msg = rx_closing.recv() => {
+                        match msg {
+                            Some(TabClosing(id, signal)) => {
+                                if let Some(name) = closing.get(&id) {
+                                    eprintln!("Sent {} to tab: {}", signal, name);
+                                }
+                            }
+                            None => break,
+                        }
+                    }
+                }
+            }
+        };
+
+        if time::timeout(timeout, wait).await.is_err() {
+            eprintln!("Timed out waiting for tabs to close.  Use --force to kill them.");
+        }
+    }
+
     async fn await_initialized(rx: &mut watch::Receiver<TabsState>) -> Option<TabsState> {
         {
             let borrow = rx.borrow();
@@ -408,18 +1223,30 @@ impl TabBus {
     async fn await_workspace(
         rx: &mut watch::Receiver<WorkspaceState>,
     ) -> Option<Vec<WorkspaceTab>> {
+        Self::await_ready(rx).await.map(|(tabs, _layouts)| tabs)
+    }
+
+    async fn await_layouts(
+        rx: &mut watch::Receiver<WorkspaceState>,
+    ) -> Option<Vec<WorkspaceLayout>> {
+        Self::await_ready(rx).await.map(|(_tabs, layouts)| layouts)
+    }
+
+    async fn await_ready(
+        rx: &mut watch::Receiver<WorkspaceState>,
+    ) -> Option<(Vec<WorkspaceTab>, Vec<WorkspaceLayout>)> {
         {
             let borrow = rx.borrow();
-            if let WorkspaceState::Ready(ref ready) = *borrow {
-                return Some(ready.clone());
+            if let WorkspaceState::Ready(ref tabs, ref layouts) = *borrow {
+                return Some((tabs.clone(), layouts.clone()));
             }
         }
         let mut state = rx.recv().await;
         // TODO: 2 second timeout?
 
         while state.is_some() {
-            if let Some(WorkspaceState::Ready(ready)) = state {
-                return Some(ready);
+            if let Some(WorkspaceState::Ready(tabs, layouts)) = state {
+                return Some((tabs, layouts));
             };
 
             state = rx.recv().await;
@@ -428,24 +1255,21 @@ impl TabBus {
         None
     }
 
-    async fn await_created(name: String, rx: &mut watch::Receiver<TabsState>) -> TabMetadata {
+    /// Waits for the tab to be created.  Returns an error if the tab state is closed.
+    async fn await_created(
+        name: String,
+        rx: &mut watch::Receiver<TabsState>,
+    ) -> anyhow::Result<TabMetadata> {
         {
             let borrow = rx.borrow();
             let existing = borrow.tabs.values().find(|tab| tab.name == name);
             if let Some(metadata) = existing {
-                return metadata.clone();
+                return Ok(metadata.clone());
             }
         }
 
-        // TODO: 2 second timeout?
         loop {
-            let state = rx.recv().await;
-
-            if !state.is_some() {
-                continue;
-            }
-
-            let state = state.unwrap();
+            let state = rx.recv().await.context("rx TabsState closed")?;
 
             if !state.initialized {
                 continue;
@@ -453,7 +1277,7 @@ impl TabBus {
 
             for (_id, metadata) in state.tabs {
                 if metadata.name == name {
-                    return metadata;
+                    return Ok(metadata);
                 }
             }
         }
@@ -0,0 +1,253 @@
+//! Searches tab scrollback for lines which match a pattern.
+
+use regex::Regex;
+use std::io::Write;
+use tab_api::ansi::AnsiStripper;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+static CONTEXT_LINES: usize = 2;
+
+/// A scrollback line which matches the search pattern, with the surrounding lines.
+#[derive(Debug, Clone)]
+pub struct GrepMatch {
+    pub tab: String,
+    /// The line number within the scrollback buffer, starting at 1
+    pub line: usize,
+    pub before: Vec<String>,
+    pub text: String,
+    pub after: Vec<String>,
+}
+
+/// Searches the scrollback of the tab for matching lines.  ANSI escape sequences are removed before matching.
+pub fn search(pattern: &Regex, tab: &str, scrollback: &[u8]) -> Vec<GrepMatch> {
+    let stripped = AnsiStripper::default().strip(scrollback);
+    let text = String::from_utf8_lossy(stripped.as_slice());
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if !pattern.is_match(line) {
+            continue;
+        }
+
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + 1 + CONTEXT_LINES).min(lines.len());
+
+        matches.push(GrepMatch {
+            tab: tab.to_string(),
+            line: index + 1,
+            before: lines[start..index].iter().map(|s| s.to_string()).collect(),
+            text: line.to_string(),
+            after: lines[index + 1..end]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        });
+    }
+
+    matches
+}
+
+/// Prints the matches in grep format (`tab:line:text`, with context lines as `tab-line-text`).
+/// If numbered, matches are prefixed with an index which can be selected with `prompt_selection`.
+pub fn print_matches(matches: &[GrepMatch], numbered: bool) -> std::io::Result<()> {
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    write_matches(&mut stdout, matches, numbered)?;
+    stdout.flush()
+}
+
+fn write_matches(
+    out: &mut impl Write,
+    matches: &[GrepMatch],
+    numbered: bool,
+) -> std::io::Result<()> {
+    let width = matches.len().to_string().len() + 3;
+    let context_prefix = if numbered {
+        " ".repeat(width)
+    } else {
+        "".into()
+    };
+
+    for (index, grep_match) in matches.iter().enumerate() {
+        if index > 0 {
+            writeln!(out, "--")?;
+        }
+
+        let first_context = grep_match.line - grep_match.before.len();
+        for (offset, line) in grep_match.before.iter().enumerate() {
+            writeln!(
+                out,
+                "{}{}-{}-{}",
+                context_prefix,
+                grep_match.tab,
+                first_context + offset,
+                line
+            )?;
+        }
+
+        let prefix = if numbered {
+            format!("{:<width$}", format!("[{}]", index + 1), width = width)
+        } else {
+            "".into()
+        };
+        writeln!(
+            out,
+            "{}{}:{}:{}",
+            prefix, grep_match.tab, grep_match.line, grep_match.text
+        )?;
+
+        for (offset, line) in grep_match.after.iter().enumerate() {
+            writeln!(
+                out,
+                "{}{}-{}-{}",
+                context_prefix,
+                grep_match.tab,
+                grep_match.line + 1 + offset,
+                line
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prompts the user to select one of the numbered matches.  Returns None if the selection is empty or invalid.
+pub async fn prompt_selection(matches: &[GrepMatch]) -> anyhow::Result<Option<&GrepMatch>> {
+    eprint!("Select a match to switch tabs [1-{}]: ", matches.len());
+
+    let line = read_line(&mut tokio::io::stdin()).await?;
+    Ok(parse_selection(line.as_str(), matches))
+}
+
+/// Reads a single line, one byte at a time.
+/// Stdin is not buffered, so the input which follows the line is left for the terminal.
+async fn read_line(input: &mut (impl AsyncRead + Unpin)) -> std::io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+
+    while input.read(&mut byte).await? > 0 && byte[0] != b'\n' {
+        line.push(byte[0]);
+    }
+
+    Ok(String::from_utf8_lossy(line.as_slice()).into_owned())
+}
+
+fn parse_selection<'m>(line: &str, matches: &'m [GrepMatch]) -> Option<&'m GrepMatch> {
+    line.trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| index.checked_sub(1))
+        .and_then(|index| matches.get(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_selection, read_line, search, write_matches};
+    use regex::Regex;
+
+    fn scrollback(lines: &[&str]) -> Vec<u8> {
+        lines.join("\r\n").into_bytes()
+    }
+
+    fn printed(scrollback: &[u8], pattern: &str, numbered: bool) -> String {
+        let pattern = Regex::new(pattern).unwrap();
+        let matches = search(&pattern, "tab/", scrollback);
+
+        let mut out = Vec::new();
+        write_matches(&mut out, matches.as_slice(), numbered).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn search_context() {
+        let scrollback = scrollback(&["a", "b", "c", "d", "e", "f", "g"]);
+        let pattern = Regex::new("d").unwrap();
+        let matches = search(&pattern, "tab/", scrollback.as_slice());
+
+        assert_eq!(1, matches.len());
+        assert_eq!(4, matches[0].line);
+        assert_eq!(vec!["b", "c"], matches[0].before);
+        assert_eq!("d", matches[0].text);
+        assert_eq!(vec!["e", "f"], matches[0].after);
+    }
+
+    #[test]
+    fn search_context_start() {
+        let scrollback = scrollback(&["a", "b", "c", "d"]);
+        let pattern = Regex::new("a").unwrap();
+        let matches = search(&pattern, "tab/", scrollback.as_slice());
+
+        assert_eq!(1, matches.len());
+        assert_eq!(1, matches[0].line);
+        assert!(matches[0].before.is_empty());
+        assert_eq!(vec!["b", "c"], matches[0].after);
+    }
+
+    #[test]
+    fn search_context_end() {
+        let scrollback = scrollback(&["a", "b", "c", "d"]);
+        let pattern = Regex::new("d").unwrap();
+        let matches = search(&pattern, "tab/", scrollback.as_slice());
+
+        assert_eq!(1, matches.len());
+        assert_eq!(4, matches[0].line);
+        assert_eq!(vec!["b", "c"], matches[0].before);
+        assert!(matches[0].after.is_empty());
+    }
+
+    #[test]
+    fn search_strips_ansi() {
+        let scrollback = b"\x1b[1;31merror\x1b[0m: failed\r\nok";
+        let pattern = Regex::new("^error: failed$").unwrap();
+        let matches = search(&pattern, "tab/", scrollback);
+
+        assert_eq!(1, matches.len());
+        assert_eq!("error: failed", matches[0].text);
+        assert_eq!(vec!["ok"], matches[0].after);
+    }
+
+    #[test]
+    fn print_context() {
+        let scrollback = scrollback(&["a", "b", "c", "d", "e"]);
+        let printed = printed(scrollback.as_slice(), "^[ae]$", false);
+
+        assert_eq!(
+            "tab/:1:a\ntab/-2-b\ntab/-3-c\n--\ntab/-3-c\ntab/-4-d\ntab/:5:e\n",
+            printed
+        );
+    }
+
+    #[test]
+    fn print_numbered() {
+        let scrollback = scrollback(&["a", "b", "c", "d", "e"]);
+        let printed = printed(scrollback.as_slice(), "^[ae]$", true);
+
+        assert_eq!(
+            "[1] tab/:1:a\n    tab/-2-b\n    tab/-3-c\n--\n    tab/-3-c\n    tab/-4-d\n[2] tab/:5:e\n",
+            printed
+        );
+    }
+
+    #[test]
+    fn selection() {
+        let scrollback = scrollback(&["a", "b", "a"]);
+        let pattern = Regex::new("a").unwrap();
+        let matches = search(&pattern, "tab/", scrollback.as_slice());
+
+        assert_eq!(3, parse_selection(" 2\n", matches.as_slice()).unwrap().line);
+        assert!(parse_selection("0", matches.as_slice()).is_none());
+        assert!(parse_selection("3", matches.as_slice()).is_none());
+        assert!(parse_selection("", matches.as_slice()).is_none());
+    }
+
+    #[tokio::test]
+    async fn read_line_leaves_remaining_input() -> anyhow::Result<()> {
+        let mut input: &[u8] = b"2\nls\n";
+
+        assert_eq!("2", read_line(&mut input).await?);
+        assert_eq!(b"ls\n", input);
+
+        Ok(())
+    }
+}
@@ -9,16 +9,36 @@ use crate::bus::MainBus;
 use message::main::{MainRecv, MainShutdown};
 
 use lifeline::dyn_bus::DynBus;
-use tab_api::{launch::*, log::get_level, tab::normalize_name};
+use tab_api::{
+    config::daemon_log,
+    launch::*,
+    log::get_level,
+    tab::{CloseOptions, TabSelector, TabSignal},
+};
 use tab_websocket::resource::connection::WebsocketResource;
 
 mod bus;
 mod env;
+mod grep;
+mod logs;
 mod message;
 mod prelude;
+mod recent;
+mod replay;
 mod service;
 mod state;
 
+/// Checks the workspace configuration for the current directory, and prints any problems.
+/// Returns true if the configuration is valid.
+pub fn check_config_main() -> bool {
+    service::workspace::check_config()
+}
+
+/// Prints the JSON Schema for the `tab.yml` configuration files
+pub fn print_schema_main() {
+    println!("{}", state::workspace::config_schema());
+}
+
 pub fn command_main(args: ArgMatches) -> anyhow::Result<()> {
     TermLogger::init(
         get_level().unwrap_or(LevelFilter::Warn),
@@ -50,11 +70,47 @@ pub fn command_main(args: ArgMatches) -> anyhow::Result<()> {
 }
 
 async fn main_async(matches: ArgMatches<'_>) -> anyhow::Result<()> {
-    let select_tab = matches.value_of("TAB-NAME");
+    let select_tab = match matches.value_of("TAB-NAME") {
+        // like `cd -`, jumps back to the previous tab
+        Some("-") => {
+            let current = std::env::var("TAB").ok();
+            match recent::previous(current.as_deref()) {
+                Some(previous) => Some(previous),
+                None => {
+                    eprintln!("No previous tab.");
+                    return Ok(());
+                }
+            }
+        }
+        select_tab => select_tab.map(str::to_string),
+    };
+    let select_tab = select_tab.as_deref();
+
+    // the daemon log is printed without launching the daemon, as it may be needed to diagnose launch failures
+    let logs = matches
+        .is_present("LOGS")
+        .then(|| matches.value_of("LOGS").unwrap_or("daemon"));
+    let follow = matches.is_present("FOLLOW");
+    if logs == Some("daemon") {
+        return logs::print(daemon_log()?.as_path(), follow).await;
+    }
+
+    if let Some(file) = matches.value_of("REPLAY") {
+        let path = std::env::current_dir()?.join(file);
+        return replay::replay(path.as_path()).await;
+    }
+
     let close_tabs = matches.values_of("CLOSE-TAB");
+    let record = matches.values_of("RECORD");
+    let log_output = matches.values_of("LOG-OUTPUT");
+    let grep = matches.value_of("GREP");
+    let signal = matches.value_of("SIGNAL");
+    let rename = matches.values_of("RENAME");
+    let send = matches.values_of("SEND");
     let (mut tx, rx_shutdown, _service) = spawn().await?;
     let completion = matches.is_present("AUTOCOMPLETE-TAB");
     let close_completion = matches.is_present("AUTOCOMPLETE-CLOSE-TAB");
+    let layout_completion = matches.is_present("AUTOCOMPLETE-LAYOUT");
     let shutdown = matches.is_present("SHUTDOWN");
 
     if shutdown {
@@ -63,14 +119,72 @@ async fn main_async(matches: ArgMatches<'_>) -> anyhow::Result<()> {
         tx.send(MainRecv::AutocompleteTab).await?;
     } else if close_completion {
         tx.send(MainRecv::AutocompleteCloseTab).await?;
+    } else if layout_completion {
+        tx.send(MainRecv::AutocompleteLayout).await?;
     } else if matches.is_present("LIST") {
-        tx.send(MainRecv::ListTabs).await?;
+        let recent = matches.is_present("RECENT");
+        let stats = matches.is_present("STATS");
+        let selectors = matches
+            .values_of("LIST")
+            .map(|selectors| selectors.map(str::to_string).collect())
+            .unwrap_or_default();
+        tx.send(MainRecv::ListTabs(recent, stats, selectors))
+            .await?;
+    } else if matches.is_present("TOP") {
+        tx.send(MainRecv::Top).await?;
+    } else if matches.is_present("STATUS") {
+        tx.send(MainRecv::Status).await?;
+    } else if let Some(tab) = logs {
+        tx.send(MainRecv::Logs(tab.to_string(), follow)).await?;
+    } else if let Some(mut record) = record {
+        let tab = record.next().expect("a tab is required for --record");
+        let file = record.next().expect("a file is required for --record");
+        let path = std::env::current_dir()?.join(file);
+        tx.send(MainRecv::RecordTab(tab.to_string(), path)).await?;
+    } else if let Some(mut rename) = rename {
+        let tab = rename.next().expect("a tab is required for --rename");
+        let name = rename.next().expect("a new name is required for --rename");
+        tx.send(MainRecv::RenameTab(tab.to_string(), name.to_string()))
+            .await?;
+    } else if let Some(mut send) = send {
+        let tabs = send.next().expect("tabs are required for --send");
+        let text = send.next().expect("text is required for --send");
+        let selector = TabSelector::parse(tabs);
+        tx.send(MainRecv::SendTabs(selector, text.to_string()))
+            .await?;
+    } else if let Some(mut log_output) = log_output {
+        let tab = log_output
+            .next()
+            .expect("a tab is required for --log-output");
+        let file = log_output
+            .next()
+            .expect("a file is required for --log-output");
+        let path = std::env::current_dir()?.join(file);
+        tx.send(MainRecv::LogTab(tab.to_string(), path)).await?;
+    } else if let Some(pattern) = grep {
+        let interactive = matches.is_present("INTERACTIVE");
+        tx.send(MainRecv::Grep(pattern.to_string(), interactive))
+            .await?;
+    } else if let Some(signal) = signal {
+        let signal = TabSignal::parse(signal).expect("a valid signal is required for --signal");
+        let tab = select_tab.expect("a tab is required for --signal");
+        tx.send(MainRecv::Signal(tab.to_string(), signal)).await?;
+    } else if let Some(layout) = matches.value_of("UP") {
+        let select = select_tab.map(str::to_string);
+        tx.send(MainRecv::Up(layout.to_string(), select)).await?;
+    } else if let Some(layout) = matches.value_of("DOWN") {
+        let options = close_options(false, matches.value_of("GRACE"));
+        tx.send(MainRecv::Down(layout.to_string(), options)).await?;
+    } else if matches.is_present("PRUNE") {
+        let options = close_options(false, matches.value_of("GRACE"));
+        tx.send(MainRecv::Prune(options)).await?;
     } else if let Some(tab) = select_tab {
         info!("selecting tab: {}", tab);
         tx.send(MainRecv::SelectTab(tab.to_string())).await?;
     } else if let Some(tabs) = close_tabs {
-        let tabs: Vec<String> = tabs.map(normalize_name).collect();
-        tx.send(MainRecv::CloseTabs(tabs)).await?;
+        let tabs: Vec<TabSelector> = tabs.map(TabSelector::parse).collect();
+        let options = close_options(matches.is_present("FORCE"), matches.value_of("GRACE"));
+        tx.send(MainRecv::CloseTabs(tabs, options)).await?;
     } else {
         tx.send(MainRecv::SelectTab("any/".to_string())).await?;
     }
@@ -82,6 +196,21 @@ async fn main_async(matches: ArgMatches<'_>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Closes tabs gracefully, waiting `--grace` or `TAB_CLOSE_GRACE_SECS` (default 5) seconds before escalating signals
+fn close_options(force: bool, grace: Option<&str>) -> CloseOptions {
+    let default = CloseOptions::default();
+    let grace_period = grace
+        .map(str::to_string)
+        .or_else(|| std::env::var("TAB_CLOSE_GRACE_SECS").ok())
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or(default.grace_period);
+
+    CloseOptions {
+        force,
+        grace_period,
+    }
+}
+
 async fn spawn() -> anyhow::Result<(
     impl Sender<MainRecv>,
     impl Receiver<MainShutdown>,
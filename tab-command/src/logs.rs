@@ -0,0 +1,191 @@
+//! Prints the daemon & pty log files, for `tab --logs`.
+//! With `--follow`, new records are printed as they are written, and rotated log files are reopened.
+
+use anyhow::Context;
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{task, time};
+
+static POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Prints the log file, and if `follow` is set, waits for new records until the process is interrupted.
+/// The file is read on a blocking thread.
+pub async fn print(path: &Path, follow: bool) -> anyhow::Result<()> {
+    let path = path.to_path_buf();
+    let mut log =
+        task::spawn_blocking(move || LogFile::open(path, &mut std::io::stdout())).await??;
+
+    if !follow {
+        return Ok(());
+    }
+
+    loop {
+        time::delay_for(POLL_INTERVAL).await;
+
+        log = task::spawn_blocking(move || {
+            log.follow(&mut std::io::stdout())?;
+            Ok::<_, anyhow::Error>(log)
+        })
+        .await??;
+    }
+}
+
+/// A log file which has been copied up to `position`
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    inode: u64,
+    position: u64,
+}
+
+impl LogFile {
+    /// Opens the log file, and copies the existing records to the output
+    fn open(path: PathBuf, out: &mut impl Write) -> anyhow::Result<Self> {
+        let mut file = File::open(&path)
+            .with_context(|| format!("failed to open the log file {}", path.to_string_lossy()))?;
+        let inode = file.metadata()?.ino();
+        let position = copy(&mut file, out)?;
+
+        Ok(Self {
+            path,
+            file,
+            inode,
+            position,
+        })
+    }
+
+    /// Copies new records to the output, and reopens the file if it was rotated or truncated
+    fn follow(&mut self, out: &mut impl Write) -> anyhow::Result<()> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // the file is being rotated
+            Err(_) => return Ok(()),
+        };
+
+        if metadata.ino() != self.inode || metadata.len() < self.position {
+            // records may have been written to the rotated file, which is still open
+            if metadata.ino() != self.inode {
+                self.file.seek(SeekFrom::Start(self.position))?;
+                copy(&mut self.file, out)?;
+            }
+
+            self.file = match File::open(&self.path) {
+                Ok(file) => file,
+                Err(_) => return Ok(()),
+            };
+            self.inode = metadata.ino();
+            self.position = 0;
+        }
+
+        if metadata.len() > self.position {
+            self.file.seek(SeekFrom::Start(self.position))?;
+            self.position += copy(&mut self.file, out)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Copies the remainder of the file to the output, and returns the number of bytes copied
+fn copy(file: &mut File, out: &mut impl Write) -> anyhow::Result<u64> {
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+
+    out.write_all(buf.as_slice())?;
+    out.flush()?;
+
+    Ok(buf.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogFile;
+    use std::{fs::OpenOptions, io::Write, path::Path};
+
+    fn append(path: &Path, data: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    fn followed(log: &mut LogFile) -> String {
+        let mut out = Vec::new();
+        log.follow(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn follow_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+        append(&path, "a\n");
+
+        let mut out = Vec::new();
+        let mut log = LogFile::open(path.clone(), &mut out).unwrap();
+        assert_eq!(b"a\n", out.as_slice());
+
+        assert_eq!("", followed(&mut log));
+
+        append(&path, "b\n");
+        assert_eq!("b\n", followed(&mut log));
+    }
+
+    #[test]
+    fn follow_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+        let rotated = dir.path().join("daemon.log.1");
+        append(&path, "a\n");
+
+        let mut log = LogFile::open(path.clone(), &mut Vec::new()).unwrap();
+
+        // the final records of the rotated file are copied, then the new file is followed from the start
+        append(&path, "b\n");
+        std::fs::rename(&path, &rotated).unwrap();
+        append(&path, "c\n");
+        assert_eq!("b\nc\n", followed(&mut log));
+
+        append(&rotated, "x\n");
+        append(&path, "d\n");
+        assert_eq!("d\n", followed(&mut log));
+    }
+
+    #[test]
+    fn follow_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+        append(&path, "a\n");
+
+        let mut log = LogFile::open(path.clone(), &mut Vec::new()).unwrap();
+
+        // the file is waited for while it is missing
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!("", followed(&mut log));
+
+        append(&path, "b\n");
+        assert_eq!("b\n", followed(&mut log));
+    }
+
+    #[test]
+    fn follow_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+        append(&path, "aaaa\n");
+
+        let mut log = LogFile::open(path.clone(), &mut Vec::new()).unwrap();
+
+        std::fs::write(&path, "b\n").unwrap();
+        assert_eq!("b\n", followed(&mut log));
+
+        append(&path, "c\n");
+        assert_eq!("c\n", followed(&mut log));
+    }
+}
@@ -1,4 +1,12 @@
-use tab_api::tab::TabId;
+use tab_api::tab::{TabId, TabSignal};
 
 #[derive(Clone, Debug)]
 pub struct TabTerminated(pub TabId);
+
+/// A notification that the signal has been sent to the processes in a closing tab
+#[derive(Clone, Debug)]
+pub struct TabClosing(pub TabId, pub TabSignal);
+
+/// A copy of the scrollback buffer for a tab, requested with `Request::Scrollback`
+#[derive(Clone, Debug)]
+pub struct TabScrollback(pub TabId, pub Vec<u8>);
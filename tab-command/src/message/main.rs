@@ -1,6 +1,7 @@
+use std::path::PathBuf;
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    tab::TabId,
+    tab::{CloseOptions, TabId, TabSelector, TabSignal},
 };
 
 #[derive(Debug)]
@@ -9,10 +10,33 @@ pub struct MainShutdown {}
 #[derive(Debug, Clone)]
 pub enum MainRecv {
     SelectTab(String),
-    ListTabs,
-    CloseTabs(Vec<String>),
+    /// Lists the running and workspace tabs, optionally in most-recently-selected order, and with resource usage.
+    /// If selectors are provided, only the matching tabs are listed.
+    ListTabs(bool, bool, Vec<String>),
+    /// Displays a live view of the running tabs, ordered by memory usage
+    Top,
+    /// Closes the tabs which match the selectors
+    CloseTabs(Vec<TabSelector>, CloseOptions),
+    /// Sends a line of input to the tabs which match the selector
+    SendTabs(TabSelector, String),
+    /// Starts the tabs in the named layout, and optionally selects a tab
+    Up(String, Option<String>),
+    /// Closes the tabs in the named layout
+    Down(String, CloseOptions),
+    /// Closes the running tabs which are marked stale
+    Prune(CloseOptions),
+    /// Prints the state of the daemon, including connections and pty assignments
+    Status,
+    /// Prints the pty log of the named tab, and follows it if the flag is set
+    Logs(String, bool),
+    RenameTab(String, String),
+    RecordTab(String, PathBuf),
+    LogTab(String, PathBuf),
+    Signal(String, TabSignal),
+    Grep(String, bool),
     AutocompleteTab,
     AutocompleteCloseTab,
+    AutocompleteLayout,
     GlobalShutdown,
 }
 
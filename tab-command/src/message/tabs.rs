@@ -1,11 +1,12 @@
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{TabActivity, TabId, TabMetadata};
 
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 pub enum TabsRecv {
-    Init(HashMap<TabId, TabMetadata>),
+    Init(HashMap<TabId, TabMetadata>, HashMap<TabId, TabActivity>),
     Update(TabMetadata),
+    Activity(TabId, TabActivity),
     Terminated(TabId),
 }
 
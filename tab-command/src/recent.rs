@@ -0,0 +1,152 @@
+//! The most-recently-used list of selected tabs, which is shared by all of the user's clients.
+//! Used to jump back to the previous tab with `tab -`, and to order `tab -l --recent` and autocompletion.
+
+use log::warn;
+use tab_api::config::recent_path;
+
+static MAX_RECENT: usize = 64;
+
+/// Loads the recently selected tabs, with the most recent tab first.
+pub fn load() -> Vec<String> {
+    recent_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|recent| recent.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Moves the tab to the front of the list, as it has been selected.
+pub fn push(name: &str) {
+    let mut recent = load();
+    recent.retain(|tab| tab != name);
+    recent.insert(0, name.to_string());
+    recent.truncate(MAX_RECENT);
+
+    if let Err(e) = save(recent.as_slice()) {
+        warn!("failed to save recent tabs: {}", e);
+    }
+}
+
+/// Replaces the name of a renamed tab, retaining its position.
+pub fn rename(from: &str, to: &str) {
+    let mut recent = load();
+    recent.retain(|tab| tab != to);
+
+    for tab in recent.iter_mut() {
+        if tab == from {
+            *tab = to.to_string();
+        }
+    }
+
+    if let Err(e) = save(recent.as_slice()) {
+        warn!("failed to save recent tabs: {}", e);
+    }
+}
+
+/// The most recently selected tab, other than the current tab.
+pub fn previous(current: Option<&str>) -> Option<String> {
+    load().into_iter().find(|tab| Some(tab.as_str()) != current)
+}
+
+/// Sorts the tabs so recently selected tabs come first, in the order they were selected.
+/// Other tabs retain their order.
+pub fn sort<T>(tabs: &mut [T], name: impl Fn(&T) -> &str) {
+    let recent = load();
+    tabs.sort_by_key(|tab| {
+        recent
+            .iter()
+            .position(|recent| recent == name(tab))
+            .unwrap_or(usize::MAX)
+    });
+}
+
+fn save(recent: &[String]) -> anyhow::Result<()> {
+    let path = recent_path()?;
+    let mut contents = recent.join("\n");
+    contents.push('\n');
+    std::fs::write(path, contents)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, previous, push, rename, sort, MAX_RECENT};
+    use std::sync::{Mutex, MutexGuard};
+    use tempfile::{tempdir, TempDir};
+
+    /// The recent list is read from `TAB_RUNTIME_DIR`, so the tests run one at a time
+    static RUNTIME_DIR: Mutex<()> = Mutex::new(());
+
+    fn runtime_dir() -> (MutexGuard<'static, ()>, TempDir) {
+        let lock = RUNTIME_DIR.lock().unwrap_or_else(|err| err.into_inner());
+        let dir = tempdir().unwrap();
+        std::env::set_var("TAB_RUNTIME_DIR", dir.path());
+        (lock, dir)
+    }
+
+    #[test]
+    fn push_moves_to_front() {
+        let _dir = runtime_dir();
+        assert!(load().is_empty());
+
+        push("a/");
+        push("b/");
+        push("a/");
+
+        assert_eq!(vec!["a/", "b/"], load());
+    }
+
+    #[test]
+    fn push_truncates() {
+        let _dir = runtime_dir();
+
+        for index in 0..MAX_RECENT + 2 {
+            push(format!("{}/", index).as_str());
+        }
+
+        let recent = load();
+        assert_eq!(MAX_RECENT, recent.len());
+        assert_eq!(format!("{}/", MAX_RECENT + 1), recent[0]);
+    }
+
+    #[test]
+    fn rename_retains_position() {
+        let _dir = runtime_dir();
+        push("c/");
+        push("b/");
+        push("a/");
+
+        rename("b/", "c/");
+        assert_eq!(vec!["a/", "c/"], load());
+
+        rename("a/", "d/");
+        assert_eq!(vec!["d/", "c/"], load());
+    }
+
+    #[test]
+    fn previous_skips_current() {
+        let _dir = runtime_dir();
+        assert_eq!(None, previous(None));
+
+        push("a/");
+        push("b/");
+
+        assert_eq!(Some("b/".to_string()), previous(None));
+        assert_eq!(Some("a/".to_string()), previous(Some("b/")));
+        assert_eq!(Some("b/".to_string()), previous(Some("c/")));
+    }
+
+    #[test]
+    fn sort_recent_first() {
+        let _dir = runtime_dir();
+        push("c/");
+        push("a/");
+
+        let mut tabs = vec!["a/", "b/", "c/", "d/"];
+        sort(&mut tabs, |tab| tab);
+
+        // other tabs retain their order
+        assert_eq!(vec!["a/", "c/", "b/", "d/"], tabs);
+    }
+}
@@ -0,0 +1,41 @@
+//! Plays asciicast recordings to the terminal, for `tab --replay`.
+//! Replays don't need a running tab, so they are handled without launching the daemon.
+
+use anyhow::Context;
+use log::debug;
+use std::{path::Path, time::Duration};
+use tab_api::asciicast::{self, Event};
+use tokio::{
+    io::AsyncWriteExt,
+    time::{self, Instant},
+};
+
+/// Plays the output events of the recording to stdout, with the recorded timing.
+///
+/// Resize events are ignored.  The terminal belongs to the user, so the recording is played at its current size.
+pub async fn replay(path: &Path) -> anyhow::Result<()> {
+    let recording = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read the recording {}", path.to_string_lossy()))?;
+    let (_header, events) = asciicast::parse(recording.as_str())?;
+
+    let mut stdout = tokio::io::stdout();
+    let start = Instant::now();
+
+    for event in events {
+        let at = start + Duration::from_secs_f64(event.time().max(0.0));
+        time::delay_until(at).await;
+
+        match event {
+            Event::Output(_time, data) => {
+                stdout.write_all(data.as_bytes()).await?;
+                stdout.flush().await?;
+            }
+            Event::Resize(_time, dimensions) => {
+                debug!("ignoring a recorded resize to {:?}", dimensions);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -3,4 +3,4 @@ pub mod main;
 mod tab_state;
 mod tabs;
 pub mod terminal;
-mod workspace;
+pub mod workspace;
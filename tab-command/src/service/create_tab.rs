@@ -2,6 +2,7 @@ use crate::{
     message::tabs::CreateTabRequest,
     prelude::*,
     state::{
+        tab::TabState,
         tabs::TabsState,
         terminal::TerminalSizeState,
         workspace::{WorkspaceState, WorkspaceTab},
@@ -25,6 +26,7 @@ impl Service for CreateTabService {
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
         let mut rx = bus.rx::<CreateTabRequest>()?;
         let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+        let rx_tab_state = bus.rx::<TabState>()?.into_inner();
         let rx_terminal_size = bus.rx::<TerminalSizeState>()?.into_inner();
         let rx_workspace = bus.rx::<WorkspaceState>()?.into_inner();
         let mut tx_websocket = bus.tx::<Request>()?;
@@ -42,10 +44,15 @@ impl Service for CreateTabService {
 
                         if !tab_exists {
                             let workspace = Self::await_workspace(&rx_workspace).await;
+                            let live_directory = Self::live_directory(
+                                &rx_tab_state.borrow(),
+                                &rx_tabs_state.borrow(),
+                            );
 
                             Self::create_named(
                                 name,
                                 workspace,
+                                live_directory,
                                 &rx_terminal_size,
                                 &mut tx_websocket,
                             )
@@ -66,6 +73,7 @@ impl CreateTabService {
     pub async fn create_named(
         name: String,
         workspace: Vec<WorkspaceTab>,
+        live_directory: Option<PathBuf>,
         rx_terminal_size: &watch::Receiver<TerminalSizeState>,
         tx_websocket: &mut impl Sender<Request>,
     ) -> anyhow::Result<()> {
@@ -74,13 +82,38 @@ impl CreateTabService {
 
         let dimensions = rx_terminal_size.borrow().0.clone();
         let shell = std::env::var("SHELL").unwrap_or("/usr/bin/env bash".to_string());
-        let directory = Self::compute_directory(&workspace_tab)?;
+        let directory = Self::compute_directory(&workspace_tab, live_directory)?;
+        let record = workspace_tab
+            .as_ref()
+            .and_then(|tab| tab.record.as_ref())
+            .map(|path| path.to_string_lossy().to_string());
+        let log = workspace_tab
+            .as_ref()
+            .and_then(|tab| tab.log.as_ref())
+            .map(|path| path.to_string_lossy().to_string());
+        let hooks = workspace_tab
+            .as_ref()
+            .map(|tab| Box::new(tab.hooks.clone()))
+            .unwrap_or_default();
+        let tags = workspace_tab
+            .as_ref()
+            .map(|tab| tab.tags.clone())
+            .unwrap_or_default();
+        let idle = workspace_tab
+            .as_ref()
+            .map(|tab| Box::new(tab.idle.clone()))
+            .unwrap_or_default();
 
         let metadata = CreateTabMetadata {
             name: Self::compute_name(&workspace_tab, name.as_str()),
             dir: directory.to_string_lossy().to_string(),
             dimensions,
             shell,
+            record,
+            log,
+            hooks,
+            tags,
+            idle,
         };
 
         let request = Request::CreateTab(metadata);
@@ -89,7 +122,27 @@ impl CreateTabService {
         Ok(())
     }
 
-    fn compute_directory(tab: &Option<WorkspaceTab>) -> anyhow::Result<PathBuf> {
+    /// If the client is attached to a tab, returns the live working directory of that tab, as reported by the daemon.
+    fn live_directory(tab: &TabState, tabs: &TabsState) -> Option<PathBuf> {
+        let id = match tab {
+            TabState::Selected(id) => id,
+            _ => return None,
+        };
+
+        let process = tabs.tabs.get(id)?.process.as_ref()?;
+        let dir = PathBuf::from(process.cwd.as_str());
+
+        if dir.exists() {
+            Some(dir)
+        } else {
+            None
+        }
+    }
+
+    fn compute_directory(
+        tab: &Option<WorkspaceTab>,
+        live_directory: Option<PathBuf>,
+    ) -> anyhow::Result<PathBuf> {
         if let Some(ref tab) = tab {
             if tab.directory.exists() {
                 return Ok(tab.directory.clone());
@@ -103,6 +156,11 @@ impl CreateTabService {
             }
         }
 
+        // ad-hoc tabs inherit the live working directory of the attached tab
+        if let Some(dir) = live_directory {
+            return Ok(dir);
+        }
+
         std::env::current_dir().map_err(|err| err.into())
     }
 
@@ -116,7 +174,7 @@ impl CreateTabService {
 
     async fn await_workspace(rx_workspace: &watch::Receiver<WorkspaceState>) -> Vec<WorkspaceTab> {
         loop {
-            if let WorkspaceState::Ready(ref state) = *rx_workspace.borrow() {
+            if let WorkspaceState::Ready(ref state, _) = *rx_workspace.borrow() {
                 return state.clone();
             }
 
@@ -1,5 +1,6 @@
 use crate::{
     message::{client::TabTerminated, tabs::TabShutdown},
+    recent,
     state::tab::{SelectTab, TabState},
 };
 use crate::{prelude::*, state::terminal::TerminalSizeState};
@@ -8,7 +9,7 @@ use anyhow::Context;
 
 use std::collections::HashMap;
 use tab_api::tab::{TabId, TabMetadata};
-use tokio::{stream::StreamExt, sync::watch};
+use tokio::{stream::StreamExt, sync::watch, task};
 
 /// Tracks the current tab state, and updates TabState.
 pub struct TabStateService {
@@ -62,8 +63,14 @@ impl Service for TabStateService {
                             state = if let Some(id) = tabs.get(&name.to_string()) {
                                 info!("selected tab {}", name);
 
-                                Self::select_tab(*id, &rx_terminal_size, &mut tx, &mut tx_websocket)
-                                    .await?
+                                Self::select_tab(
+                                    *id,
+                                    Some(name.as_str()),
+                                    &rx_terminal_size,
+                                    &mut tx,
+                                    &mut tx_websocket,
+                                )
+                                .await?
                             } else {
                                 info!("awaiting tab {}", name);
                                 TabState::Awaiting(name.to_string())
@@ -75,9 +82,19 @@ impl Service for TabStateService {
                             if state.is_selected(&id) {
                                 continue;
                             }
-                            state =
-                                Self::select_tab(id, &rx_terminal_size, &mut tx, &mut tx_websocket)
-                                    .await?;
+                            let name = tabs
+                                .iter()
+                                .find(|(_, tab_id)| **tab_id == id)
+                                .map(|(name, _)| name.as_str());
+
+                            state = Self::select_tab(
+                                id,
+                                name,
+                                &rx_terminal_size,
+                                &mut tx,
+                                &mut tx_websocket,
+                            )
+                            .await?;
                         }
                     },
                     Event::Metadata(metadata) => {
@@ -86,6 +103,7 @@ impl Service for TabStateService {
 
                             state = Self::select_tab(
                                 metadata.id,
+                                Some(metadata.name.as_str()),
                                 &rx_terminal_size,
                                 &mut tx,
                                 &mut tx_websocket,
@@ -95,6 +113,9 @@ impl Service for TabStateService {
 
                         let id = metadata.id;
                         let name = metadata.name;
+
+                        // the tab may have been renamed
+                        tabs.retain(|_, tab_id| *tab_id != id);
                         tabs.insert(name, id);
                     }
                     Event::Terminated(terminated_id) => {
@@ -132,12 +153,19 @@ impl Service for TabStateService {
 impl TabStateService {
     pub async fn select_tab(
         id: TabId,
+        name: Option<&str>,
         rx_terminal_size: &watch::Receiver<TerminalSizeState>,
         tx_state: &mut impl Sender<TabState>,
         tx_websocket: &mut impl Sender<Request>,
     ) -> anyhow::Result<TabState> {
         tx_websocket.send(Request::Subscribe(id)).await?;
 
+        if let Some(name) = name {
+            // the recent list is a file, so it is updated on a blocking thread
+            let name = name.to_string();
+            task::spawn_blocking(move || recent::push(name.as_str())).await?;
+        }
+
         let terminal_size = rx_terminal_size.borrow().clone();
         tx_websocket
             .send(Request::ResizeTab(id, terminal_size.0))
@@ -19,29 +19,36 @@ impl Service for TabsStateService {
         let mut tx_metadata = bus.tx::<TabMetadata>()?;
         let _run = Self::try_task("run", async move {
             let mut state = HashMap::new();
+            let mut activity = HashMap::new();
 
             while let Some(recv) = rx.recv().await {
                 info!("{:?}", recv);
                 match recv {
-                    TabsRecv::Init(tabs) => {
+                    TabsRecv::Init(tabs, tab_activity) => {
                         for metadata in tabs.values() {
                             tx_metadata.send(metadata.clone()).await?;
                         }
 
                         state.extend(tabs.into_iter());
+                        activity.extend(tab_activity);
                     }
                     TabsRecv::Update(metadata) => {
                         state.insert(metadata.id, metadata.clone());
                         tx_metadata.send(metadata.clone()).await?;
                     }
+                    TabsRecv::Activity(id, tab_activity) => {
+                        activity.insert(id, tab_activity);
+                    }
                     TabsRecv::Terminated(id) => {
                         state.remove(&id);
+                        activity.remove(&id);
                     }
                 }
 
                 tx.send(TabsState {
                     initialized: true,
                     tabs: state.clone(),
+                    activity: activity.clone(),
                 })
                 .await?;
             }
@@ -1,19 +1,37 @@
 use crate::{
     prelude::*,
-    state::workspace::{Config, Repo, Workspace, WorkspaceItem, WorkspaceState, WorkspaceTab},
+    state::workspace::{
+        Config, GlobTab, Hooks, Idle, LayoutTab, Repo, RepoItem, Tab, Template, Workspace,
+        WorkspaceItem, WorkspaceLayout, WorkspaceState, WorkspaceTab,
+    },
 };
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use lifeline::Service;
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 use std::{
+    collections::{HashMap, HashSet},
+    fmt,
     fs::File,
     io::BufReader,
     path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
 };
-use tab_api::tab::normalize_name;
-use time::Duration;
-use tokio::time;
+use tab_api::{
+    config::config_path,
+    tab::{normalize_name, TabHooks, TabIdle},
+};
+use tokio::{sync::mpsc::unbounded_channel, task};
+
+mod check;
+
+pub use check::check_config;
 
-/// Loads the workspace configuration using the current directory
+/// The file names of workspace & repository configurations, in order of precedence
+const CONFIG_FILES: &[&str] = &["tab.yml", ".tab.yml", "tab.toml", ".tab.toml", "tab.json"];
+
+/// Loads the workspace configuration using the current directory,
+/// and reloads it when the configuration files change.
 pub struct WorkspaceService {
     _monitor: Lifeline,
 }
@@ -25,151 +43,557 @@ impl Service for WorkspaceService {
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
         let mut tx = bus.tx::<WorkspaceState>()?;
 
-        #[allow(unreachable_code)]
         let _monitor = Self::try_task("monitor", async move {
+            let (tx_notify, rx_notify) = mpsc::channel();
+            let mut watcher = notify::watcher(tx_notify, Duration::from_millis(250))?;
+
+            // the notify events are delivered to a std channel, so a thread forwards them to the task
+            let (tx_event, mut rx_event) = unbounded_channel();
+            std::thread::spawn(move || {
+                while let Ok(event) = rx_notify.recv() {
+                    if tx_event.send(event).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            let mut watched = HashSet::new();
+            let mut last_state = None;
+
             loop {
-                let state = load_state();
+                let loaded = task::spawn_blocking(load).await?;
+                watched = update_watches(&mut watcher, watched, loaded.watch);
 
-                if let Err(err) = state {
-                    error!("failed to load config: {:?}", err);
-                } else {
-                    let loader_state = state.unwrap();
-                    let tabs = tabs(loader_state);
-                    let state = WorkspaceState::Ready(tabs);
-                    tx.send(state).await.ok();
+                for problem in loaded.problems.iter() {
+                    warn!("{}", problem);
                 }
 
-                time::delay_for(Duration::from_millis(1000)).await;
-            }
+                match loaded.tabs {
+                    Ok(tabs) => {
+                        let state = (tabs, loaded.layouts);
+                        if last_state.as_ref() != Some(&state) {
+                            last_state = Some(state.clone());
+                            let (tabs, layouts) = state;
+                            tx.send(WorkspaceState::Ready(tabs, layouts)).await.ok();
+                        }
+                    }
+                    Err(err) => {
+                        error!("failed to load config: {:?}", err);
+                    }
+                }
 
-            Ok(())
+                loop {
+                    match rx_event.recv().await {
+                        Some(event) if needs_reload(&event, &loaded.files) => break,
+                        Some(_) => continue,
+                        None => return Ok(()),
+                    }
+                }
+            }
         });
 
         Ok(Self { _monitor })
     }
 }
 
+/// The loaded workspace tabs & layouts, and the paths which can affect them
+struct Loaded {
+    pub tabs: anyhow::Result<Vec<WorkspaceTab>>,
+
+    pub layouts: Vec<WorkspaceLayout>,
+
+    /// The directories which should be watched for configuration changes
+    pub watch: HashSet<PathBuf>,
+
+    /// The configuration files which were read
+    pub files: HashSet<PathBuf>,
+
+    /// Problems in the configuration, which did not prevent it from loading
+    pub problems: Vec<Problem>,
+}
+
+fn load() -> Loaded {
+    match std::env::current_dir() {
+        Ok(dir) => load_from(dir.as_path()),
+        Err(err) => Loaded {
+            tabs: Err(err.into()),
+            layouts: Vec::new(),
+            watch: HashSet::new(),
+            files: HashSet::new(),
+            problems: Vec::new(),
+        },
+    }
+}
+
+/// Loads the configuration which is reachable from the directory
+fn load_from(dir: &Path) -> Loaded {
+    let mut loader_state = LoaderState::default();
+    let tabs = load_state(dir, &mut loader_state).and_then(|_| tabs(&mut loader_state));
+    let layouts = layouts(&mut loader_state);
+
+    Loaded {
+        tabs,
+        layouts,
+        watch: loader_state.watch,
+        files: loader_state.files,
+        problems: loader_state.problems,
+    }
+}
+
+/// A problem in the configuration, which doesn't prevent the workspace from loading
+struct Problem {
+    /// The configuration file which contains the problem, if known
+    pub file: Option<PathBuf>,
+
+    /// The key & value of the configuration entry which caused the problem, used to locate it within the file
+    pub entry: Option<(&'static str, String)>,
+
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new(file: Option<&Path>, entry: Option<(&'static str, &str)>, message: String) -> Self {
+        Self {
+            file: file.map(Path::to_path_buf),
+            entry: entry.map(|(key, value)| (key, value.to_string())),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref file) = self.file {
+            write!(f, "{}: ", file.to_string_lossy())?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Watches the new directories, and unwatches the directories which are no longer needed.
+/// Returns the set of directories that are being watched.
+fn update_watches(
+    watcher: &mut RecommendedWatcher,
+    watched: HashSet<PathBuf>,
+    dirs: HashSet<PathBuf>,
+) -> HashSet<PathBuf> {
+    let mut updated = HashSet::new();
+
+    for dir in watched.into_iter() {
+        if dirs.contains(&dir) {
+            updated.insert(dir);
+        } else {
+            watcher.unwatch(dir.as_path()).ok();
+        }
+    }
+
+    for dir in dirs.into_iter() {
+        if updated.contains(&dir) || !dir.is_dir() {
+            continue;
+        }
+
+        match watcher.watch(dir.as_path(), RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                updated.insert(dir);
+            }
+            Err(err) => debug!("failed to watch {}: {}", dir.to_string_lossy(), err),
+        }
+    }
+
+    updated
+}
+
+/// Determines whether the filesystem event may change the workspace tabs
+fn needs_reload(event: &DebouncedEvent, files: &HashSet<PathBuf>) -> bool {
+    match event {
+        DebouncedEvent::Create(_)
+        | DebouncedEvent::Remove(_)
+        | DebouncedEvent::Rename(_, _)
+        | DebouncedEvent::Rescan => true,
+        DebouncedEvent::Write(path) | DebouncedEvent::Chmod(path) => {
+            let is_config = path
+                .file_name()
+                .map(|name| CONFIG_FILES.iter().any(|config| name == *config))
+                .unwrap_or(false);
+
+            is_config
+                || path
+                    .canonicalize()
+                    .map(|path| files.contains(&path))
+                    .unwrap_or(false)
+        }
+        DebouncedEvent::NoticeWrite(_) | DebouncedEvent::NoticeRemove(_) => false,
+        DebouncedEvent::Error(err, path) => {
+            warn!("config watch error for {:?}: {}", path, err);
+            false
+        }
+    }
+}
+
+#[derive(Default)]
 struct LoaderState {
     pub repos: Vec<(PathBuf, Repo)>,
 
     /// The list of compiled tabs
     pub tabs: Vec<WorkspaceTab>,
 
+    /// The layouts, starting with the innermost configuration
+    pub layouts: Vec<WorkspaceLayout>,
+
     /// The hierarchy of workspaces, starting with the innermost
     pub workspaces: Vec<Workspace>,
-}
 
-fn load_state() -> anyhow::Result<LoaderState> {
-    let mut loader_state = LoaderState {
-        repos: Vec::new(),
-        tabs: Vec::new(),
-        workspaces: Vec::new(),
-    };
+    /// The configuration files which have been loaded, which prevents include cycles
+    pub loaded: HashSet<PathBuf>,
+
+    /// Every configuration file which has been read, including linked workspaces & repositories
+    pub files: HashSet<PathBuf>,
 
-    let init_dir = std::env::current_dir()?;
-    let mut working_dir: Option<&Path> = Some(init_dir.as_path());
+    /// The directories in which new or changed files may affect the configuration
+    pub watch: HashSet<PathBuf>,
+
+    pub problems: Vec<Problem>,
+}
+
+fn load_state(init_dir: &Path, loader_state: &mut LoaderState) -> anyhow::Result<()> {
+    let mut working_dir: Option<&Path> = Some(init_dir);
 
     while let Some(dir) = working_dir {
-        let config = load_yml(dir);
-        if let Some(config) = config {
-            let config = config.context(dir.to_string_lossy().to_string())?;
-            match config {
-                Config::Workspace(workspace) => {
-                    load_items(dir, &workspace, &mut loader_state)?;
-                    loader_state.workspaces.push(workspace);
+        loader_state.watch.insert(dir.to_path_buf());
+
+        if let Some(path) = find_config(dir) {
+            load_config(path.as_path(), true, loader_state)?;
+        }
+
+        working_dir = dir.parent();
+    }
+
+    // the global workspace is available in every directory, and has the lowest priority
+    if let Ok(dir) = config_path() {
+        if let Some(parent) = dir.parent() {
+            loader_state.watch.insert(parent.to_path_buf());
+        }
+
+        loader_state.watch.insert(dir.clone());
+
+        if let Some(path) = find_config(dir.as_path()) {
+            load_config(path.as_path(), false, loader_state)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads the configuration file, and any files it includes.
+/// If `implicit_tab` is false, a workspace only creates a tab for itself if it is named.
+fn load_config(path: &Path, implicit_tab: bool, target: &mut LoaderState) -> anyhow::Result<()> {
+    let canonical = path
+        .canonicalize()
+        .context(path.to_string_lossy().to_string())?;
+
+    if target.loaded.contains(&canonical) {
+        return Ok(());
+    }
+
+    target.loaded.insert(canonical.clone());
+    target.files.insert(canonical.clone());
+    let dir = canonical
+        .parent()
+        .ok_or_else(|| anyhow!("invalid config path: {}", path.to_string_lossy()))?;
+    target.watch.insert(dir.to_path_buf());
+    check_conflicts(dir, target);
+
+    let config = load_file(path).context(path.to_string_lossy().to_string())?;
+    match config {
+        Config::Workspace(workspace) => {
+            if implicit_tab || workspace.tab.is_some() {
+                if let Some(tab) = workspace_tab(dir, &workspace) {
+                    target.tabs.push(tab);
                 }
-                Config::Repo(repo) => {
-                    let repo_path = dir.to_path_buf();
-                    loader_state.repos.push((repo_path, repo));
+            }
+
+            push_layouts(&workspace.layouts, target);
+
+            load_items(canonical.as_path(), &workspace, target)
+                .with_context(|| canonical.to_string_lossy().to_string())?;
+
+            for include in workspace.include.iter().flat_map(|i| i.iter()) {
+                target.watch.insert(glob_base(dir, include.as_str()));
+                for path in expand_include(dir, include.as_str())? {
+                    load_config(path.as_path(), false, target)?;
                 }
             }
+
+            target.workspaces.push(workspace);
+        }
+        Config::Repo(repo) => {
+            push_repo(dir.to_path_buf(), repo, target);
         }
+    }
 
-        working_dir = dir.parent();
+    Ok(())
+}
+
+fn push_repo(path: PathBuf, repo: Repo, target: &mut LoaderState) {
+    push_layouts(&repo.layouts, target);
+
+    for item in repo.tabs.iter().flat_map(|t| t.iter()) {
+        if let RepoItem::Glob(glob) = item {
+            target
+                .watch
+                .insert(glob_base(path.as_path(), glob.glob.as_str()));
+        }
     }
 
-    Ok(loader_state)
+    target.repos.push((path, repo));
 }
 
-fn load_yml(dir: &Path) -> Option<anyhow::Result<Config>> {
-    let mut path_buf = dir.to_owned();
-    path_buf.push("tab.yml");
+fn push_layouts(layouts: &Option<HashMap<String, Vec<LayoutTab>>>, target: &mut LoaderState) {
+    let layouts = match layouts {
+        Some(layouts) => layouts,
+        None => return,
+    };
+
+    let mut names: Vec<&String> = layouts.keys().collect();
+    names.sort();
+
+    for name in names {
+        let tabs = layouts[name]
+            .iter()
+            .map(|tab| LayoutTab {
+                tab: normalize_name(tab.tab.as_str()),
+                command: tab.command.clone(),
+            })
+            .collect();
+
+        target.layouts.push(WorkspaceLayout {
+            name: name.clone(),
+            tabs,
+        });
+    }
+}
+
+/// The deepest directory which contains all of the glob matches.
+/// New matches are created within this directory.
+fn glob_base(dir: &Path, pattern: &str) -> PathBuf {
+    let mut base = dir.to_path_buf();
+
+    for component in Path::new(pattern).components() {
+        if component
+            .as_os_str()
+            .to_string_lossy()
+            .contains(&['*', '?', '['][..])
+        {
+            return base;
+        }
 
-    if path_buf.is_file() {
-        return Some(load_file(path_buf.as_path()));
+        base.push(component);
     }
 
-    let mut path_buf = dir.to_owned();
-    path_buf.push(".tab.yml");
+    // without any wildcards, the pattern is a path which may not exist yet
+    base.parent().map(Path::to_path_buf).unwrap_or(base)
+}
+
+/// Resolves the include path or glob to configuration files, relative to the including directory
+fn expand_include(dir: &Path, include: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let pattern = dir.join(include);
+    let pattern = pattern
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid include: {}", include))?;
+
+    let mut paths = Vec::new();
+    for entry in glob::glob(pattern).context(include.to_string())? {
+        let matched = entry?;
+
+        if matched.is_dir() {
+            if let Some(path) = find_config(matched.as_path()) {
+                paths.push(path);
+            }
+        } else {
+            paths.push(matched);
+        }
+    }
 
-    if path_buf.is_file() {
-        return Some(load_file(path_buf.as_path()));
+    let is_glob = include.contains(&['*', '?', '['][..]);
+    if paths.is_empty() && !is_glob {
+        return Err(anyhow!("included config not found: {}", include));
+    }
+
+    Ok(paths)
+}
+
+/// Returns the path to the configuration file in the directory, if one exists
+fn find_config(dir: &Path) -> Option<PathBuf> {
+    for file in CONFIG_FILES {
+        let mut path_buf = dir.to_owned();
+        path_buf.push(file);
+
+        if path_buf.is_file() {
+            return Some(path_buf);
+        }
     }
 
     None
 }
 
+/// Reports a problem if the directory contains more than one configuration file
+fn check_conflicts(dir: &Path, target: &mut LoaderState) {
+    let files: Vec<&str> = CONFIG_FILES
+        .iter()
+        .copied()
+        .filter(|file| dir.join(file).is_file())
+        .collect();
+
+    if files.len() < 2 {
+        return;
+    }
+
+    let message = format!(
+        "conflicting configuration files in {}: {}, using {}",
+        dir.to_string_lossy(),
+        files.join(", "),
+        files[0]
+    );
+
+    // the directory can be reached more than once, through links, includes, and the parent directories
+    if target
+        .problems
+        .iter()
+        .any(|problem| problem.message == message)
+    {
+        return;
+    }
+
+    let file = dir.join(files[0]).canonicalize().ok();
+    target
+        .problems
+        .push(Problem::new(file.as_deref(), None, message));
+}
+
+/// Loads the configuration file in a linked directory
+fn load_dir(dir: &Path, target: &mut LoaderState) -> Option<anyhow::Result<Config>> {
+    check_conflicts(dir, target);
+
+    let path = find_config(dir)?;
+    if let Ok(canonical) = path.canonicalize() {
+        target.files.insert(canonical);
+    }
+
+    Some(load_file(path.as_path()).with_context(|| path.to_string_lossy().to_string()))
+}
+
+/// Loads the configuration file, using the parser for the file extension
 fn load_file(path: &Path) -> anyhow::Result<Config> {
-    // TODO: figure out how to get rid fo the blocking IO
-    let reader = File::open(path)?;
-    let buf_reader = BufReader::new(reader);
-    let config = serde_yaml::from_reader(buf_reader)?;
+    let extension = path.extension().and_then(|extension| extension.to_str());
+
+    let config = match extension {
+        Some("toml") => toml::from_str(std::fs::read_to_string(path)?.as_str())?,
+        Some("json") => serde_json::from_reader(BufReader::new(File::open(path)?))?,
+        _ => serde_yaml::from_reader(BufReader::new(File::open(path)?))?,
+    };
+
     Ok(config)
 }
 
-fn load_items(path: &Path, workspace: &Workspace, target: &mut LoaderState) -> anyhow::Result<()> {
-    if let Some(tab) = workspace_tab(path, workspace) {
-        target.tabs.push(tab);
-    }
+/// Loads the items of the workspace configuration `file`
+fn load_items(file: &Path, workspace: &Workspace, target: &mut LoaderState) -> anyhow::Result<()> {
+    let path = file
+        .parent()
+        .ok_or_else(|| anyhow!("invalid config path: {}", file.to_string_lossy()))?;
 
     for item in workspace.workspace.iter() {
         match item {
             WorkspaceItem::Workspace(link) => {
                 let mut workspace_path = path.to_path_buf();
                 workspace_path.push(link.workspace.as_str());
+                target.watch.insert(workspace_path.clone());
 
-                if let Some(workspace) = load_yml(workspace_path.as_path()) {
-                    let workspace = workspace?;
-                    if let Config::Workspace(workspace) = workspace {
-                        if let Some(tab) = workspace_tab(workspace_path.as_path(), &workspace) {
-                            target.tabs.push(tab);
+                let entry = Some(("workspace", link.workspace.as_str()));
+                match load_dir(workspace_path.as_path(), target) {
+                    Some(workspace) => {
+                        let workspace = workspace?;
+                        if let Config::Workspace(workspace) = workspace {
+                            if let Some(tab) = workspace_tab(workspace_path.as_path(), &workspace) {
+                                target.tabs.push(tab);
+                            }
+                        } else {
+                            let message = format!(
+                                "the linked workspace is a repository configuration: {}",
+                                link.workspace
+                            );
+                            target
+                                .problems
+                                .push(Problem::new(Some(file), entry, message));
                         }
                     }
+                    None => {
+                        let message = format!(
+                            "the linked workspace has no configuration file: {}",
+                            workspace_path.to_string_lossy()
+                        );
+                        target
+                            .problems
+                            .push(Problem::new(Some(file), entry, message));
+                    }
                 }
             }
 
             WorkspaceItem::Repo(repo) => {
                 let mut repo_path = path.to_path_buf();
                 repo_path.push(repo.repo.as_str());
-                if let Some(repo) = load_yml(repo_path.as_path()) {
-                    let repo = repo?;
-                    if let Config::Repo(repo) = repo {
-                        target.repos.push((repo_path, repo));
+                target.watch.insert(repo_path.clone());
+
+                let entry = Some(("repo", repo.repo.as_str()));
+                if let Some(config) = load_dir(repo_path.as_path(), target) {
+                    if let Config::Repo(repo) = config? {
+                        push_repo(repo_path, repo, target);
+                    } else {
+                        let message = format!(
+                            "the linked repository is a workspace configuration: {}",
+                            repo.repo
+                        );
+                        target
+                            .problems
+                            .push(Problem::new(Some(file), entry, message));
                     }
                 } else if repo_path.exists() {
                     let tab = WorkspaceTab {
                         name: normalize_name(repo.repo.as_str()),
                         directory: repo_path,
                         doc: "".to_string(),
+                        record: None,
+                        log: None,
+                        hooks: TabHooks::default(),
+                        tags: Vec::new(),
+                        idle: TabIdle::default(),
                     };
 
                     target.tabs.push(tab);
+                } else {
+                    let message = format!(
+                        "the linked repository does not exist: {}",
+                        repo_path.to_string_lossy()
+                    );
+                    target
+                        .problems
+                        .push(Problem::new(Some(file), entry, message));
                 }
             }
 
-            WorkspaceItem::Tab(tab) => {
-                let mut directory = path.to_path_buf();
-
-                if let Some(ref dir) = tab.dir {
-                    directory.push(dir);
+            WorkspaceItem::Glob(glob) => {
+                target.watch.insert(glob_base(path, glob.glob.as_str()));
+                for tab in expand_glob(path, glob, &workspace.templates)? {
+                    let tab = workspace_item_tab(path, tab);
+                    check_directory(&tab, file, ("glob", glob.glob.as_str()), target);
+                    target.tabs.push(tab);
                 }
+            }
 
-                let tab = WorkspaceTab {
-                    name: normalize_name(tab.tab.as_str()),
-                    directory,
-                    doc: tab.doc.as_ref().unwrap_or(&"".to_string()).clone(),
-                };
-
+            WorkspaceItem::Tab(item) => {
+                let tab = instantiate(item, &workspace.templates, HashMap::new())?;
+                let tab = workspace_item_tab(path, tab);
+                check_directory(&tab, file, ("tab", item.tab.as_str()), target);
                 target.tabs.push(tab);
             }
         }
@@ -178,11 +602,221 @@ fn load_items(path: &Path, workspace: &Workspace, target: &mut LoaderState) -> a
     Ok(())
 }
 
+/// Reports a problem if the tab directory does not exist
+fn check_directory(
+    tab: &WorkspaceTab,
+    file: &Path,
+    entry: (&'static str, &str),
+    target: &mut LoaderState,
+) {
+    if tab.directory.is_dir() {
+        return;
+    }
+
+    let message = format!(
+        "the directory for tab {} does not exist: {}",
+        tab.name,
+        tab.directory.to_string_lossy()
+    );
+    target
+        .problems
+        .push(Problem::new(Some(file), Some(entry), message));
+}
+
+fn workspace_item_tab(path: &Path, tab: Tab) -> WorkspaceTab {
+    let mut directory = path.to_path_buf();
+
+    if let Some(ref dir) = tab.dir {
+        directory.push(dir);
+    }
+
+    WorkspaceTab {
+        name: normalize_name(tab.tab.as_str()),
+        directory,
+        doc: tab.doc.unwrap_or_default(),
+        record: tab.record.map(|record| path.join(record)),
+        log: tab.log.map(|log| path.join(log)),
+        hooks: tab.hooks.into(),
+        tags: tab.tags.unwrap_or_default(),
+        idle: tab.idle.into(),
+    }
+}
+
+/// Applies the tab's template, and substitutes the `{param}` placeholders in the tab fields
+fn instantiate(
+    tab: &Tab,
+    templates: &Option<HashMap<String, Template>>,
+    mut params: HashMap<String, String>,
+) -> anyhow::Result<Tab> {
+    let template = match tab.template {
+        Some(ref name) => templates
+            .as_ref()
+            .and_then(|templates| templates.get(name))
+            .ok_or_else(|| anyhow!("tab {} uses an unknown template: {}", tab.tab, name))?,
+        None => &EMPTY_TEMPLATE,
+    };
+
+    if let Some(ref tab_params) = tab.params {
+        params.extend(tab_params.clone());
+    }
+
+    let field = |value: &Option<String>, default: &Option<String>| {
+        value
+            .as_ref()
+            .or(default.as_ref())
+            .map(|value| substitute(value.as_str(), &params))
+            .transpose()
+    };
+
+    Ok(Tab {
+        tab: substitute(tab.tab.as_str(), &params)?,
+        doc: field(&tab.doc, &template.doc)?,
+        dir: field(&tab.dir, &template.dir)?,
+        record: field(&tab.record, &template.record)?,
+        log: field(&tab.log, &template.log)?,
+        template: None,
+        params: None,
+        tags: tab
+            .tags
+            .as_ref()
+            .or(template.tags.as_ref())
+            .map(|tags| {
+                tags.iter()
+                    .map(|tag| substitute(tag.as_str(), &params))
+                    .collect::<anyhow::Result<Vec<String>>>()
+            })
+            .transpose()?,
+        hooks: Hooks {
+            on_create: field(&tab.hooks.on_create, &template.hooks.on_create)?,
+            on_attach: field(&tab.hooks.on_attach, &template.hooks.on_attach)?,
+            on_detach: field(&tab.hooks.on_detach, &template.hooks.on_detach)?,
+            on_close: field(&tab.hooks.on_close, &template.hooks.on_close)?,
+        },
+        // the idle policy is checked by `tab --check-config`, so it is not substituted
+        idle: Idle {
+            idle_timeout: tab
+                .idle
+                .idle_timeout
+                .clone()
+                .or_else(|| template.idle.idle_timeout.clone()),
+            idle_action: tab
+                .idle
+                .idle_action
+                .clone()
+                .or_else(|| template.idle.idle_action.clone()),
+        },
+    })
+}
+
+const EMPTY_TEMPLATE: Template = Template {
+    doc: None,
+    dir: None,
+    record: None,
+    log: None,
+    tags: None,
+    hooks: Hooks {
+        on_create: None,
+        on_attach: None,
+        on_detach: None,
+        on_close: None,
+    },
+    idle: Idle {
+        idle_timeout: None,
+        idle_action: None,
+    },
+};
+
+/// Replaces `{param}` placeholders with the parameter values
+fn substitute(value: &str, params: &HashMap<String, String>) -> anyhow::Result<String> {
+    let mut substituted = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed placeholder in: {}", value))?;
+
+        let param = &rest[start + 1..start + end];
+        let param_value = params
+            .get(param)
+            .ok_or_else(|| anyhow!("unknown parameter {{{}}} in: {}", param, value))?;
+
+        substituted += &rest[..start];
+        substituted += param_value;
+        rest = &rest[start + end + 1..];
+    }
+
+    substituted += rest;
+    Ok(substituted)
+}
+
+/// Generates a tab for each directory matching the glob, relative to the configuration directory
+fn expand_glob(
+    path: &Path,
+    glob: &GlobTab,
+    templates: &Option<HashMap<String, Template>>,
+) -> anyhow::Result<Vec<Tab>> {
+    let pattern = path.join(glob.glob.as_str());
+    let pattern = pattern
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid glob: {}", glob.glob))?;
+
+    let mut tabs = Vec::new();
+    for entry in glob::glob(pattern).context(glob.glob.clone())? {
+        let matched = entry?;
+        if !matched.is_dir() {
+            continue;
+        }
+
+        let name = match matched.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => continue,
+        };
+
+        let dir = matched
+            .strip_prefix(path)
+            .unwrap_or(matched.as_path())
+            .to_string_lossy()
+            .to_string();
+
+        let mut params = HashMap::new();
+        params.insert("name".to_string(), name);
+        params.insert("dir".to_string(), dir.clone());
+
+        let tab = Tab {
+            tab: glob.tab.clone().unwrap_or_else(|| "{name}".to_string()),
+            doc: glob.doc.clone(),
+            dir: glob.dir.clone(),
+            record: glob.record.clone(),
+            log: glob.log.clone(),
+            template: glob.template.clone(),
+            params: glob.params.clone(),
+            tags: glob.tags.clone(),
+            hooks: glob.hooks.clone(),
+            idle: glob.idle.clone(),
+        };
+
+        let mut tab = instantiate(&tab, templates, params)?;
+        if tab.dir.is_none() {
+            tab.dir = Some(dir);
+        }
+
+        tabs.push(tab);
+    }
+
+    Ok(tabs)
+}
+
 fn workspace_tab(path: &Path, workspace: &Workspace) -> Option<WorkspaceTab> {
     workspace_tab_name(path, &workspace).map(|name| WorkspaceTab {
         name: normalize_name(name.as_str()),
         directory: path.to_owned(),
         doc: workspace_tab_doc(path, workspace),
+        record: None,
+        log: None,
+        hooks: workspace.hooks.clone().into(),
+        tags: Vec::new(),
+        idle: workspace.idle.clone().into(),
     })
 }
 
@@ -207,11 +841,12 @@ fn workspace_tab_doc(path: &Path, workspace: &Workspace) -> String {
     }
 }
 
-fn tabs(mut loader: LoaderState) -> Vec<WorkspaceTab> {
+fn tabs(loader: &mut LoaderState) -> anyhow::Result<Vec<WorkspaceTab>> {
     let mut tabs = Vec::new();
     tabs.append(&mut loader.tabs);
 
-    for (path, repo) in loader.repos.into_iter() {
+    for (path, repo) in std::mem::take(&mut loader.repos).into_iter() {
+        let file = find_config(path.as_path()).and_then(|file| file.canonicalize().ok());
         let repo_name = normalize_name(repo.repo.as_str());
 
         // push a tab for the repo
@@ -219,11 +854,36 @@ fn tabs(mut loader: LoaderState) -> Vec<WorkspaceTab> {
             name: repo_name.clone(),
             directory: path.clone(),
             doc: repo.doc.unwrap_or("".to_string()),
+            record: None,
+            log: None,
+            hooks: repo.hooks.into(),
+            tags: Vec::new(),
+            idle: repo.idle.into(),
         };
         tabs.push(tab);
 
         // and then for any tabs the user defined
-        for tab in repo.tabs.into_iter().flat_map(|t| t.into_iter()) {
+        let context = || path.to_string_lossy().to_string();
+        let mut repo_tabs = Vec::new();
+        for item in repo.tabs.iter().flat_map(|t| t.iter()) {
+            match item {
+                RepoItem::Glob(glob) => {
+                    let glob_tabs =
+                        expand_glob(path.as_path(), glob, &repo.templates).with_context(context)?;
+                    for tab in glob_tabs {
+                        repo_tabs.push((("glob", glob.glob.as_str()), tab));
+                    }
+                }
+                RepoItem::Tab(tab) => {
+                    let entry = ("tab", tab.tab.as_str());
+                    let tab =
+                        instantiate(tab, &repo.templates, HashMap::new()).with_context(context)?;
+                    repo_tabs.push((entry, tab))
+                }
+            }
+        }
+
+        for (entry, tab) in repo_tabs.into_iter() {
             let mut directory = path.to_path_buf();
             if let Some(subdir) = tab.dir {
                 directory.push(subdir);
@@ -236,11 +896,547 @@ fn tabs(mut loader: LoaderState) -> Vec<WorkspaceTab> {
                 name: tab_name,
                 directory,
                 doc: tab.doc.unwrap_or("".to_string()), // command: tab.command,
+                record: tab.record.map(|record| path.join(record)),
+                log: tab.log.map(|log| path.join(log)),
+                hooks: tab.hooks.into(),
+                tags: tab.tags.unwrap_or_default(),
+                idle: tab.idle.into(),
             };
 
+            if let Some(ref file) = file {
+                check_directory(&tab, file.as_path(), entry, loader);
+            }
+
             tabs.push(tab);
         }
     }
 
-    tabs
+    // the same configuration can be reached more than once, through links, includes, and the parent directories
+    let mut seen = HashSet::new();
+    tabs.retain(|tab| seen.insert((tab.name.clone(), tab.directory.clone())));
+
+    // if different tabs share a name, the first (innermost) definition is used
+    for collision in collisions(tabs.as_slice()) {
+        loader.problems.push(Problem::new(None, None, collision));
+    }
+
+    let mut names = HashSet::new();
+    tabs.retain(|tab| names.insert(tab.name.clone()));
+
+    Ok(tabs)
+}
+
+/// Removes duplicate layouts.  If different layouts share a name, the first (innermost) definition is used
+fn layouts(loader: &mut LoaderState) -> Vec<WorkspaceLayout> {
+    let mut layouts: Vec<WorkspaceLayout> = Vec::new();
+
+    for layout in std::mem::take(&mut loader.layouts) {
+        match layouts.iter().find(|existing| existing.name == layout.name) {
+            Some(existing) if *existing != layout => {
+                let message = format!(
+                    "layout name collision: {} has more than one definition, using the innermost",
+                    layout.name
+                );
+                loader.problems.push(Problem::new(None, None, message));
+            }
+            Some(_) => {}
+            None => layouts.push(layout),
+        }
+    }
+
+    layouts
+}
+
+/// Describes the workspace tabs which share a name, but have different directories
+fn collisions(tabs: &[WorkspaceTab]) -> Vec<String> {
+    let mut first: HashMap<&str, &WorkspaceTab> = HashMap::new();
+    let mut collisions = Vec::new();
+
+    for tab in tabs {
+        match first.get(tab.name.as_str()) {
+            Some(existing) if existing.directory != tab.directory => {
+                collisions.push(format!(
+                    "tab name collision: {} is defined in {} and {}, using {}",
+                    tab.name,
+                    existing.directory.to_string_lossy(),
+                    tab.directory.to_string_lossy(),
+                    existing.directory.to_string_lossy(),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                first.insert(tab.name.as_str(), tab);
+            }
+        }
+    }
+
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_glob, instantiate, load_from, needs_reload, substitute, Loaded};
+    use crate::state::workspace::{GlobTab, Tab, Template, WorkspaceTab};
+    use notify::DebouncedEvent;
+    use std::{
+        collections::{HashMap, HashSet},
+        fs::create_dir_all,
+        path::{Path, PathBuf},
+        sync::Mutex,
+    };
+    use tempfile::{tempdir, TempDir};
+
+    /// The loader reads the global workspace from `TAB_CONFIG_DIR`, so the loader tests run one at a time
+    static CONFIG_DIR: Mutex<()> = Mutex::new(());
+
+    /// A temporary directory tree of configuration files
+    struct Tree {
+        _dir: TempDir,
+        root: PathBuf,
+    }
+
+    impl Tree {
+        pub fn new() -> Self {
+            let dir = tempdir().unwrap();
+            let root = dir.path().canonicalize().unwrap();
+            Self { _dir: dir, root }
+        }
+
+        pub fn path(&self, path: &str) -> PathBuf {
+            self.root.join(path)
+        }
+
+        pub fn write(&self, path: &str, contents: &str) -> &Self {
+            let path = self.path(path);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+            self
+        }
+
+        /// Loads the configuration from the directory, with the `global` directory as the global workspace
+        pub fn load(&self, dir: &str, global: &str) -> Loaded {
+            let _lock = CONFIG_DIR.lock().unwrap_or_else(|err| err.into_inner());
+            create_dir_all(self.path(global)).unwrap();
+            std::env::set_var("TAB_CONFIG_DIR", self.path(global));
+
+            load_from(self.path(dir).as_path())
+        }
+    }
+
+    fn names(tabs: &[WorkspaceTab]) -> Vec<&str> {
+        let mut names: Vec<&str> = tabs.iter().map(|tab| tab.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    fn find<'a>(tabs: &'a [WorkspaceTab], name: &str) -> &'a WorkspaceTab {
+        tabs.iter()
+            .find(|tab| tab.name == name)
+            .unwrap_or_else(|| panic!("tab {} should be loaded", name))
+    }
+
+    fn problems(loaded: &Loaded) -> Vec<String> {
+        loaded
+            .problems
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect()
+    }
+
+    fn display(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    fn params(params: &[(&str, &str)]) -> HashMap<String, String> {
+        params
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn templates(yaml: &str) -> Option<HashMap<String, Template>> {
+        Some(serde_yaml::from_str(yaml).expect("the templates should parse"))
+    }
+
+    #[test]
+    fn substitute_params() {
+        let params = params(&[("name", "api"), ("dir", "services/api")]);
+
+        assert_eq!(
+            "api in services/api",
+            substitute("{name} in {dir}", &params).unwrap()
+        );
+        assert_eq!("plain", substitute("plain", &params).unwrap());
+    }
+
+    #[test]
+    fn substitute_unclosed() {
+        let err = substitute("{name", &params(&[("name", "api")])).unwrap_err();
+        assert_eq!("unclosed placeholder in: {name", err.to_string());
+    }
+
+    #[test]
+    fn substitute_unknown_param() {
+        let err = substitute("{name}-{port}", &params(&[("name", "api")])).unwrap_err();
+        assert_eq!(
+            "unknown parameter {port} in: {name}-{port}",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn instantiate_template_defaults() {
+        let templates = templates(
+            "service:\n  doc: the {svc} service\n  dir: services/{svc}\n  tags: [\"svc-{svc}\"]\n  on_close: stop {svc}\n  idle_timeout: 2h\n",
+        );
+        let tab: Tab =
+            serde_yaml::from_str("tab: api\ntemplate: service\nparams:\n  svc: api\ndir: api\n")
+                .unwrap();
+
+        let tab = instantiate(&tab, &templates, HashMap::new()).unwrap();
+
+        assert_eq!("api", tab.tab);
+        assert_eq!(Some("the api service".to_string()), tab.doc);
+        // fields set on the tab override the template
+        assert_eq!(Some("api".to_string()), tab.dir);
+        assert_eq!(Some(vec!["svc-api".to_string()]), tab.tags);
+        assert_eq!(Some("stop api".to_string()), tab.hooks.on_close);
+        assert_eq!(None, tab.hooks.on_create);
+        assert_eq!(
+            Some(7200),
+            tab.idle.idle_timeout.as_ref().and_then(|t| t.seconds())
+        );
+        assert!(tab.template.is_none());
+        assert!(tab.params.is_none());
+    }
+
+    #[test]
+    fn instantiate_unknown_template() {
+        let tab: Tab = serde_yaml::from_str("tab: api\ntemplate: service\n").unwrap();
+
+        let err = instantiate(&tab, &None, HashMap::new()).unwrap_err();
+        assert_eq!("tab api uses an unknown template: service", err.to_string());
+    }
+
+    #[test]
+    fn instantiate_unknown_param() {
+        let tab: Tab = serde_yaml::from_str("tab: api\ndoc: the {svc} service\n").unwrap();
+
+        let err = instantiate(&tab, &None, HashMap::new()).unwrap_err();
+        assert_eq!(
+            "unknown parameter {svc} in: the {svc} service",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn expand_glob_dirs() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join("packages/cli")).unwrap();
+        create_dir_all(dir.path().join("packages/core")).unwrap();
+        std::fs::write(dir.path().join("packages/README.md"), "").unwrap();
+
+        let glob: GlobTab =
+            serde_yaml::from_str("glob: packages/*\ndoc: the {name} package in {dir}\n").unwrap();
+        let tabs = expand_glob(dir.path(), &glob, &None).unwrap();
+
+        // files are skipped, and the tab name & directory default to `{name}` and `{dir}`
+        let tabs: Vec<(&str, Option<&str>, Option<&str>)> = tabs
+            .iter()
+            .map(|tab| (tab.tab.as_str(), tab.dir.as_deref(), tab.doc.as_deref()))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "cli",
+                    Some("packages/cli"),
+                    Some("the cli package in packages/cli")
+                ),
+                (
+                    "core",
+                    Some("packages/core"),
+                    Some("the core package in packages/core")
+                ),
+            ],
+            tabs
+        );
+    }
+
+    #[test]
+    fn expand_glob_template() {
+        let dir = tempdir().unwrap();
+        create_dir_all(dir.path().join("services/api")).unwrap();
+
+        let templates = templates("service:\n  dir: \"{dir}/src\"\n  tags: [\"{name}\"]\n");
+        let glob: GlobTab =
+            serde_yaml::from_str("glob: services/*\ntab: svc-{name}\ntemplate: service\n").unwrap();
+        let tabs = expand_glob(dir.path(), &glob, &templates).unwrap();
+
+        assert_eq!(1, tabs.len());
+        assert_eq!("svc-api", tabs[0].tab);
+        assert_eq!(Some("services/api/src".to_string()), tabs[0].dir);
+        assert_eq!(Some(vec!["api".to_string()]), tabs[0].tags);
+    }
+
+    #[test]
+    fn include_cycle() {
+        let tree = Tree::new();
+        tree.write(
+            "root/tab.yml",
+            "workspace:\n  - tab: root-tab\ninclude: [other]\n",
+        )
+        .write(
+            "root/other/tab.yml",
+            "workspace:\n  - tab: other-tab\ninclude: [\"..\"]\n",
+        );
+
+        let loaded = tree.load("root", "global");
+        let tabs = loaded.tabs.as_ref().unwrap();
+
+        // the included workspace doesn't create a tab for itself, unless it is named
+        assert_eq!(vec!["other-tab/", "root-tab/", "root/"], names(tabs));
+        assert_eq!(tree.path("root/other"), find(tabs, "other-tab/").directory);
+        assert!(problems(&loaded).is_empty(), "{:?}", problems(&loaded));
+    }
+
+    #[test]
+    fn include_glob() {
+        let tree = Tree::new();
+        tree.write(
+            "root/tab.yml",
+            "workspace: []\ninclude: [\"services/*.yml\", projects/*]\n",
+        )
+        .write("root/services/api.yml", "workspace:\n  - tab: api\n")
+        .write("root/services/db.yml", "workspace:\n  - tab: db\n")
+        .write("root/projects/web/tab.yml", "workspace:\n  - tab: web\n");
+
+        let loaded = tree.load("root", "global");
+        let tabs = loaded.tabs.as_ref().unwrap();
+
+        assert_eq!(vec!["api/", "db/", "root/", "web/"], names(tabs));
+        assert_eq!(tree.path("root/services"), find(tabs, "db/").directory);
+        assert_eq!(tree.path("root/projects/web"), find(tabs, "web/").directory);
+
+        // new files which match the globs are loaded when they are created
+        assert!(loaded.watch.contains(&tree.path("root/services")));
+        assert!(loaded.watch.contains(&tree.path("root/projects")));
+        assert!(loaded.files.contains(&tree.path("root/services/api.yml")));
+    }
+
+    #[test]
+    fn include_not_found() {
+        let tree = Tree::new();
+        tree.write(
+            "root/tab.yml",
+            "workspace: []\ninclude: [missing, \"optional/*\"]\n",
+        );
+
+        let loaded = tree.load("root", "global");
+
+        let err = loaded.tabs.err().unwrap();
+        assert_eq!("included config not found: missing", err.to_string());
+    }
+
+    #[test]
+    fn global_workspace_lowest_priority() {
+        let tree = Tree::new();
+        tree.write(
+            "global/tab.yml",
+            "workspace:\n  - tab: shared\n    doc: global\n  - tab: global-only\n",
+        )
+        .write(
+            "root/tab.yml",
+            "workspace:\n  - tab: shared\n    doc: local\n",
+        );
+
+        let loaded = tree.load("root", "global");
+        let tabs = loaded.tabs.as_ref().unwrap();
+
+        assert_eq!(vec!["global-only/", "root/", "shared/"], names(tabs));
+        assert_eq!("local", find(tabs, "shared/").doc);
+        assert_eq!(
+            vec![format!(
+                "tab name collision: shared/ is defined in {} and {}, using {}",
+                display(&tree.path("root")),
+                display(&tree.path("global")),
+                display(&tree.path("root")),
+            )],
+            problems(&loaded)
+        );
+    }
+
+    #[test]
+    fn repo_reached_twice() {
+        let tree = Tree::new();
+        tree.write(
+            "tab.yml",
+            "workspace:\n  - repo: project\n  - tab: docs\n    dir: project/docs\n",
+        )
+        .write(
+            "project/tab.yml",
+            "repo: project\ntabs:\n  - tab: docs\n    dir: docs\n",
+        )
+        .write("project/docs/README.md", "");
+
+        let loaded = tree.load("project", "global");
+        let tabs = loaded.tabs.as_ref().unwrap();
+
+        // the repository is reached through the current directory and the parent workspace, and is only loaded once
+        let name = tree.root.file_name().unwrap().to_string_lossy().to_string() + "/";
+        let mut expected = vec!["docs/", "project/", "project/docs/", name.as_str()];
+        expected.sort();
+        assert_eq!(expected, names(tabs));
+        assert!(problems(&loaded).is_empty(), "{:?}", problems(&loaded));
+    }
+
+    #[test]
+    fn repo_collisions() {
+        let tree = Tree::new();
+        tree.write("root/tab.yml", "workspace:\n  - repo: a\n  - repo: b\n")
+            .write("root/a/tab.yml", "repo: app\n")
+            .write("root/b/tab.yml", "repo: app\n");
+
+        let loaded = tree.load("root", "global");
+        let tabs = loaded.tabs.as_ref().unwrap();
+
+        assert_eq!(vec!["app/", "root/"], names(tabs));
+        assert_eq!(tree.path("root/a"), find(tabs, "app/").directory);
+        assert_eq!(
+            vec![format!(
+                "tab name collision: app/ is defined in {} and {}, using {}",
+                display(&tree.path("root/a")),
+                display(&tree.path("root/b")),
+                display(&tree.path("root/a")),
+            )],
+            problems(&loaded)
+        );
+    }
+
+    #[test]
+    fn reload_events() {
+        let tree = Tree::new();
+        tree.write("tab.yml", "workspace: []\n")
+            .write("services.yml", "workspace: []\n")
+            .write("README.md", "");
+
+        // an included file, which doesn't have a configuration file name
+        let mut files = HashSet::new();
+        files.insert(tree.path("services.yml"));
+
+        let cases = vec![
+            (DebouncedEvent::Write(tree.path("tab.yml")), true),
+            (DebouncedEvent::Write(tree.path("other/.tab.toml")), true),
+            (DebouncedEvent::Write(tree.path("services.yml")), true),
+            (DebouncedEvent::Write(tree.path("README.md")), false),
+            (DebouncedEvent::Chmod(tree.path("tab.yml")), true),
+            (DebouncedEvent::Chmod(tree.path("README.md")), false),
+            (DebouncedEvent::Create(tree.path("README.md")), true),
+            (DebouncedEvent::Remove(tree.path("README.md")), true),
+            (
+                DebouncedEvent::Rename(tree.path("README.md"), tree.path("tab.yml")),
+                true,
+            ),
+            (DebouncedEvent::Rescan, true),
+            (DebouncedEvent::NoticeWrite(tree.path("tab.yml")), false),
+            (DebouncedEvent::NoticeRemove(tree.path("tab.yml")), false),
+        ];
+
+        for (event, expected) in cases {
+            assert_eq!(expected, needs_reload(&event, &files), "{:?}", event);
+        }
+    }
+
+    /// Loads the tabs, with paths relative to the directory
+    fn relative_tabs(tree: &Tree, dir: &str) -> Vec<WorkspaceTab> {
+        let base = tree.path(dir);
+        let relative = |path: &Path| path.strip_prefix(base.as_path()).unwrap().to_path_buf();
+
+        let mut tabs = tree.load(dir, "global").tabs.unwrap();
+        for tab in tabs.iter_mut() {
+            tab.directory = relative(tab.directory.as_path());
+            tab.record = tab.record.as_deref().map(relative);
+            tab.log = tab.log.as_deref().map(relative);
+        }
+
+        tabs.sort_by(|a, b| a.name.cmp(&b.name));
+        tabs
+    }
+
+    #[test]
+    fn toml_and_json() {
+        let tree = Tree::new();
+        tree.write(
+            "toml/app/tab.toml",
+            r#"
+repo = "app"
+doc = "the app"
+idle_timeout = "2h"
+
+[templates.service]
+dir = "{svc}"
+on_close = "stop {svc}"
+
+[[tabs]]
+tab = "api"
+template = "service"
+params = { svc = "api" }
+tags = ["backend"]
+
+[[tabs]]
+glob = "packages/*"
+log = "logs/{name}.log"
+"#,
+        )
+        .write(
+            "json/app/tab.json",
+            r#"{
+  "repo": "app",
+  "doc": "the app",
+  "idle_timeout": "2h",
+  "templates": {
+    "service": { "dir": "{svc}", "on_close": "stop {svc}" }
+  },
+  "tabs": [
+    { "tab": "api", "template": "service", "params": { "svc": "api" }, "tags": ["backend"] },
+    { "glob": "packages/*", "log": "logs/{name}.log" }
+  ]
+}"#,
+        );
+
+        for dir in &["toml/app", "json/app"] {
+            create_dir_all(tree.path(dir).join("api")).unwrap();
+            create_dir_all(tree.path(dir).join("packages/web")).unwrap();
+        }
+
+        let tabs = relative_tabs(&tree, "toml/app");
+        assert_eq!(vec!["app/", "app/api/", "app/web/"], names(tabs.as_slice()));
+        assert_eq!("the app", tabs[0].doc);
+        assert_eq!(Some(7200), tabs[0].idle.timeout);
+        assert_eq!(PathBuf::from("api"), tabs[1].directory);
+        assert_eq!(Some("stop api".to_string()), tabs[1].hooks.on_close);
+        assert_eq!(vec!["backend".to_string()], tabs[1].tags);
+        assert_eq!(Some(PathBuf::from("logs/web.log")), tabs[2].log);
+
+        assert_eq!(tabs, relative_tabs(&tree, "json/app"));
+    }
+
+    #[test]
+    fn conflicting_files() {
+        let tree = Tree::new();
+        tree.write("app/tab.yml", "repo: app\n")
+            .write("app/tab.toml", "repo = \"other\"\n");
+
+        let loaded = tree.load("app", "global");
+
+        // the YAML file takes precedence
+        assert_eq!(vec!["app/"], names(loaded.tabs.as_ref().unwrap()));
+        assert_eq!(
+            vec![format!(
+                "{}: conflicting configuration files in {}: tab.yml, tab.toml, using tab.yml",
+                display(&tree.path("app/tab.yml")),
+                display(&tree.path("app")),
+            )],
+            problems(&loaded)
+        );
+    }
 }
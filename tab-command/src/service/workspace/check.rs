@@ -0,0 +1,908 @@
+//! Validates the workspace configuration files, for `tab --check-config`.
+//!
+//! The configuration is deserialized with untagged enums, which ignore unknown keys,
+//! and report mismatched items without a location.  This module walks the YAML events directly,
+//! so it can report the line & column of each problem.  JSON files are parsed as YAML,
+//! and TOML files are checked without positions.
+
+use super::load;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+use tab_api::tab::{parse_duration, IdleAction};
+use yaml_rust::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+};
+
+/// Checks the configuration files that are reachable from the current directory, and prints any problems.
+/// Returns true if the configuration is valid.
+pub fn check_config() -> bool {
+    let loaded = load();
+
+    let mut files: Vec<PathBuf> = loaded.files.into_iter().collect();
+    files.sort();
+
+    if files.is_empty() {
+        println!("No tab configuration files were found.");
+        return true;
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut documents = Vec::new();
+    for file in files.iter() {
+        let mut checker = Checker::new(file.as_path());
+        let document = checker.check_file();
+
+        diagnostics.append(&mut checker.diagnostics);
+        documents.push((file.as_path(), document));
+    }
+
+    // if the files are structurally valid, the loader may still fail (e.g. an unknown template)
+    if let Err(err) = loaded.tabs {
+        if diagnostics.is_empty() {
+            diagnostics.push(Diagnostic {
+                file: None,
+                position: None,
+                message: format!("{:#}", err),
+            });
+        }
+    }
+
+    for problem in loaded.problems.into_iter() {
+        let document = documents
+            .iter()
+            .find(|(file, _)| Some(*file) == problem.file.as_deref())
+            .and_then(|(_, document)| document.as_ref());
+
+        let position = match (document, problem.entry.as_ref()) {
+            (Some(document), Some((key, value))) => locate(document, key, value.as_str()),
+            _ => None,
+        };
+
+        diagnostics.push(Diagnostic {
+            file: problem.file,
+            position,
+            message: problem.message,
+        });
+    }
+
+    diagnostics.sort_by_key(|diagnostic| {
+        (
+            diagnostic.file.is_none(),
+            diagnostic.file.clone(),
+            diagnostic.position,
+        )
+    });
+
+    for diagnostic in diagnostics.iter() {
+        println!("{}", diagnostic);
+    }
+
+    let noun = if files.len() == 1 { "file" } else { "files" };
+    match diagnostics.len() {
+        0 => {
+            println!(
+                "No problems found in {} configuration {}.",
+                files.len(),
+                noun
+            );
+            true
+        }
+        1 => {
+            println!("Found 1 problem.");
+            false
+        }
+        problems => {
+            println!("Found {} problems.", problems);
+            false
+        }
+    }
+}
+
+/// A configuration problem, with the position of the problem in the file
+struct Diagnostic {
+    file: Option<PathBuf>,
+    position: Option<Position>,
+    message: String,
+}
+
+/// A 1-based line & column within a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl From<Marker> for Position {
+    fn from(marker: Marker) -> Self {
+        // markers have 1-based lines, and 0-based columns
+        Self {
+            line: marker.line(),
+            column: marker.col() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(ref file) = self.file {
+            write!(f, "{}", file.to_string_lossy())?;
+
+            if let Some(position) = self.position {
+                write!(f, ":{}:{}", position.line, position.column)?;
+            }
+
+            write!(f, ": ")?;
+        }
+
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A configuration node, with the position where it starts if it is known
+enum Node {
+    Scalar(String, Option<Position>),
+    Sequence(Vec<Node>, Option<Position>),
+    Mapping(Vec<(Node, Node)>, Option<Position>),
+    Alias(Option<Position>),
+}
+
+impl Node {
+    /// Converts a parsed TOML value, which doesn't have positions
+    pub fn from_toml(value: toml::Value) -> Self {
+        match value {
+            toml::Value::Table(table) => {
+                let entries = table
+                    .into_iter()
+                    .map(|(key, value)| (Node::Scalar(key, None), Node::from_toml(value)))
+                    .collect();
+                Node::Mapping(entries, None)
+            }
+            toml::Value::Array(items) => {
+                Node::Sequence(items.into_iter().map(Node::from_toml).collect(), None)
+            }
+            toml::Value::String(value) => Node::Scalar(value, None),
+            value => Node::Scalar(value.to_string(), None),
+        }
+    }
+
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            // block mappings are marked after the first key, so the key is a better position
+            Node::Mapping(entries, position) => entries
+                .first()
+                .and_then(|(key, _value)| key.position())
+                .or(*position),
+            Node::Scalar(_, position) | Node::Sequence(_, position) | Node::Alias(position) => {
+                *position
+            }
+        }
+    }
+
+    pub fn scalar(&self) -> Option<&str> {
+        match self {
+            Node::Scalar(value, _) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Node::Scalar(_, _) => "a value",
+            Node::Sequence(_, _) => "a list",
+            Node::Mapping(_, _) => "a map",
+            Node::Alias(_) => "an alias",
+        }
+    }
+}
+
+/// Builds a tree of marked nodes from the parser events
+#[derive(Default)]
+struct TreeBuilder {
+    /// The open sequences & mappings, with the pending key of each mapping
+    stack: Vec<(Node, Option<Node>)>,
+    root: Option<Node>,
+}
+
+impl TreeBuilder {
+    fn push(&mut self, node: Node) {
+        match self.stack.last_mut() {
+            None => {
+                if self.root.is_none() {
+                    self.root = Some(node);
+                }
+            }
+            Some((Node::Sequence(items, _), _)) => items.push(node),
+            Some((Node::Mapping(entries, _), key)) => match key.take() {
+                Some(key) => entries.push((key, node)),
+                None => *key = Some(node),
+            },
+            Some(_) => {}
+        }
+    }
+}
+
+impl MarkedEventReceiver for TreeBuilder {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        let marker = Some(Position::from(marker));
+        match event {
+            Event::Scalar(value, _, _, _) => self.push(Node::Scalar(value, marker)),
+            Event::Alias(_) => self.push(Node::Alias(marker)),
+            Event::SequenceStart(_) => self.stack.push((Node::Sequence(Vec::new(), marker), None)),
+            Event::MappingStart(_) => self.stack.push((Node::Mapping(Vec::new(), marker), None)),
+            Event::SequenceEnd | Event::MappingEnd => {
+                if let Some((node, _key)) = self.stack.pop() {
+                    self.push(node);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The expected type of a configuration value
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Strings,
+    Params,
+    Templates,
+    WorkspaceItems,
+    RepoItems,
+    Layouts,
+    Duration,
+    IdleAction,
+}
+
+struct Field {
+    name: &'static str,
+    kind: Kind,
+    required: bool,
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, kind: Kind) -> Field {
+    Field {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+// these match the fields of the configuration structs, in `state/workspace.rs`
+const WORKSPACE: &[Field] = &[
+    field("tab", Kind::String),
+    field("doc", Kind::String),
+    field("templates", Kind::Templates),
+    required("workspace", Kind::WorkspaceItems),
+    field("include", Kind::Strings),
+    field("layouts", Kind::Layouts),
+    field("on_create", Kind::String),
+    field("on_attach", Kind::String),
+    field("on_detach", Kind::String),
+    field("on_close", Kind::String),
+    field("idle_timeout", Kind::Duration),
+    field("idle_action", Kind::IdleAction),
+];
+
+const REPO: &[Field] = &[
+    required("repo", Kind::String),
+    field("doc", Kind::String),
+    field("templates", Kind::Templates),
+    field("tabs", Kind::RepoItems),
+    field("layouts", Kind::Layouts),
+    field("on_create", Kind::String),
+    field("on_attach", Kind::String),
+    field("on_detach", Kind::String),
+    field("on_close", Kind::String),
+    field("idle_timeout", Kind::Duration),
+    field("idle_action", Kind::IdleAction),
+];
+
+const WORKSPACE_LINK: &[Field] = &[required("workspace", Kind::String)];
+
+const REPO_LINK: &[Field] = &[required("repo", Kind::String)];
+
+const TAB: &[Field] = &[
+    required("tab", Kind::String),
+    field("doc", Kind::String),
+    field("dir", Kind::String),
+    field("record", Kind::String),
+    field("log", Kind::String),
+    field("template", Kind::String),
+    field("params", Kind::Params),
+    field("tags", Kind::Strings),
+    field("on_create", Kind::String),
+    field("on_attach", Kind::String),
+    field("on_detach", Kind::String),
+    field("on_close", Kind::String),
+    field("idle_timeout", Kind::Duration),
+    field("idle_action", Kind::IdleAction),
+];
+
+const GLOB: &[Field] = &[
+    required("glob", Kind::String),
+    field("tab", Kind::String),
+    field("doc", Kind::String),
+    field("dir", Kind::String),
+    field("record", Kind::String),
+    field("log", Kind::String),
+    field("template", Kind::String),
+    field("params", Kind::Params),
+    field("tags", Kind::Strings),
+    field("on_create", Kind::String),
+    field("on_attach", Kind::String),
+    field("on_detach", Kind::String),
+    field("on_close", Kind::String),
+    field("idle_timeout", Kind::Duration),
+    field("idle_action", Kind::IdleAction),
+];
+
+const TEMPLATE: &[Field] = &[
+    field("doc", Kind::String),
+    field("dir", Kind::String),
+    field("record", Kind::String),
+    field("log", Kind::String),
+    field("tags", Kind::Strings),
+    field("on_create", Kind::String),
+    field("on_attach", Kind::String),
+    field("on_detach", Kind::String),
+    field("on_close", Kind::String),
+    field("idle_timeout", Kind::Duration),
+    field("idle_action", Kind::IdleAction),
+];
+
+const LAYOUT_TAB: &[Field] = &[
+    required("tab", Kind::String),
+    field("command", Kind::String),
+];
+
+/// Validates the structure of a configuration file
+struct Checker<'a> {
+    file: &'a Path,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(file: &'a Path) -> Self {
+        Self {
+            file,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Parses & checks the file, returning the document if it could be parsed
+    pub fn check_file(&mut self) -> Option<Node> {
+        let text = match std::fs::read_to_string(self.file) {
+            Ok(text) => text,
+            Err(err) => {
+                self.error(None, format!("failed to read the file: {}", err));
+                return None;
+            }
+        };
+
+        self.check_text(text.as_str())
+    }
+
+    /// Parses & checks the contents of the file, in the format given by the file extension
+    pub fn check_text(&mut self, text: &str) -> Option<Node> {
+        let extension = self.file.extension().and_then(|ext| ext.to_str());
+        let root = match extension {
+            Some("toml") => self.parse_toml(text)?,
+            // JSON is a subset of YAML, so the YAML parser provides positions for JSON files
+            Some("json") => self.parse_yaml(text, "JSON")?,
+            _ => self.parse_yaml(text, "YAML")?,
+        };
+
+        match root {
+            Some(document) => {
+                self.config(&document);
+                Some(document)
+            }
+            None => {
+                self.error(None, "the configuration file is empty".to_string());
+                None
+            }
+        }
+    }
+
+    fn parse_yaml(&mut self, text: &str, format: &str) -> Option<Option<Node>> {
+        let mut builder = TreeBuilder::default();
+
+        if let Err(err) = Parser::new(text.chars()).load(&mut builder, false) {
+            let message = format!("invalid {}: {}", format, without_position(err.to_string()));
+            self.error(Some(Position::from(*err.marker())), message);
+            return None;
+        }
+
+        Some(builder.root)
+    }
+
+    fn parse_toml(&mut self, text: &str) -> Option<Option<Node>> {
+        match text.parse::<toml::Value>() {
+            Ok(value) => Some(Some(Node::from_toml(value))),
+            Err(err) => {
+                // toml has 0-based lines & columns
+                let position = err.line_col().map(|(line, column)| Position {
+                    line: line + 1,
+                    column: column + 1,
+                });
+
+                let message = format!("invalid TOML: {}", without_position(err.to_string()));
+                self.error(position, message);
+                None
+            }
+        }
+    }
+
+    fn config(&mut self, node: &Node) {
+        if has_key(node, "workspace") {
+            self.fields(node, "workspace configuration", WORKSPACE);
+        } else if has_key(node, "repo") {
+            self.fields(node, "repository configuration", REPO);
+        } else {
+            self.error(
+                node.position(),
+                "expected a workspace configuration with a `workspace:` key, or a repository configuration with a `repo:` key".to_string(),
+            );
+        }
+    }
+
+    fn workspace_item(&mut self, node: &Node) {
+        if has_key(node, "workspace") {
+            self.fields(node, "workspace link", WORKSPACE_LINK);
+        } else if has_key(node, "repo") {
+            self.fields(node, "repository link", REPO_LINK);
+        } else if has_key(node, "glob") {
+            self.fields(node, "glob tab", GLOB);
+        } else if has_key(node, "tab") {
+            self.fields(node, "tab", TAB);
+        } else {
+            self.error(
+                node.position(),
+                "expected a workspace item with a `tab:`, `glob:`, `repo:`, or `workspace:` key"
+                    .to_string(),
+            );
+        }
+    }
+
+    fn repo_item(&mut self, node: &Node) {
+        if has_key(node, "glob") {
+            self.fields(node, "glob tab", GLOB);
+        } else if has_key(node, "tab") {
+            self.fields(node, "tab", TAB);
+        } else {
+            self.error(
+                node.position(),
+                "expected a repository tab with a `tab:` or `glob:` key".to_string(),
+            );
+        }
+    }
+
+    /// Checks the keys & values of the mapping against the fields
+    fn fields(&mut self, node: &Node, name: &str, fields: &[Field]) {
+        let entries = match node {
+            Node::Mapping(entries, _) => entries,
+            node => {
+                let message = format!(
+                    "expected the {} to be a map, found {}",
+                    name,
+                    node.describe()
+                );
+                self.error(node.position(), message);
+                return;
+            }
+        };
+
+        for (key, value) in entries.iter() {
+            let key_name = match key.scalar() {
+                Some(key_name) => key_name,
+                None => {
+                    let message =
+                        format!("expected a key in the {}, found {}", name, key.describe());
+                    self.error(key.position(), message);
+                    continue;
+                }
+            };
+
+            match fields.iter().find(|field| field.name == key_name) {
+                Some(field) => self.value(value, field),
+                None => {
+                    let message = unknown_key(key_name, name, fields);
+                    self.error(key.position(), message);
+                }
+            }
+        }
+
+        for field in fields.iter().filter(|field| field.required) {
+            if !has_key(node, field.name) {
+                let message = format!("the {} is missing the `{}:` key", name, field.name);
+                self.error(node.position(), message);
+            }
+        }
+    }
+
+    fn value(&mut self, node: &Node, field: &Field) {
+        match (field.kind, node) {
+            (Kind::String, Node::Scalar(_, _)) => {}
+            (Kind::Strings, Node::Sequence(items, _)) => {
+                for item in items.iter().filter(|item| item.scalar().is_none()) {
+                    let message = format!(
+                        "expected the `{}:` entries to be values, found {}",
+                        field.name,
+                        item.describe()
+                    );
+                    self.error(item.position(), message);
+                }
+            }
+            (Kind::Params, Node::Mapping(entries, _)) => {
+                for (_key, value) in entries.iter().filter(|(_, value)| value.scalar().is_none()) {
+                    let message = format!(
+                        "expected the `{}:` entries to be values, found {}",
+                        field.name,
+                        value.describe()
+                    );
+                    self.error(value.position(), message);
+                }
+            }
+            (Kind::Templates, Node::Mapping(entries, _)) => {
+                for (_name, template) in entries.iter() {
+                    self.fields(template, "template", TEMPLATE);
+                }
+            }
+            (Kind::WorkspaceItems, Node::Sequence(items, _)) => {
+                for item in items.iter() {
+                    self.workspace_item(item);
+                }
+            }
+            (Kind::RepoItems, Node::Sequence(items, _)) => {
+                for item in items.iter() {
+                    self.repo_item(item);
+                }
+            }
+            (Kind::Layouts, Node::Mapping(entries, _)) => {
+                for (name, layout) in entries.iter() {
+                    match layout {
+                        Node::Sequence(items, _) => {
+                            for item in items.iter() {
+                                self.fields(item, "layout tab", LAYOUT_TAB);
+                            }
+                        }
+                        node => {
+                            let message = format!(
+                                "expected layout `{}` to be a list of tabs, found {}",
+                                name.scalar().unwrap_or_default(),
+                                node.describe()
+                            );
+                            self.error(node.position(), message);
+                        }
+                    }
+                }
+            }
+            (Kind::Duration, Node::Scalar(value, position)) => {
+                if parse_duration(value).is_none() {
+                    let message = format!(
+                        "expected `{}:` to be a duration, like `90`, `30m`, or `2h`, found `{}`",
+                        field.name, value
+                    );
+                    self.error(*position, message);
+                }
+            }
+            (Kind::IdleAction, Node::Scalar(value, position)) => {
+                if IdleAction::parse(value).is_none() {
+                    let message = format!(
+                        "expected `{}:` to be `close` or `stale`, found `{}`",
+                        field.name, value
+                    );
+                    self.error(*position, message);
+                }
+            }
+            (kind, node) => {
+                let expected = match kind {
+                    Kind::String | Kind::Duration | Kind::IdleAction => "a value",
+                    Kind::Strings | Kind::WorkspaceItems | Kind::RepoItems => "a list",
+                    Kind::Params | Kind::Templates | Kind::Layouts => "a map",
+                };
+
+                let message = format!(
+                    "expected `{}:` to be {}, found {}",
+                    field.name,
+                    expected,
+                    node.describe()
+                );
+                self.error(node.position(), message);
+            }
+        }
+    }
+
+    fn error(&mut self, position: Option<Position>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            file: Some(self.file.to_path_buf()),
+            position,
+            message,
+        });
+    }
+}
+
+fn has_key(node: &Node, key: &str) -> bool {
+    match node {
+        Node::Mapping(entries, _) => entries.iter().any(|(k, _)| k.scalar() == Some(key)),
+        _ => false,
+    }
+}
+
+/// Describes an unknown key, suggesting a similar key if there is one
+fn unknown_key(key: &str, name: &str, fields: &[Field]) -> String {
+    let similar = fields
+        .iter()
+        .map(|field| (edit_distance(key, field.name), field.name))
+        .filter(|(distance, _)| *distance <= 2)
+        .min();
+
+    if let Some((_, similar)) = similar {
+        return format!(
+            "unknown key `{}` in the {}, did you mean `{}`?",
+            key, name, similar
+        );
+    }
+
+    let expected: Vec<String> = fields
+        .iter()
+        .map(|field| format!("`{}`", field.name))
+        .collect();
+
+    format!(
+        "unknown key `{}` in the {}, expected one of {}",
+        key,
+        name,
+        expected.join(", ")
+    )
+}
+
+/// The Levenshtein distance between the strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Removes the position from a parser error message, as it is reported separately
+fn without_position(message: String) -> String {
+    match message.rfind(" at line ") {
+        Some(index) => message[..index].to_string(),
+        None => message,
+    }
+}
+
+/// Finds the first mapping entry with the key & value, and returns the position of the key
+fn locate(node: &Node, key: &str, value: &str) -> Option<Position> {
+    match node {
+        Node::Mapping(entries, _) => entries.iter().find_map(|(k, v)| {
+            if k.scalar() == Some(key) && v.scalar() == Some(value) {
+                k.position()
+            } else {
+                locate(v, key, value)
+            }
+        }),
+        Node::Sequence(items, _) => items.iter().find_map(|item| locate(item, key, value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{edit_distance, locate, Checker, Field, Node, Position, TreeBuilder};
+    use super::{GLOB, LAYOUT_TAB, REPO, REPO_LINK, TAB, TEMPLATE, WORKSPACE, WORKSPACE_LINK};
+    use crate::state::workspace::config_schema;
+    use std::{collections::BTreeSet, path::Path};
+    use yaml_rust::parser::Parser;
+
+    /// Checks the text as the named file, and returns the formatted diagnostics
+    fn check(file: &str, text: &str) -> Vec<String> {
+        let mut checker = Checker::new(Path::new(file));
+        checker.check_text(text);
+        checker
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect()
+    }
+
+    fn parse(text: &str) -> Node {
+        let mut builder = TreeBuilder::default();
+        Parser::new(text.chars())
+            .load(&mut builder, false)
+            .expect("the yaml should parse");
+        builder.root.expect("the yaml should have a document")
+    }
+
+    #[test]
+    fn fields_match_schema() {
+        let schema: serde_json::Value =
+            serde_json::from_str(config_schema().as_str()).expect("the schema should parse");
+
+        let definitions: &[(&str, &[Field])] = &[
+            ("Workspace", WORKSPACE),
+            ("WorkspaceLink", WORKSPACE_LINK),
+            ("WorkspaceRepoLink", REPO_LINK),
+            ("Repo", REPO),
+            ("Tab", TAB),
+            ("GlobTab", GLOB),
+            ("Template", TEMPLATE),
+            ("LayoutTab", LAYOUT_TAB),
+        ];
+
+        for (definition, fields) in definitions.iter() {
+            let schema = &schema["definitions"][definition];
+
+            let properties: BTreeSet<&str> = schema["properties"]
+                .as_object()
+                .expect("the definition should have properties")
+                .keys()
+                .map(String::as_str)
+                .collect();
+            let names: BTreeSet<&str> = fields.iter().map(|field| field.name).collect();
+            assert_eq!(properties, names, "the fields of {}", definition);
+
+            let required: BTreeSet<&str> = schema["required"]
+                .as_array()
+                .map(|required| required.iter().filter_map(|key| key.as_str()).collect())
+                .unwrap_or_default();
+            let required_fields: BTreeSet<&str> = fields
+                .iter()
+                .filter(|field| field.required)
+                .map(|field| field.name)
+                .collect();
+            assert_eq!(
+                required, required_fields,
+                "the required fields of {}",
+                definition
+            );
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let text = "repo: proj\ntabs:\n  - tab: run\n    dir: src\n    idle_timeout: 2h\n";
+        assert!(check("tab.yml", text).is_empty());
+    }
+
+    #[test]
+    fn typo_suggestion() {
+        let text = "repo: proj\ntabz:\n  - tab: run\n";
+        assert_eq!(
+            vec!["tab.yml:2:1: unknown key `tabz` in the repository configuration, did you mean `tabs`?"],
+            check("tab.yml", text)
+        );
+    }
+
+    #[test]
+    fn key_at_wrong_level() {
+        let text = "repo: proj\nlayouts:\n  dev:\n    - tab: proj/run/\n      doc: runs the app\n";
+        assert_eq!(
+            vec!["tab.yml:5:7: unknown key `doc` in the layout tab, expected one of `tab`, `command`"],
+            check("tab.yml", text)
+        );
+    }
+
+    #[test]
+    fn missing_required_key() {
+        let text = "repo: proj\nlayouts:\n  dev:\n    - command: make\n";
+        assert_eq!(
+            vec!["tab.yml:4:7: the layout tab is missing the `tab:` key"],
+            check("tab.yml", text)
+        );
+    }
+
+    #[test]
+    fn unknown_item() {
+        let text = "workspace:\n  - dir: src\n";
+        assert_eq!(
+            vec!["tab.yml:2:5: expected a workspace item with a `tab:`, `glob:`, `repo:`, or `workspace:` key"],
+            check("tab.yml", text)
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let text = "repo: proj\ntabs:\n  - tab: run\n    idle_timeout: soon\n    idle_action: delete\n    tags: tag\n";
+        assert_eq!(
+            vec![
+                "tab.yml:4:19: expected `idle_timeout:` to be a duration, like `90`, `30m`, or `2h`, found `soon`",
+                "tab.yml:5:18: expected `idle_action:` to be `close` or `stale`, found `delete`",
+                "tab.yml:6:11: expected `tags:` to be a list, found a value",
+            ],
+            check("tab.yml", text)
+        );
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        let diagnostics = check("tab.yml", "repo: proj\ntabs: [\n");
+        assert_eq!(1, diagnostics.len());
+        assert!(
+            diagnostics[0].starts_with("tab.yml:3:1: invalid YAML: "),
+            "{}",
+            diagnostics[0]
+        );
+    }
+
+    #[test]
+    fn json_positions() {
+        let text =
+            "{\n  \"repo\": \"proj\",\n  \"tabs\": [{ \"tab\": \"run\", \"dri\": \"src\" }]\n}\n";
+        assert_eq!(
+            vec!["tab.json:3:28: unknown key `dri` in the tab, did you mean `dir`?"],
+            check("tab.json", text)
+        );
+    }
+
+    #[test]
+    fn toml_without_positions() {
+        let text = "repo = \"proj\"\n\n[[tabs]]\ntabz = \"run\"\n";
+        assert_eq!(
+            vec!["tab.toml: expected a repository tab with a `tab:` or `glob:` key"],
+            check("tab.toml", text)
+        );
+    }
+
+    #[test]
+    fn invalid_toml() {
+        let diagnostics = check("tab.toml", "repo = \"proj\"\ntabs = [\n");
+        assert_eq!(1, diagnostics.len());
+        assert!(
+            diagnostics[0].starts_with("tab.toml:3:1: invalid TOML: "),
+            "{}",
+            diagnostics[0]
+        );
+    }
+
+    #[test]
+    fn empty_file() {
+        assert_eq!(
+            vec!["tab.yml: the configuration file is empty"],
+            check("tab.yml", "")
+        );
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(0, edit_distance("tab", "tab"));
+        assert_eq!(1, edit_distance("tabz", "tabs"));
+        assert_eq!(1, edit_distance("tab", "tabs"));
+        assert_eq!(2, edit_distance("dri", "dir"));
+        assert_eq!(3, edit_distance("", "doc"));
+    }
+
+    #[test]
+    fn locate_entry() {
+        let document = parse("repo: proj\ntabs:\n  - tab: build\n  - tab: run\n");
+
+        assert_eq!(
+            Some(Position { line: 4, column: 5 }),
+            locate(&document, "tab", "run")
+        );
+        assert_eq!(None, locate(&document, "tab", "test"));
+    }
+}
@@ -1,11 +1,13 @@
 use std::collections::HashMap;
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{TabActivity, TabId, TabMetadata};
 
 /// The client's view of the available tabs.
 #[derive(Clone, Debug)]
 pub struct TabsState {
     pub initialized: bool,
     pub tabs: HashMap<TabId, TabMetadata>,
+    /// Output activity on running tabs, reported by the daemon
+    pub activity: HashMap<TabId, TabActivity>,
 }
 
 impl TabsState {
@@ -19,6 +21,7 @@ impl Default for TabsState {
         Self {
             initialized: false,
             tabs: HashMap::new(),
+            activity: HashMap::new(),
         }
     }
 }
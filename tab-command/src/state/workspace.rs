@@ -1,11 +1,13 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
+use tab_api::tab::{parse_duration, IdleAction, TabHooks, TabIdle};
 
 /// The client's view of the workspace configuration
 #[derive(Debug, Clone)]
 pub enum WorkspaceState {
     Loading,
-    Ready(Vec<WorkspaceTab>),
+    Ready(Vec<WorkspaceTab>, Vec<WorkspaceLayout>),
 }
 
 impl Default for WorkspaceState {
@@ -15,16 +17,34 @@ impl Default for WorkspaceState {
 }
 
 /// A user-configured workspace tab, which may or may not be running
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WorkspaceTab {
     pub name: String,
     pub directory: PathBuf,
     pub doc: String,
+    /// If provided, the tab session is recorded to this file
+    pub record: Option<PathBuf>,
+    /// If provided, the tab output is appended to this log file
+    pub log: Option<PathBuf>,
+    /// Shell commands which the daemon executes on tab lifecycle events
+    pub hooks: TabHooks,
+    /// Tags which select the tab, with a `@tag` selector
+    pub tags: Vec<String>,
+    /// Closes, or marks stale, the tab when it is idle
+    pub idle: TabIdle,
     // pub command: Option<String>,
 }
 
+/// A user-configured set of tabs, which are started and closed together
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceLayout {
+    pub name: String,
+    /// The tabs in the layout, with normalized names
+    pub tabs: Vec<LayoutTab>,
+}
+
 /// The top-level YAML configuration object, either a workspace root, or repository root
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Config {
     #[serde(rename = "workspace")]
@@ -34,47 +54,248 @@ pub enum Config {
 }
 
 /// The workspace root configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Workspace {
+    /// The name of the workspace tab, which defaults to the directory name
     pub tab: Option<String>,
+    /// The description of the workspace tab
     pub doc: Option<String>,
+    /// Reusable tab definitions, which can be instantiated by the workspace items
+    pub templates: Option<HashMap<String, Template>>,
+    /// The tabs, repositories, and child workspaces within the workspace
     pub workspace: Vec<WorkspaceItem>,
+    /// Paths or globs of other configuration files to load, relative to this file.
+    /// A directory includes the `tab.yml` file within it.
+    pub include: Option<Vec<String>>,
+    /// Named sets of tabs, which are started by `tab --up <layout>`, and closed by `tab --down <layout>`
+    pub layouts: Option<HashMap<String, Vec<LayoutTab>>>,
+    /// Hook commands for the workspace tab
+    #[serde(flatten)]
+    pub hooks: Hooks,
+    /// The idle policy of the workspace tab
+    #[serde(flatten)]
+    pub idle: Idle,
 }
 
 /// An item within the workspace configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum WorkspaceItem {
     Workspace(WorkspaceLink),
     Repo(WorkspaceRepoLink),
+    Glob(GlobTab),
     Tab(Tab),
 }
 
 /// A link to a child workspace, from the workspace root.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct WorkspaceLink {
+    /// The directory of the child workspace, relative to this file
     pub workspace: String,
 }
 
 /// A link to a repository within the workspace, from the workspace root.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct WorkspaceRepoLink {
+    /// The directory of the repository, relative to this file
     pub repo: String,
 }
 
 /// The repository configuration root
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Repo {
+    /// The name of the repository tab, which prefixes the names of the repository tabs
     pub repo: String,
+    /// The description of the repository tab
     pub doc: Option<String>,
-    pub tabs: Option<Vec<Tab>>,
+    /// Reusable tab definitions, which can be instantiated by the repository tabs
+    pub templates: Option<HashMap<String, Template>>,
+    /// The tabs within the repository
+    pub tabs: Option<Vec<RepoItem>>,
+    /// Named sets of tabs, which are started by `tab --up <layout>`, and closed by `tab --down <layout>`
+    pub layouts: Option<HashMap<String, Vec<LayoutTab>>>,
+    /// Hook commands for the repository tab
+    #[serde(flatten)]
+    pub hooks: Hooks,
+    /// The idle policy of the repository tab
+    #[serde(flatten)]
+    pub idle: Idle,
+}
+
+/// An item within the repository configuration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum RepoItem {
+    Glob(GlobTab),
+    Tab(Tab),
 }
 
 /// A tab within the workspace or repository configurations
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Tab {
+    /// The name of the tab
     pub tab: String,
+    /// The description of the tab, displayed by `tab --list`
     pub doc: Option<String>,
+    /// The working directory of the tab, relative to the configuration directory
     pub dir: Option<String>,
+    /// Records the tab session to the file, relative to the configuration directory
+    pub record: Option<String>,
+    /// Appends the tab output to the log file, relative to the configuration directory
+    pub log: Option<String>,
+    /// Instantiates the named template, using the fields it defines as defaults
+    pub template: Option<String>,
+    /// Values for the `{param}` placeholders in the tab and template fields
+    pub params: Option<HashMap<String, String>>,
+    /// Tags for the tab.  `tab -l @tag` and `tab -w @tag` select all the tabs with the tag
+    pub tags: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub hooks: Hooks,
+    #[serde(flatten)]
+    pub idle: Idle,
     // pub command: Option<String>,
 }
+
+/// A tab definition with `{param}` placeholders, shared by several tabs
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Template {
+    pub doc: Option<String>,
+    pub dir: Option<String>,
+    pub record: Option<String>,
+    pub log: Option<String>,
+    pub tags: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub hooks: Hooks,
+    #[serde(flatten)]
+    pub idle: Idle,
+}
+
+/// Shell commands which the daemon executes on tab lifecycle events.
+///
+/// The commands run in the tab directory, with `TAB`, `TAB_ID`, `TAB_DIR` and `TAB_EVENT` in the environment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Hooks {
+    /// Executed when the tab is created
+    pub on_create: Option<String>,
+    /// Executed when a client attaches to the tab
+    pub on_attach: Option<String>,
+    /// Executed when a client detaches from the tab
+    pub on_detach: Option<String>,
+    /// Executed when the tab is closed, and its processes have stopped
+    pub on_close: Option<String>,
+}
+
+impl From<Hooks> for TabHooks {
+    fn from(hooks: Hooks) -> Self {
+        TabHooks {
+            on_create: hooks.on_create,
+            on_attach: hooks.on_attach,
+            on_detach: hooks.on_detach,
+            on_close: hooks.on_close,
+        }
+    }
+}
+
+/// Closes, or marks stale, a tab which has been idle.
+///
+/// A tab is idle when it has no input, output, or attached clients, and the shell is the foreground process.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Idle {
+    /// The idle time before the action is taken, in seconds, or with a unit (e.g. `30m`, `2h`, or `1d`).
+    /// Defaults to `TAB_IDLE_TIMEOUT`, and `0` disables the timeout.
+    pub idle_timeout: Option<IdleTimeout>,
+    /// `close` closes the idle tab, and `stale` marks it stale in `tab --list`, until it is closed by `tab --prune`.
+    /// Defaults to `TAB_IDLE_ACTION`, or `close`.
+    pub idle_action: Option<String>,
+}
+
+impl From<Idle> for TabIdle {
+    // invalid values are reported by `tab --check-config`
+    fn from(idle: Idle) -> Self {
+        TabIdle {
+            timeout: idle.idle_timeout.as_ref().and_then(IdleTimeout::seconds),
+            action: idle.idle_action.as_deref().and_then(IdleAction::parse),
+        }
+    }
+}
+
+/// An idle timeout, either a number of seconds, or a duration with a unit
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum IdleTimeout {
+    Seconds(u64),
+    Duration(String),
+}
+
+impl IdleTimeout {
+    pub fn seconds(&self) -> Option<u64> {
+        match self {
+            IdleTimeout::Seconds(seconds) => Some(*seconds),
+            IdleTimeout::Duration(duration) => parse_duration(duration.as_str()),
+        }
+    }
+}
+
+/// Generates a tab for each directory that matches the glob, relative to the configuration directory.
+///
+/// The `{name}` parameter is the name of the matched directory, and `{dir}` is the matched path.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct GlobTab {
+    /// The glob which matches the tab directories, relative to the configuration directory
+    pub glob: String,
+    /// The tab name, which defaults to `{name}`
+    pub tab: Option<String>,
+    pub doc: Option<String>,
+    /// The tab directory, which defaults to `{dir}`
+    pub dir: Option<String>,
+    pub record: Option<String>,
+    pub log: Option<String>,
+    pub template: Option<String>,
+    pub params: Option<HashMap<String, String>>,
+    pub tags: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub hooks: Hooks,
+    #[serde(flatten)]
+    pub idle: Idle,
+}
+
+/// A tab within a layout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct LayoutTab {
+    /// The full name of the tab, as displayed by `tab --list`
+    pub tab: String,
+    /// A command which is typed into the tab, if the layout creates it
+    pub command: Option<String>,
+}
+
+/// Generates the JSON Schema for the `tab.yml` configuration files
+pub fn config_schema() -> String {
+    let schema = schemars::schema_for!(Config);
+    serde_json::to_string_pretty(&schema).expect("the config schema should serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::config_schema;
+
+    #[test]
+    fn schema_matches_checked_in_copy() {
+        let expected = include_str!("../../schema/tab.schema.json");
+
+        assert_eq!(
+            expected.trim_end(),
+            config_schema().trim_end(),
+            "the config schema has changed, update it with `tab --print-schema > tab-command/schema/tab.schema.json`"
+        );
+    }
+}
@@ -1,18 +1,26 @@
 use crate::{message::cli::CliSubscriptionRecv, message::cli::CliSubscriptionSend, prelude::*};
 use crate::{
     message::{
-        cli::{CliRecv, CliSend, CliShutdown},
+        cli::{CliRecv, CliSend, CliShutdown, CliStatusRequest},
         listener::ListenerShutdown,
         tab::{TabInput, TabRecv, TabSend},
         tab_manager::{TabManagerRecv, TabManagerSend},
+        tab_record::RecordTab,
+    },
+    state::{
+        activity::{AttachmentWatch, TabsActivityState},
+        tab::TabsState,
     },
-    state::tab::TabsState,
 };
 
 use anyhow::Context;
 use lifeline::{subscription, Resource};
-use std::sync::Arc;
-use tab_api::{client::Request, client::Response, tab::TabId};
+use std::{path::PathBuf, sync::Arc};
+use tab_api::{
+    client::{DaemonStatus, Request, Response},
+    log_file::LogScope,
+    tab::TabId,
+};
 use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
 use time::Duration;
 use tokio::{
@@ -20,6 +28,12 @@ use tokio::{
     time,
 };
 
+/// The capacity of the `Request` broadcast channel
+pub const REQUEST_CHANNEL_CAPACITY: usize = 128;
+
+/// The capacity of the `Response` channel, which is written to the websocket
+pub const RESPONSE_CHANNEL_CAPACITY: usize = 256;
+
 lifeline_bus!(pub struct CliBus);
 
 impl Message<CliBus> for CliShutdown {
@@ -42,6 +56,10 @@ impl Message<CliBus> for CliRecv {
     type Channel = mpsc::Sender<Self>;
 }
 
+impl Message<CliBus> for CliStatusRequest {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Message<CliBus> for CliSubscriptionSend {
     type Channel = mpsc::Sender<Self>;
 }
@@ -60,7 +78,13 @@ impl Message<CliBus> for TabsState {
     type Channel = mpsc::Sender<Self>;
 }
 
+/// Carried from the listener, like `TabsState`
+impl Message<CliBus> for TabsActivityState {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Resource<CliBus> for WebsocketResource {}
+impl Resource<CliBus> for LogScope {}
 impl WebsocketMessageBus for CliBus {
     type Send = Response;
     type Recv = Request;
@@ -71,12 +95,16 @@ pub struct ListenerConnectionCarrier {
     _reverse: Lifeline,
     _terminated: Lifeline,
     _forward_tabs_state: Lifeline,
+    _forward_activity: Lifeline,
+    _status: Lifeline,
 }
 
 impl CarryFrom<ListenerBus> for CliBus {
     type Lifeline = anyhow::Result<ListenerConnectionCarrier>;
 
     fn carry_from(&self, from: &ListenerBus) -> Self::Lifeline {
+        let scope = self.resource::<LogScope>().unwrap_or_default();
+
         let _forward = {
             let rx_tab = from.rx::<TabSend>()?;
 
@@ -85,7 +113,7 @@ impl CarryFrom<ListenerBus> for CliBus {
 
             Self::try_task(
                 "output",
-                Self::run_output(rx_tab, tx_conn.clone(), tx_subscription),
+                scope.scope(Self::run_output(rx_tab, tx_conn.clone(), tx_subscription)),
             )
         };
 
@@ -94,36 +122,83 @@ impl CarryFrom<ListenerBus> for CliBus {
 
             let tx_tab = from.tx::<TabRecv>()?.log();
             let tx_manager = from.tx::<TabManagerRecv>()?;
+            let tx_record = from.tx::<RecordTab>()?;
+            let tx_attach = from.tx::<AttachmentWatch>()?;
             let tx_shutdown = self.tx::<CliShutdown>()?;
             let tx_listener_shutdown = from.tx::<ListenerShutdown>()?;
             Self::try_task(
                 "input",
-                Self::run_input(
+                scope.scope(Self::run_input(
                     rx_conn,
                     tx_tab,
                     tx_manager,
+                    tx_record,
+                    tx_attach,
                     tx_shutdown,
                     tx_listener_shutdown,
-                ),
+                )),
             )
         };
 
         let _terminated = {
             let rx_manager = from.rx::<TabManagerSend>()?;
             let tx_conn = self.tx::<CliRecv>()?;
-            Self::try_task("terminated", Self::handle_terminated(rx_manager, tx_conn))
+            Self::try_task(
+                "terminated",
+                scope.scope(Self::handle_terminated(rx_manager, tx_conn)),
+            )
         };
 
         let _forward_tabs_state = {
             let mut rx_tabs_state = from.rx::<TabsState>()?;
             let mut tx_tabs_state = self.tx::<TabsState>()?;
-            Self::try_task("forward_tabs_state", async move {
-                while let Some(msg) = rx_tabs_state.recv().await {
-                    tx_tabs_state.send(msg).await.ok();
-                }
+            let mut tx_subscription = self.tx::<CliSubscriptionRecv>()?;
+            Self::try_task(
+                "forward_tabs_state",
+                scope.scope(async move {
+                    while let Some(msg) = rx_tabs_state.recv().await {
+                        tx_subscription
+                            .send(CliSubscriptionRecv::Tabs(msg.clone()))
+                            .await
+                            .ok();
+                        tx_tabs_state.send(msg).await.ok();
+                    }
+
+                    Ok(())
+                }),
+            )
+        };
 
-                Ok(())
-            })
+        let _forward_activity = {
+            let mut rx_activity = from.rx::<TabsActivityState>()?;
+            let mut tx_activity = self.tx::<TabsActivityState>()?;
+            Self::try_task(
+                "forward_activity",
+                scope.scope(async move {
+                    while let Some(msg) = rx_activity.recv().await {
+                        tx_activity.send(msg).await.ok();
+                    }
+
+                    Ok(())
+                }),
+            )
+        };
+
+        let _status = {
+            let mut rx_request = self.rx::<CliStatusRequest>()?;
+            let rx_status = from.rx::<DaemonStatus>()?.into_inner();
+            let mut tx_conn = self.tx::<CliRecv>()?;
+            Self::try_task(
+                "status",
+                scope.scope(async move {
+                    while let Some(_request) = rx_request.recv().await {
+                        let status = rx_status.borrow().clone();
+                        tx_conn.send(CliRecv::Status(status)).await?;
+                    }
+
+                    Ok(())
+                }),
+            )
         };
 
         Ok(ListenerConnectionCarrier {
@@ -131,6 +206,8 @@ impl CarryFrom<ListenerBus> for CliBus {
             _reverse,
             _terminated,
             _forward_tabs_state,
+            _forward_activity,
+            _status,
         })
     }
 }
@@ -152,6 +229,8 @@ impl CliBus {
         mut rx: impl Receiver<CliSend>,
         mut tx: impl Sender<TabRecv>,
         mut tx_manager: impl Sender<TabManagerRecv>,
+        mut tx_record: impl Sender<RecordTab>,
+        mut tx_attach: impl Sender<AttachmentWatch>,
         mut tx_shutdown: impl Sender<CliShutdown>,
         mut tx_listener_shutdown: impl Sender<ListenerShutdown>,
     ) -> anyhow::Result<()> {
@@ -161,11 +240,36 @@ impl CliBus {
                     debug!("received CreateTab from client: {:?}", &create);
                     tx_manager.send(TabManagerRecv::CreateTab(create)).await?;
                 }
-                CliSend::CloseTab(id) => {
-                    tx_manager.send(TabManagerRecv::CloseTab(id)).await?;
+                CliSend::Attach(attachment) => {
+                    tx_attach.send(attachment).await?;
+                }
+                CliSend::CloseTab(id, options) => {
+                    tx_manager
+                        .send(TabManagerRecv::CloseTab(id, options))
+                        .await?;
+                }
+                CliSend::CloseNamedTab(name, options) => {
+                    tx_manager
+                        .send(TabManagerRecv::CloseNamedTab(name, options))
+                        .await?;
                 }
-                CliSend::CloseNamedTab(name) => {
-                    tx_manager.send(TabManagerRecv::CloseNamedTab(name)).await?;
+                CliSend::RenameTab(id, name) => {
+                    tx_manager.send(TabManagerRecv::RenameTab(id, name)).await?;
+                }
+                CliSend::RecordTab(id, path) => {
+                    let path = PathBuf::from(path);
+                    tx_record.send(RecordTab { id, path }).await?;
+                }
+                CliSend::LogTab(id, path) => {
+                    tx_manager
+                        .send(TabManagerRecv::UpdateLog(id, path.clone()))
+                        .await?;
+
+                    let path = PathBuf::from(path);
+                    tx.send(TabRecv::Log(id, path)).await?;
+                }
+                CliSend::Signal(id, signal) => {
+                    tx.send(TabRecv::Signal(id, signal)).await?;
                 }
                 CliSend::RequestScrollback(id) => {
                     debug!(
@@ -192,6 +296,7 @@ impl CliBus {
                 }
                 CliSend::GlobalShutdown => {
                     info!("Daemon receieved a global shutdown.");
+                    tx_manager.send(TabManagerRecv::Shutdown).await?;
                     tx.send(TabRecv::TerminateAll).await?;
                     tx_listener_shutdown.send(ListenerShutdown {}).await?;
                     time::delay_for(Duration::from_millis(50)).await;
@@ -226,6 +331,8 @@ impl CliBus {
     ) -> anyhow::Result<()> {
         match msg {
             TabSend::Started(tab) => tx.send(CliRecv::TabStarted(tab)).await?,
+            TabSend::Updated(tab) => tx.send(CliRecv::TabStarted(tab)).await?,
+            TabSend::Closing(id, signal) => tx.send(CliRecv::Closing(id, signal)).await?,
             TabSend::Stopped(id) => {
                 info!("Disconnecting client due to closed tab {}", id);
                 tx.send(CliRecv::TabStopped(id)).await?;
@@ -265,7 +372,7 @@ mod forward_tests {
     use std::sync::Arc;
     use tab_api::{
         chunk::OutputChunk,
-        tab::{TabId, TabMetadata},
+        tab::{TabId, TabMetadata, TabSignal},
     };
     use tokio::sync::Mutex;
 
@@ -285,6 +392,12 @@ mod forward_tests {
             dimensions: (1, 1),
             shell: "bash".into(),
             dir: "dir".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
         };
 
         tx.send(TabSend::Started(started.clone())).await?;
@@ -319,6 +432,27 @@ mod forward_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn closing() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = listener_bus.tx::<TabSend>()?;
+        let mut rx = cli_bus.rx::<CliRecv>()?;
+
+        tx.send(TabSend::Closing(TabId(0), TabSignal::Hangup))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliRecv::Closing(TabId(0), TabSignal::Hangup)), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn scrollback() -> anyhow::Result<()> {
         let cli_bus = CliBus::default();
@@ -452,13 +586,15 @@ mod reverse_tests {
             listener::ListenerShutdown,
             tab::{TabInput, TabRecv},
             tab_manager::TabManagerRecv,
+            tab_record::RecordTab,
         },
         prelude::*,
     };
     use lifeline::assert_completes;
+    use std::path::PathBuf;
     use tab_api::{
         chunk::InputChunk,
-        tab::{CreateTabMetadata, TabId},
+        tab::{CloseOptions, CreateTabMetadata, TabId, TabSignal},
     };
 
     #[tokio::test]
@@ -476,6 +612,11 @@ mod reverse_tests {
             shell: "bash".into(),
             dimensions: (1, 1),
             dir: "dir".into(),
+            record: None,
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
         };
 
         tx.send(CliSend::CreateTab(create.clone())).await?;
@@ -499,12 +640,112 @@ mod reverse_tests {
         let mut tx = cli_bus.tx::<CliSend>()?;
         let mut rx = listener_bus.rx::<TabManagerRecv>()?;
 
-        tx.send(CliSend::CloseTab(TabId(0))).await?;
+        let options = CloseOptions::default();
+        tx.send(CliSend::CloseTab(TabId(0), options)).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
             assert!(msg.is_some());
-            assert_eq!(TabManagerRecv::CloseTab(TabId(0)), msg.unwrap());
+            assert_eq!(TabManagerRecv::CloseTab(TabId(0), options), msg.unwrap());
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(CliSend::RenameTab(TabId(0), "new/".into())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(TabManagerRecv::RenameTab(TabId(0), "new/".into())),
+                msg
+            );
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<RecordTab>()?;
+
+        tx.send(CliSend::RecordTab(TabId(0), "/tab.cast".into()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(RecordTab {
+                    id: TabId(0),
+                    path: PathBuf::from("/tab.cast")
+                }),
+                msg
+            );
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<TabRecv>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(CliSend::LogTab(TabId(0), "/tab.log".into()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Log(TabId(0), PathBuf::from("/tab.log"))), msg);
+
+            let msg = rx_manager.recv().await;
+            assert_eq!(
+                Some(TabManagerRecv::UpdateLog(TabId(0), "/tab.log".into())),
+                msg
+            );
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signal() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = cli_bus.carry_from(&listener_bus)?;
+
+        let mut tx = cli_bus.tx::<CliSend>()?;
+        let mut rx = listener_bus.rx::<TabRecv>()?;
+
+        tx.send(CliSend::Signal(TabId(0), TabSignal::Interrupt))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(TabRecv::Signal(TabId(0), TabSignal::Interrupt)), msg);
         });
 
         Ok(())
@@ -520,12 +761,20 @@ mod reverse_tests {
         let mut tx = cli_bus.tx::<CliSend>()?;
         let mut rx = listener_bus.rx::<TabManagerRecv>()?;
 
-        tx.send(CliSend::CloseNamedTab("foo".into())).await?;
+        let options = CloseOptions {
+            force: true,
+            grace_period: 1,
+        };
+        tx.send(CliSend::CloseNamedTab("foo".into(), options))
+            .await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
             assert!(msg.is_some());
-            assert_eq!(TabManagerRecv::CloseNamedTab("foo".into()), msg.unwrap());
+            assert_eq!(
+                TabManagerRecv::CloseNamedTab("foo".into(), options),
+                msg.unwrap()
+            );
         });
 
         Ok(())
@@ -628,12 +877,14 @@ mod reverse_tests {
 
         let mut tx = cli_bus.tx::<CliSend>()?;
         let mut rx = listener_bus.rx::<ListenerShutdown>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
 
         tx.send(CliSend::GlobalShutdown).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
             assert!(msg.is_some());
+            assert_eq!(Some(TabManagerRecv::Shutdown), rx_manager.recv().await);
         });
 
         Ok(())
@@ -3,19 +3,30 @@ use crate::{
     message::{
         daemon::DaemonShutdown,
         listener::ListenerShutdown,
+        status::StatusRecv,
         tab::{TabRecv, TabSend},
         tab_assignment::{AssignTab, TabAssignmentRetraction},
         tab_manager::{TabManagerRecv, TabManagerSend},
+        tab_record::RecordTab,
+    },
+    state::{
+        activity::{AttachmentWatch, TabsActivityState},
+        tab::TabsState,
     },
-    state::tab::TabsState,
 };
-use lifeline::error::into_msg;
+use lifeline::{error::into_msg, Resource};
 
+use tab_api::{client::DaemonStatus, config::DaemonConfig};
 use tab_websocket::{bus::WebsocketListenerBus, message::listener::WebsocketConnectionMessage};
 use tokio::sync::{broadcast, mpsc, watch};
 
+/// The capacity of the `TabSend` and `TabRecv` broadcast channels
+pub const TAB_CHANNEL_CAPACITY: usize = 128;
+
 lifeline_bus!(pub struct ListenerBus);
 
+impl Resource<ListenerBus> for DaemonConfig {}
+
 impl Message<ListenerBus> for WebsocketConnectionMessage {
     type Channel = mpsc::Sender<Self>;
 }
@@ -48,10 +59,30 @@ impl Message<ListenerBus> for TabAssignmentRetraction {
     type Channel = broadcast::Sender<Self>;
 }
 
+impl Message<ListenerBus> for RecordTab {
+    type Channel = mpsc::Sender<Self>;
+}
+
 impl Message<ListenerBus> for TabsState {
     type Channel = watch::Sender<Self>;
 }
 
+impl Message<ListenerBus> for AttachmentWatch {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<ListenerBus> for TabsActivityState {
+    type Channel = watch::Sender<Self>;
+}
+
+impl Message<ListenerBus> for StatusRecv {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Message<ListenerBus> for DaemonStatus {
+    type Channel = watch::Sender<Self>;
+}
+
 pub struct ListenerDaemonCarrier {
     _forward_shutdown: Lifeline,
 }
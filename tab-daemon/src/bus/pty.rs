@@ -2,8 +2,9 @@ use crate::prelude::*;
 use crate::{
     message::{
         pty::{PtyRecv, PtySend, PtyShutdown},
+        status::StatusRecv,
         tab::{TabOutput, TabRecv, TabScrollback, TabSend},
-        tab_manager::TabManagerRecv,
+        tab_manager::{ResumeTab, TabManagerRecv},
     },
     state::pty::{PtyScrollback, PtyState},
 };
@@ -13,11 +14,18 @@ use std::sync::Arc;
 use lifeline::Resource;
 use tab_api::{
     chunk::InputChunk,
+    log_file::LogScope,
     pty::{PtyWebsocketRequest, PtyWebsocketResponse},
     tab::TabMetadata,
 };
 use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
-use tokio::sync::{broadcast, mpsc, watch};
+use tokio::{
+    stream::StreamExt,
+    sync::{broadcast, mpsc, watch},
+};
+
+/// The capacity of the `PtySend` and `PtyRecv` broadcast channels
+pub const PTY_CHANNEL_CAPACITY: usize = 128;
 
 lifeline_bus!(pub struct PtyBus);
 
@@ -54,11 +62,17 @@ impl Message<PtyBus> for TabMetadata {
 }
 
 impl Resource<PtyBus> for WebsocketResource {}
+impl Resource<PtyBus> for LogScope {}
 impl WebsocketMessageBus for PtyBus {
     type Send = PtyWebsocketRequest;
     type Recv = PtyWebsocketResponse;
 }
 
+enum Event {
+    Tab(TabRecv),
+    Resume(TabMetadata),
+}
+
 pub struct ListenerPtyCarrier {
     _to_pty: Lifeline,
     _to_listener: Lifeline,
@@ -68,6 +82,8 @@ impl CarryFrom<ListenerBus> for PtyBus {
     type Lifeline = anyhow::Result<ListenerPtyCarrier>;
 
     fn carry_from(&self, from: &ListenerBus) -> Self::Lifeline {
+        let scope = self.resource::<LogScope>().unwrap_or_default();
+
         // converts TabRecv into PtyRecv
         // forwards input and output chunks
         // receives startup and shutdown signals
@@ -76,13 +92,66 @@ impl CarryFrom<ListenerBus> for PtyBus {
             let rx_id = self.rx::<PtyState>()?.into_inner();
             // FIXME I think the bug is here.
             // the channel is being taken from the
-            let mut rx_tab = from.rx::<TabRecv>()?;
+            let rx_tab = from.rx::<TabRecv>()?.into_inner().filter_map(Result::ok);
+            let rx_resume = self
+                .rx::<PtySend>()?
+                .into_inner()
+                .filter_map(|msg| match msg {
+                    Ok(PtySend::Resume(metadata)) => Some(metadata),
+                    _ => None,
+                });
 
             let mut tx_pty = self.tx::<PtyRecv>()?;
             let mut tx_pty_state = self.tx::<PtyState>()?;
+            let mut tx_tab_manager = from.tx::<TabManagerRecv>()?;
+            let mut tx_status = from.tx::<StatusRecv>()?;
+
+            // the records of the connection carry the tab, once it is assigned
+            let assigned_scope = scope.clone();
+
+            Self::try_task("to_pty", scope.scope(async move {
+                let mut events = {
+                    let tabs = rx_tab.map(Event::Tab);
+                    let resumes = rx_resume.map(Event::Resume);
+                    tabs.merge(resumes)
+                };
+
+                while let Some(event) = events.next().await {
+                    let msg = match event {
+                        Event::Tab(msg) => msg,
+                        Event::Resume(metadata) => {
+                            if rx_id.borrow().is_assigned() {
+                                continue;
+                            }
+
+                            // the tab manager decides whether the tab conflicts with a running tab
+                            let (resume, rx_resumed) = ResumeTab::new(metadata.clone());
+                            tx_tab_manager
+                                .send(TabManagerRecv::ResumeTab(resume))
+                                .await?;
+
+                            if !rx_resumed.await.unwrap_or(false) {
+                                warn!(
+                                    "Resumed tab {} ({}) was rejected, as it conflicts with a running tab",
+                                    metadata.id, metadata.name
+                                );
+                                tx_pty.send(PtyRecv::Terminate).await?;
+                                continue;
+                            }
+
+                            assigned_scope.set_tab(metadata.id);
+                            info!("PTY resumed tab {}", metadata.id);
+
+                            tx_pty_state.send(PtyState::Assigned(metadata.id)).await?;
+                            tx_status
+                                .send(StatusRecv::PtyAssigned(metadata.id))
+                                .await
+                                .ok();
+                            tx_pty.send(PtyRecv::Init(metadata)).await?;
+                            continue;
+                        }
+                    };
 
-            Self::try_task("to_pty", async move {
-                while let Some(msg) = rx_tab.recv().await {
                     match msg {
                         TabRecv::Assign(offer) => {
                             if rx_id.borrow().is_assigned() {
@@ -90,9 +159,17 @@ impl CarryFrom<ListenerBus> for PtyBus {
                             }
 
                             if let Some(assignment) = offer.accept() {
+                                assigned_scope.set_tab(assignment.id);
                                 info!("New PTY connected on tab {}", assignment.id);
 
                                 tx_pty_state.send(PtyState::Assigned(assignment.id)).await?;
+                                tx_status
+                                    .send(StatusRecv::PtyAssigned(assignment.id))
+                                    .await
+                                    .ok();
+                                tx_tab_manager
+                                    .send(TabManagerRecv::TabAssigned(assignment.id))
+                                    .await?;
                                 tx_pty.send(PtyRecv::Init(assignment)).await?;
                             }
                         }
@@ -118,6 +195,20 @@ impl CarryFrom<ListenerBus> for PtyBus {
 
                             tx_pty.send(PtyRecv::Terminate).await?;
                         }
+                        TabRecv::Rename(id, name) => {
+                            if !rx_id.borrow().has_assigned(id) {
+                                continue;
+                            }
+
+                            tx_pty.send(PtyRecv::Rename(name)).await?;
+                        }
+                        TabRecv::Close(id, options) => {
+                            if !rx_id.borrow().has_assigned(id) {
+                                continue;
+                            }
+
+                            tx_pty.send(PtyRecv::Close(options)).await?;
+                        }
                         TabRecv::Resize(id, dimensions) => {
                             if !rx_id.borrow().has_assigned(id) {
                                 continue;
@@ -125,6 +216,20 @@ impl CarryFrom<ListenerBus> for PtyBus {
 
                             tx_pty.send(PtyRecv::Resize(dimensions)).await?;
                         }
+                        TabRecv::Log(id, path) => {
+                            if !rx_id.borrow().has_assigned(id) {
+                                continue;
+                            }
+
+                            tx_pty.send(PtyRecv::Log(path)).await?;
+                        }
+                        TabRecv::Signal(id, signal) => {
+                            if !rx_id.borrow().has_assigned(id) {
+                                continue;
+                            }
+
+                            tx_pty.send(PtyRecv::Signal(signal)).await?;
+                        }
                         TabRecv::Retask(_, _) => {}
                         TabRecv::TerminateAll => {
                             tx_pty.send(PtyRecv::Terminate).await?;
@@ -133,7 +238,7 @@ impl CarryFrom<ListenerBus> for PtyBus {
                 }
 
                 Ok(())
-            })
+            }))
         };
 
         let _to_listener = {
@@ -142,43 +247,72 @@ impl CarryFrom<ListenerBus> for PtyBus {
 
             let mut tx_tab = from.tx::<TabSend>()?;
             let mut tx_tab_manager = from.tx::<TabManagerRecv>()?;
+            let mut tx_status = from.tx::<StatusRecv>()?;
+
+            Self::try_task(
+                "to_listener",
+                scope.scope(async move {
+                    let mut pid = None;
+
+                    while let Some(msg) = rx_pty.recv().await {
+                        match msg {
+                            PtySend::Started(metadata) => {
+                                tx_status
+                                    .send(StatusRecv::PtyStarted(metadata.id, pid))
+                                    .await
+                                    .ok();
+
+                                let message = TabSend::Started(metadata);
+                                tx_tab.send(message).await.ok();
+                            }
+                            PtySend::Output(chunk) | PtySend::Replay(chunk) => {
+                                let id = rx_id.borrow().unwrap();
 
-            Self::try_task("to_listener", async move {
-                while let Some(msg) = rx_pty.recv().await {
-                    match msg {
-                        PtySend::Started(metadata) => {
-                            let message = TabSend::Started(metadata);
-                            tx_tab.send(message).await.ok();
-                        }
-                        PtySend::Output(chunk) => {
-                            let id = rx_id.borrow().unwrap();
-
-                            let output = TabOutput {
-                                id,
-                                stdout: Arc::new(chunk),
-                            };
+                                let output = TabOutput {
+                                    id,
+                                    stdout: Arc::new(chunk),
+                                };
 
-                            let send = TabSend::Output(output);
-                            tx_tab.send(send).await.ok();
-                        }
-                        PtySend::Scrollback(scrollback) => {
-                            let id = rx_id.borrow().unwrap();
-                            let scrollback = TabScrollback { id, scrollback };
-                            let message = TabSend::Scrollback(scrollback);
-                            tx_tab.send(message).await.ok();
-                        }
-                        PtySend::Stopped => {
-                            let id = rx_id.borrow().unwrap();
-                            // todo - this should be a notification, not an action
-                            // serious bugs were going on because this was missing, though.
-                            tx_tab_manager.send(TabManagerRecv::CloseTab(id)).await?;
-                            tx_tab.send(TabSend::Stopped(id)).await.ok();
+                                let send = TabSend::Output(output);
+                                tx_tab.send(send).await.ok();
+                            }
+                            PtySend::Scrollback(scrollback) => {
+                                let id = rx_id.borrow().unwrap();
+                                let scrollback = TabScrollback { id, scrollback };
+                                let message = TabSend::Scrollback(scrollback);
+                                tx_tab.send(message).await.ok();
+                            }
+                            PtySend::Process(process) => {
+                                let id = rx_id.borrow().unwrap();
+                                tx_tab_manager
+                                    .send(TabManagerRecv::UpdateProcess(id, process))
+                                    .await?;
+                            }
+                            PtySend::Stats(stats) => {
+                                let id = rx_id.borrow().unwrap();
+                                tx_tab_manager
+                                    .send(TabManagerRecv::UpdateStats(id, stats))
+                                    .await?;
+                            }
+                            PtySend::Closing(signal) => {
+                                let id = rx_id.borrow().unwrap();
+                                tx_tab.send(TabSend::Closing(id, signal)).await.ok();
+                            }
+                            PtySend::Stopped => {
+                                let id = rx_id.borrow().unwrap();
+                                tx_tab_manager.send(TabManagerRecv::TabStopped(id)).await?;
+                                tx_tab.send(TabSend::Stopped(id)).await.ok();
+                            }
+                            PtySend::Resume(_) => {}
+                            PtySend::Pid(pty_pid) => {
+                                pid = Some(pty_pid);
+                            }
                         }
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         Ok(ListenerPtyCarrier {
@@ -189,7 +323,276 @@ impl CarryFrom<ListenerBus> for PtyBus {
 }
 
 #[cfg(test)]
-mod to_pty_tests {}
+mod to_pty_tests {
+    use crate::{
+        message::{
+            pty::{PtyRecv, PtySend},
+            tab::TabRecv,
+            tab_manager::TabManagerRecv,
+        },
+        prelude::*,
+        state::pty::PtyState,
+    };
+    use lifeline::assert_completes;
+    use tab_api::tab::{CloseOptions, TabId, TabMetadata};
+
+    fn tab(id: u16, name: &str) -> TabMetadata {
+        TabMetadata {
+            id: TabId(id),
+            name: name.into(),
+            dimensions: (1, 2),
+            shell: "shell".into(),
+            dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        }
+    }
+
+    /// Receives the resume request from the pty, and replies as the tab manager would
+    async fn reply_resume(rx_manager: &mut impl Receiver<TabManagerRecv>, accept: bool) {
+        match rx_manager.recv().await {
+            Some(TabManagerRecv::ResumeTab(resume)) if accept => resume.accept(),
+            Some(TabManagerRecv::ResumeTab(resume)) => resume.reject(),
+            msg => panic!("expected a resume request, got {:?}", msg),
+        }
+    }
+
+    #[tokio::test]
+    async fn resume() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut rx_pty = pty_bus.rx::<PtyRecv>()?;
+        let rx_state = pty_bus.rx::<PtyState>()?.into_inner();
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(PtySend::Resume(tab(2, "name"))).await?;
+
+        assert_completes!(async move {
+            let msg = rx_manager.recv().await;
+            let resume = match msg {
+                Some(TabManagerRecv::ResumeTab(resume)) => resume,
+                msg => panic!("expected a resume request, got {:?}", msg),
+            };
+            assert_eq!(tab(2, "name"), resume.metadata);
+            resume.accept();
+
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Init(metadata)) if metadata == tab(2, "name")));
+        });
+
+        assert!(rx_state.borrow().has_assigned(TabId(2)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut tx_tab = listener_bus.tx::<TabRecv>()?;
+        let mut rx_pty = pty_bus.rx::<PtyRecv>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(PtySend::Resume(tab(2, "name"))).await?;
+
+        assert_completes!(async {
+            reply_resume(&mut rx_manager, true).await;
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Init(_))));
+        });
+
+        let options = CloseOptions::default();
+        tx_tab.send(TabRecv::Close(TabId(1), options)).await?;
+        tx_tab.send(TabRecv::Close(TabId(2), options)).await?;
+
+        assert_completes!(async move {
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Close(close)) if close == options));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut tx_tab = listener_bus.tx::<TabRecv>()?;
+        let mut rx_pty = pty_bus.rx::<PtyRecv>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(PtySend::Resume(tab(2, "name"))).await?;
+
+        assert_completes!(async {
+            reply_resume(&mut rx_manager, true).await;
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Init(_))));
+        });
+
+        tx_tab
+            .send(TabRecv::Rename(TabId(1), "other/".into()))
+            .await?;
+        tx_tab
+            .send(TabRecv::Rename(TabId(2), "new/".into()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Rename(name)) if name == "new/"));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_conflict() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut rx_pty = pty_bus.rx::<PtyRecv>()?;
+        let rx_state = pty_bus.rx::<PtyState>()?.into_inner();
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        tx.send(PtySend::Resume(tab(2, "name"))).await?;
+
+        assert_completes!(async move {
+            reply_resume(&mut rx_manager, false).await;
+            let msg = rx_pty.recv().await;
+            assert!(matches!(msg, Some(PtyRecv::Terminate)));
+        });
+
+        assert!(!rx_state.borrow().has_assigned(TabId(2)));
+
+        Ok(())
+    }
+}
 
 #[cfg(test)]
-mod to_listener_tests {}
+mod to_listener_tests {
+    use crate::{
+        message::{pty::PtySend, tab_manager::TabManagerRecv},
+        prelude::*,
+    };
+    use lifeline::assert_completes;
+    use tab_api::tab::{TabId, TabMetadata, TabProcess, TabStats};
+
+    #[tokio::test]
+    async fn process() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        // resume the tab, so the pty is assigned
+        let tab = TabMetadata {
+            id: TabId(1),
+            name: "name".into(),
+            dimensions: (1, 2),
+            shell: "shell".into(),
+            dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        };
+        tx.send(PtySend::Resume(tab)).await?;
+
+        assert_completes!(async {
+            match rx_manager.recv().await {
+                Some(TabManagerRecv::ResumeTab(resume)) => resume.accept(),
+                msg => panic!("expected a resume request, got {:?}", msg),
+            }
+        });
+
+        let process = TabProcess {
+            name: "cargo".into(),
+            cwd: "/src".into(),
+        };
+        tx.send(PtySend::Process(process.clone())).await?;
+
+        assert_completes!(async {
+            let msg = rx_manager.recv().await;
+            assert_eq!(Some(TabManagerRecv::UpdateProcess(TabId(1), process)), msg);
+        });
+
+        let stats = TabStats {
+            cpu: 12,
+            memory: 2048,
+            processes: 3,
+        };
+        tx.send(PtySend::Stats(stats)).await?;
+
+        assert_completes!(async move {
+            let msg = rx_manager.recv().await;
+            assert_eq!(Some(TabManagerRecv::UpdateStats(TabId(1), stats)), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stopped() -> anyhow::Result<()> {
+        let pty_bus = PtyBus::default();
+        let listener_bus = ListenerBus::default();
+
+        let _carrier = pty_bus.carry_from(&listener_bus)?;
+
+        let mut tx = pty_bus.tx::<PtySend>()?;
+        let mut rx_manager = listener_bus.rx::<TabManagerRecv>()?;
+
+        let tab = TabMetadata {
+            id: TabId(1),
+            name: "name".into(),
+            dimensions: (1, 2),
+            shell: "shell".into(),
+            dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        };
+        tx.send(PtySend::Resume(tab)).await?;
+
+        assert_completes!(async {
+            match rx_manager.recv().await {
+                Some(TabManagerRecv::ResumeTab(resume)) => resume.accept(),
+                msg => panic!("expected a resume request, got {:?}", msg),
+            }
+        });
+
+        tx.send(PtySend::Stopped).await?;
+
+        assert_completes!(async move {
+            let msg = rx_manager.recv().await;
+            assert_eq!(Some(TabManagerRecv::TabStopped(TabId(1))), msg);
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use log::{debug, error, info, warn};
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+use tab_api::tab::{TabHook, TabMetadata};
+
+/// Executes the tab's hook command for the event, if one is configured.
+///
+/// The command runs in the background, within the tab directory.  A thread waits for the command to exit,
+/// and logs failures.
+pub fn run_hook(hook: TabHook, tab: &TabMetadata) {
+    let command = match tab.hooks.command(hook) {
+        Some(command) => command,
+        None => return,
+    };
+
+    info!(
+        "running {} hook for tab {}: {}",
+        hook.event(),
+        &tab.name,
+        command
+    );
+
+    let mut child = Command::new("sh");
+    child
+        .args(["-c", command.as_str()])
+        .env("TAB", tab.name.as_str())
+        .env("TAB_ID", tab.id.0.to_string())
+        .env("TAB_DIR", tab.dir.as_str())
+        .env("TAB_EVENT", hook.event())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    if Path::new(tab.dir.as_str()).is_dir() {
+        child.current_dir(tab.dir.as_str());
+    }
+
+    let mut child = match child.spawn() {
+        Ok(child) => child,
+        Err(err) => {
+            error!(
+                "failed to execute {} hook for tab {}: {}",
+                hook.event(),
+                &tab.name,
+                err
+            );
+            return;
+        }
+    };
+
+    let name = tab.name.clone();
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if status.success() => {
+            debug!("{} hook for tab {} completed", hook.event(), name)
+        }
+        Ok(status) => warn!("{} hook for tab {} failed: {}", hook.event(), name, status),
+        Err(err) => error!(
+            "failed to wait for {} hook for tab {}: {}",
+            hook.event(),
+            name,
+            err
+        ),
+    });
+}
+
+/// Runs the detach hook of the tab when dropped.
+/// Held by the client connection while it is attached, so the hook also runs when the connection closes abruptly.
+#[derive(Debug)]
+pub struct DetachHook {
+    tab: TabMetadata,
+}
+
+impl DetachHook {
+    /// Runs the attach hook of the tab, and returns a guard which runs the detach hook
+    pub fn attach(tab: TabMetadata) -> Self {
+        run_hook(TabHook::Attach, &tab);
+        Self { tab }
+    }
+}
+
+impl Drop for DetachHook {
+    fn drop(&mut self) {
+        run_hook(TabHook::Detach, &self.tab);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run_hook;
+    use std::time::{Duration, Instant};
+    use tab_api::tab::{TabHook, TabHooks, TabId, TabMetadata};
+
+    #[test]
+    fn hook_environment() {
+        let dir = std::env::temp_dir().join(format!("tab-hook-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("hook.out");
+
+        let tab = TabMetadata {
+            id: TabId(3),
+            name: "hook/".to_string(),
+            dimensions: (1, 1),
+            shell: "bash".to_string(),
+            dir: dir.to_string_lossy().to_string(),
+            log: None,
+            hooks: Box::new(TabHooks {
+                on_close: Some("echo $TAB $TAB_ID $TAB_EVENT $TAB_DIR > hook.out".to_string()),
+                ..Default::default()
+            }),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        };
+
+        run_hook(TabHook::Create, &tab);
+        run_hook(TabHook::Close, &tab);
+
+        let start = Instant::now();
+        let mut contents = String::new();
+        while start.elapsed() < Duration::from_secs(5) {
+            contents = std::fs::read_to_string(&output).unwrap_or_default();
+            if contents.ends_with('\n') {
+                break;
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        let expected = format!("hook/ 3 close {}\n", tab.dir);
+        assert_eq!(expected, contents);
+    }
+}
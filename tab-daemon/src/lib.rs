@@ -6,12 +6,13 @@ use log::{info, LevelFilter};
 use lifeline::{dyn_bus::DynBus, prelude::*};
 use message::daemon::DaemonShutdown;
 use service::daemon::DaemonService;
-use simplelog::{CombinedLogger, TermLogger, TerminalMode, WriteLogger};
+use simplelog::{CombinedLogger, TermLogger, TerminalMode};
 use std::time::Duration;
 use tab_api::{
     config::{daemon_log, DaemonConfig},
     launch::wait_for_shutdown,
     log::get_level,
+    log_file::file_logger,
 };
 use tab_websocket::resource::listener::{WebsocketAuthToken, WebsocketListenerResource};
 use tokio::net::TcpListener;
@@ -19,6 +20,7 @@ use tokio::net::TcpListener;
 mod auth;
 mod bus;
 mod daemonfile;
+mod hooks;
 mod message;
 mod prelude;
 mod service;
@@ -52,6 +54,7 @@ pub async fn new_bus() -> anyhow::Result<DaemonBus> {
         pid: pid as i32,
         port,
         auth_token: auth_token.clone(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
     };
 
     let bus = DaemonBus::default();
@@ -69,10 +72,15 @@ async fn main_async() -> anyhow::Result<()> {
         .set_time_format_str("%H:%M:%S%.3f DAE")
         .build();
 
+    // the log file is appended to across restarts, so it includes the date
+    let file_config = simplelog::ConfigBuilder::new()
+        .set_time_format_str("%Y-%m-%d %H:%M:%S%.3f DAE")
+        .build();
+
     let level = get_level().unwrap_or(LevelFilter::Info);
     CombinedLogger::init(vec![
-        TermLogger::new(level, config.clone(), TerminalMode::Stderr),
-        WriteLogger::new(level, config, std::fs::File::create(log_file)?),
+        TermLogger::new(level, config, TerminalMode::Stderr),
+        file_logger(level, file_config, "daemon", log_file)?,
     ])
     .unwrap();
 
@@ -83,6 +91,7 @@ async fn main_async() -> anyhow::Result<()> {
     info!("Daemon started.");
     info!("Daemon pid: {}", config.pid);
     info!("Daemon port: {}", config.port);
+    info!("Daemon version: {}", config.version);
 
     let _service = DaemonService::spawn(&bus)?;
     let shutdown = bus.rx::<DaemonShutdown>()?;
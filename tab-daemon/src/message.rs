@@ -2,6 +2,8 @@ pub mod cli;
 pub mod daemon;
 pub mod listener;
 pub mod pty;
+pub mod status;
 pub mod tab;
 pub mod tab_assignment;
 pub mod tab_manager;
+pub mod tab_record;
@@ -1,8 +1,10 @@
 use super::tab::{TabOutput, TabScrollback};
+use crate::state::{activity::AttachmentWatch, tab::TabsState};
 
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    tab::{CreateTabMetadata, TabId, TabMetadata},
+    client::DaemonStatus,
+    tab::{CloseOptions, CreateTabMetadata, TabId, TabMetadata, TabSignal},
 };
 
 /// The CLI connection Send message.  Messaged on the CliBus, and
@@ -25,10 +27,20 @@ pub enum CliSend {
     RequestScrollback(TabId),
     /// Resizes the tab to the given number of (cols, rows)
     ResizeTab(TabId, (u16, u16)),
+    /// Notifies the daemon that the client is viewing the tab, until the attachment is dropped
+    Attach(AttachmentWatch),
     /// Closes the tab with the given ID
-    CloseTab(TabId),
-    /// Closes the tab with the given name, if one exists.
-    CloseNamedTab(String),
+    CloseTab(TabId, CloseOptions),
+    /// Closes the tabs matching the name, prefix (`proj/*`), or tag (`@backend`), if any exist.
+    CloseNamedTab(String, CloseOptions),
+    /// Renames the tab, if the name is not already in use
+    RenameTab(TabId, String),
+    /// Records the tab output to the given file, in asciicast v2 format
+    RecordTab(TabId, String),
+    /// Appends the tab output to the given log file
+    LogTab(TabId, String),
+    /// Delivers the signal to the foreground process group of the tab
+    Signal(TabId, TabSignal),
     /// Shuts down the Daemon and all PTY processes
     GlobalShutdown,
 }
@@ -45,11 +57,24 @@ pub enum CliSend {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CliRecv {
     /// A notification that a tab with the given metadata has started, and is ready for subscriptions.
+    /// Also sent when the metadata of a running tab has changed.
     TabStarted(TabMetadata),
+    /// A notification that the signal has been sent to the processes in a closing tab.
+    Closing(TabId, TabSignal),
     /// A notification that a tab has been terminated.
     TabStopped(TabId),
+    /// The state of the daemon, requested with a `CliStatusRequest`.
+    Status(DaemonStatus),
 }
 
+/// Requests the `DaemonStatus`, which is replied to as a `CliRecv::Status` message.
+///
+/// Usage:
+/// - Tx from `CliService`, when the client sends a `Request::Status`.
+/// - Rx into `ListenerConnectionCarrier`, which reads the status from the `ListenerBus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliStatusRequest {}
+
 /// A message sent to the command client's tab subscription service
 /// Maintains subscription state, buffers, and filters messages based on their byte position
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -63,6 +88,10 @@ pub enum CliSubscriptionRecv {
     Output(TabOutput),
     /// A notification that a tab has been retasked.  The client may need to request scrollback and change their subscriptions.
     Retask(TabId, TabId),
+    /// Requests a copy of the scrollback for the given tab, without changing the subscription.
+    Snapshot(TabId),
+    /// The currently running tabs, carried from the listener.  Provides the hooks which run on attach & detach.
+    Tabs(TabsState),
 }
 
 /// A message sent by the client's subscription state service
@@ -71,6 +100,8 @@ pub enum CliSubscriptionRecv {
 pub enum CliSubscriptionSend {
     Retask(TabId),
     Output(TabId, OutputChunk),
+    /// A copy of the scrollback for the given tab, requested with `CliSubscriptionRecv::Snapshot`
+    Snapshot(TabId, Vec<u8>),
 }
 
 /// Terminates the websocket connection & supporing services.
@@ -1,8 +1,9 @@
 use crate::state::pty::PtyScrollback;
+use std::path::PathBuf;
 
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    tab::TabMetadata,
+    tab::{CloseOptions, TabMetadata, TabProcess, TabSignal, TabStats},
 };
 
 /// Terminates the PTY connection & supporting services.
@@ -15,6 +16,7 @@ pub struct PtyShutdown {}
 /// Usage:
 /// - Tx from `PtyService`, to communicate initialization to the daemon and to provide events for CLI connections.
 /// - Rx from `PtyScrollbackService`, to collect scrollback in the daemon process.
+/// - Rx from `PtyLogService`, to open tab log files.
 #[derive(Debug, Clone)]
 pub enum PtyRecv {
     Init(TabMetadata),
@@ -22,6 +24,14 @@ pub enum PtyRecv {
     /// Resizes to the given number of (cols, rows)
     Resize((u16, u16)),
     Input(InputChunk),
+    /// Appends the tab output to the given log file
+    Log(PathBuf),
+    /// Delivers the signal to the foreground process group of the pty
+    Signal(TabSignal),
+    /// Renames the tab, and migrates the shell history
+    Rename(String),
+    /// Closes the tab, escalating from SIGHUP to SIGTERM to SIGKILL
+    Close(CloseOptions),
     Terminate,
 }
 
@@ -37,7 +47,19 @@ pub enum PtySend {
     Started(TabMetadata),
     Output(OutputChunk),
     Scrollback(PtyScrollback),
+    /// The foreground process or working directory of the tab has changed
+    Process(TabProcess),
+    /// The resource usage of the processes in the tab has changed
+    Stats(TabStats),
+    /// The signal has been sent to the processes in the closing tab
+    Closing(TabSignal),
     Stopped,
+    /// The PTY process is running a tab from a previous daemon, and requests that it be re-registered.
+    Resume(TabMetadata),
+    /// The process id of the PTY process, sent when it connects
+    Pid(u32),
+    /// Output which was sent to a previous daemon, and is replayed into the scrollback of a resumed tab
+    Replay(OutputChunk),
 }
 
 impl PartialEq for PtySend {
@@ -68,6 +90,24 @@ impl PartialEq for PtySend {
                     return false;
                 }
             }
+            PtySend::Process(process) => {
+                matches!(other, PtySend::Process(other_process) if process == other_process)
+            }
+            PtySend::Stats(stats) => {
+                matches!(other, PtySend::Stats(other_stats) if stats == other_stats)
+            }
+            PtySend::Closing(signal) => {
+                matches!(other, PtySend::Closing(other_signal) if signal == other_signal)
+            }
+            PtySend::Replay(output) => {
+                matches!(other, PtySend::Replay(other_output) if output == other_output)
+            }
+            PtySend::Resume(tab) => {
+                matches!(other, PtySend::Resume(other_tab) if tab == other_tab)
+            }
+            PtySend::Pid(pid) => {
+                matches!(other, PtySend::Pid(other_pid) if pid == other_pid)
+            }
         }
     }
 }
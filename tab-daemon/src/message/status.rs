@@ -0,0 +1,22 @@
+use tab_api::tab::TabId;
+
+/// Connection events, which are summarized in the `DaemonStatus`.
+///
+/// Carried over the `ListenerBus`
+///
+/// Usage:
+/// - Tx from the `ListenerService`, as CLI and PTY connections are opened and closed.
+/// - Tx from the `ListenerPtyCarrier`, when a tab is assigned to a PTY process, and when the PTY process starts the tab.
+/// - Rx into the `StatusService`, which publishes the `DaemonStatus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusRecv {
+    CliConnected,
+    CliDisconnected,
+    PtyConnected,
+    /// The PTY connection has closed.  Contains the tab which was assigned to the connection, if any.
+    PtyDisconnected(Option<TabId>),
+    /// The tab has been assigned to the PTY connection, which is no longer idle
+    PtyAssigned(TabId),
+    /// The PTY process has started the tab.  Contains the pid of the process, if it was reported.
+    PtyStarted(TabId, Option<u32>),
+}
@@ -1,8 +1,8 @@
 use crate::state::{assignment::Assignment, pty::PtyScrollback};
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
-    tab::{TabId, TabMetadata},
+    tab::{CloseOptions, TabId, TabMetadata, TabSignal},
 };
 
 /// An input (stdin) event for tab, identified by an id.
@@ -69,8 +69,16 @@ pub enum TabRecv {
     // Retasks all clients from the first tab, to the second
     Retask(TabId, TabId),
     Input(TabInput),
+    /// Renames the tab, and migrates the shell history
+    Rename(TabId, String),
+    /// Closes the tab gracefully, escalating from SIGHUP to SIGTERM to SIGKILL
+    Close(TabId, CloseOptions),
     Terminate(TabId),
     TerminateAll,
+    /// Appends the tab output to the given log file
+    Log(TabId, PathBuf),
+    /// Delivers the signal to the foreground process group of the tab
+    Signal(TabId, TabSignal),
 }
 
 /// A cheaply clonable message with the latest tab scrollback.
@@ -118,12 +126,17 @@ impl TabScrollback {
 /// Usage:
 /// - Tx from the `ListenerPtyCarrier` to forward raw PTY events.
 /// - Tx from the `RetaskService`, to provide retask notifications to subscribed CLI connections
+/// - Tx from the `TabManagerService`, to notify CLI connections of updated tab metadata
 /// - Rx from the `ListenerConnectionCarrier`, to forward notifications to subscribed CLI connections
 #[derive(Debug, Clone)]
 pub enum TabSend {
     Started(TabMetadata),
+    /// The metadata of a running tab has changed (e.g. the foreground process)
+    Updated(TabMetadata),
     Scrollback(TabScrollback),
     Retask(TabId, TabId),
     Output(TabOutput),
+    /// The signal has been sent to the processes in the closing tab
+    Closing(TabId, TabSignal),
     Stopped(TabId),
 }
@@ -1,4 +1,6 @@
-use tab_api::tab::{CreateTabMetadata, TabId};
+use std::sync::{Arc, Mutex};
+use tab_api::tab::{CloseOptions, CreateTabMetadata, TabId, TabMetadata, TabProcess, TabStats};
+use tokio::sync::oneshot;
 
 /// A message received by the `TabManagerService`, which manages the tab lifecycle and assigns tabs to PTY connections.
 ///
@@ -6,15 +8,82 @@ use tab_api::tab::{CreateTabMetadata, TabId};
 ///
 /// Usage:
 /// - Rx from the `TabManagerService`, which creates & closes active tabs.
-/// - Tx into the `ListenerConnectionCarrier`, to request that tabs be created/closed from a CLI connection.
-/// - Tx into the `ListenerPtyCarrier`, to notify the manager that a PTY process is terminating (e.g. user typed `exit`)
+/// - Tx into the `ListenerConnectionCarrier`, to request that tabs be created/closed/renamed from a CLI connection,
+///   or to notify the manager of a global shutdown.
+/// - Tx from the `TabAssignmentService`, when the assignment of a closed tab is cancelled.
+/// - Tx into the `ListenerPtyCarrier`, to notify the manager that a PTY process accepted a tab, that it is terminating (e.g. user typed `exit`),
+///   that a PTY process from a previous daemon is resuming a running tab (which the manager accepts or rejects),
+///   or that the foreground process of a tab has changed.
+/// - Tx from the `TabManagerService` itself, when a closing tab misses the close deadline.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TabManagerRecv {
     CreateTab(CreateTabMetadata),
-    CloseNamedTab(String),
-    CloseTab(TabId),
+    /// Closes the tabs matching the selector, which is a name, a prefix (`proj/*`), or a tag (`@backend`)
+    CloseNamedTab(String, CloseOptions),
+    CloseTab(TabId, CloseOptions),
+    /// A PTY process has accepted the assignment of the tab
+    TabAssigned(TabId),
+    /// The PTY process has stopped, and the tab should be removed
+    TabStopped(TabId),
+    /// The tab was closed gracefully, but the PTY did not stop within the close deadline
+    CloseExpired(TabId),
+    /// The assignment of a closed tab was cancelled before a PTY accepted it, so the id can be released
+    AssignmentCancelled(TabId),
+    /// A PTY process from a previous daemon requests that its tab is resumed
+    ResumeTab(ResumeTab),
+    /// Renames the tab, if the name is not already in use
+    RenameTab(TabId, String),
+    UpdateProcess(TabId, TabProcess),
+    UpdateStats(TabId, TabStats),
+    /// The tab output is being appended to the log file
+    UpdateLog(TabId, String),
+    /// The daemon is shutting down, and the running tabs are terminated
+    Shutdown,
 }
 
+/// A request to resume a running tab from a previous daemon.
+/// The `TabManagerService` replies with `accept` or `reject`, and the PTY connection is only assigned to the tab if it is accepted.
+#[derive(Debug, Clone)]
+pub struct ResumeTab {
+    pub metadata: TabMetadata,
+    reply: Arc<Mutex<Option<oneshot::Sender<bool>>>>,
+}
+
+impl ResumeTab {
+    /// Creates the request, and a receiver which resolves to true if the tab is resumed
+    pub fn new(metadata: TabMetadata) -> (Self, oneshot::Receiver<bool>) {
+        let (tx, rx) = oneshot::channel();
+        let request = Self {
+            metadata,
+            reply: Arc::new(Mutex::new(Some(tx))),
+        };
+
+        (request, rx)
+    }
+
+    pub fn accept(&self) {
+        self.reply(true);
+    }
+
+    pub fn reject(&self) {
+        self.reply(false);
+    }
+
+    fn reply(&self, resumed: bool) {
+        if let Some(tx) = self.reply.lock().ok().and_then(|mut reply| reply.take()) {
+            tx.send(resumed).ok();
+        }
+    }
+}
+
+impl PartialEq for ResumeTab {
+    fn eq(&self, other: &Self) -> bool {
+        self.metadata == other.metadata
+    }
+}
+
+impl Eq for ResumeTab {}
+
 /// A message sent by the `TabManagerService`, which notifies CLI connections of a closing tab.
 ///
 /// Carried over the `ListenerBus`
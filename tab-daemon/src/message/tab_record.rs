@@ -0,0 +1,17 @@
+use std::path::PathBuf;
+use tab_api::tab::TabId;
+
+/// Requests that the output of a tab be recorded to a file, in asciicast v2 format.
+/// If the tab is already being recorded, the previous recording is finished.
+///
+/// Carried over the `ListenerBus`
+///
+/// Usage:
+/// - Tx from the `TabManagerService`, if a recording was configured when the tab was created.
+/// - Tx from the `ListenerConnectionCarrier`, if a CLI connection requests a recording.
+/// - Rx into the `TabRecordService`, which writes the recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordTab {
+    pub id: TabId,
+    pub path: PathBuf,
+}
@@ -1,11 +1,11 @@
 // mod session;
 use crate::message::cli::{
-    CliRecv, CliSend, CliShutdown, CliSubscriptionRecv, CliSubscriptionSend,
+    CliRecv, CliSend, CliShutdown, CliStatusRequest, CliSubscriptionRecv, CliSubscriptionSend,
 };
 use crate::prelude::*;
-use crate::state::tab::TabsState;
+use crate::state::{activity::TabsActivityState, tab::TabsState};
 use anyhow::Context;
-use tab_api::client::InitResponse;
+use tab_api::{client::InitResponse, log_file::LogScope};
 
 use tokio::stream::StreamExt;
 
@@ -25,31 +25,61 @@ impl Service for CliService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let scope = bus.resource::<LogScope>().unwrap_or_default();
+
         let _init = {
             let mut tx_websocket = bus.tx::<Response>()?;
             let mut rx_tabs_state = bus.rx::<TabsState>()?;
+            let mut rx_activity = bus.rx::<TabsActivityState>()?;
+
+            Self::try_task(
+                "init",
+                scope.scope(async move {
+                    let tabs = rx_tabs_state
+                        .recv()
+                        .await
+                        .ok_or_else(|| anyhow::Error::msg("rx TabsState closed"))?;
+
+                    let mut activity = rx_activity
+                        .recv()
+                        .await
+                        .ok_or_else(|| anyhow::Error::msg("rx TabsActivityState closed"))?;
+
+                    let init = InitResponse {
+                        tabs: tabs.tabs.clone(),
+                        activity: activity.tabs.clone(),
+                    };
+
+                    let init = Response::Init(init);
+                    tx_websocket.send(init).await?;
+
+                    for tab in tabs.tabs.values() {
+                        debug!("notifying client of existing tab {}", &tab.name);
+                        let message = Response::TabUpdate(tab.clone());
+                        tx_websocket.send(message).await?;
+                    }
 
-            Self::try_task("init", async move {
-                let tabs = rx_tabs_state
-                    .recv()
-                    .await
-                    .ok_or_else(|| anyhow::Error::msg("rx TabsState closed"))?;
-
-                let init = InitResponse {
-                    tabs: tabs.tabs.clone(),
-                };
-
-                let init = Response::Init(init);
-                tx_websocket.send(init).await?;
+                    // notify the client when tabs are marked unread, rang the bell, or went silent
+                    while let Some(update) = rx_activity.recv().await {
+                        for (id, tab_activity) in update.tabs.iter() {
+                            let changed = activity
+                                .tabs
+                                .get(id)
+                                .map(|previous| previous.flags_differ(tab_activity))
+                                .unwrap_or(true);
+
+                            if changed {
+                                let message = Response::TabActivity(*id, tab_activity.clone());
+                                tx_websocket.send(message).await?;
+                            }
+                        }
 
-                for tab in tabs.tabs.values() {
-                    debug!("notifying client of existing tab {}", &tab.name);
-                    let message = Response::TabUpdate(tab.clone());
-                    tx_websocket.send(message).await?;
-                }
+                        activity = update;
+                    }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _rx_websocket = {
@@ -57,19 +87,29 @@ impl Service for CliService {
 
             let mut tx_daemon = bus.tx::<CliSend>()?;
             let mut tx_subscription = bus.tx::<CliSubscriptionRecv>()?;
+            let mut tx_status = bus.tx::<CliStatusRequest>()?;
             let mut tx_shutdown = bus.tx::<CliShutdown>()?;
 
-            Self::try_task("run", async move {
-                debug!("cli connection waiting for messages");
-
-                while let Some(msg) = rx.recv().await {
-                    Self::recv_websocket(msg, &mut tx_subscription, &mut tx_daemon).await?
-                }
+            Self::try_task(
+                "run",
+                scope.scope(async move {
+                    debug!("cli connection waiting for messages");
+
+                    while let Some(msg) = rx.recv().await {
+                        Self::recv_websocket(
+                            msg,
+                            &mut tx_subscription,
+                            &mut tx_daemon,
+                            &mut tx_status,
+                        )
+                        .await?
+                    }
 
-                tx_shutdown.send(CliShutdown {}).await?;
+                    tx_shutdown.send(CliShutdown {}).await?;
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _rx_daemon = {
@@ -77,13 +117,16 @@ impl Service for CliService {
 
             let mut tx_websocket = bus.tx::<Response>()?;
 
-            Self::try_task("run", async move {
-                while let Some(msg) = rx.next().await {
-                    Self::recv_daemon(msg, &mut tx_websocket).await?
-                }
+            Self::try_task(
+                "run",
+                scope.scope(async move {
+                    while let Some(msg) = rx.next().await {
+                        Self::recv_daemon(msg, &mut tx_websocket).await?
+                    }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _rx_subscription = {
@@ -91,22 +134,28 @@ impl Service for CliService {
 
             let mut tx = bus.tx::<Response>()?;
 
-            Self::try_task("run", async move {
-                debug!("cli connection waiting for messages");
-
-                while let Some(msg) = rx.recv().await {
-                    match msg {
-                        CliSubscriptionSend::Retask(id) => {
-                            tx.send(Response::Retask(id)).await?;
-                        }
-                        CliSubscriptionSend::Output(id, chunk) => {
-                            tx.send(Response::Output(id, chunk)).await?;
+            Self::try_task(
+                "run",
+                scope.scope(async move {
+                    debug!("cli connection waiting for messages");
+
+                    while let Some(msg) = rx.recv().await {
+                        match msg {
+                            CliSubscriptionSend::Retask(id) => {
+                                tx.send(Response::Retask(id)).await?;
+                            }
+                            CliSubscriptionSend::Output(id, chunk) => {
+                                tx.send(Response::Output(id, chunk)).await?;
+                            }
+                            CliSubscriptionSend::Snapshot(id, data) => {
+                                tx.send(Response::Scrollback(id, data)).await?;
+                            }
                         }
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         Ok(CliService {
@@ -123,6 +172,7 @@ impl CliService {
         request: Request,
         tx_subscription: &mut impl Sender<CliSubscriptionRecv>,
         tx_daemon: &mut impl Sender<CliSend>,
+        tx_status: &mut impl Sender<CliStatusRequest>,
     ) -> anyhow::Result<()> {
         debug!("received Request: {:?}", &request);
 
@@ -150,16 +200,42 @@ impl CliService {
                 let message = CliSend::CreateTab(create);
                 tx_daemon.send(message).await.context("tx_daemon closed")?;
             }
+            Request::Scrollback(id) => {
+                tx_subscription
+                    .send(CliSubscriptionRecv::Snapshot(id))
+                    .await
+                    .context("tx_subscription closed")?;
+            }
             Request::ResizeTab(id, dimensions) => {
                 debug!("resizing tab {} to {:?}", id.0, dimensions);
                 tx_daemon.send(CliSend::ResizeTab(id, dimensions)).await?;
             }
-            Request::CloseTab(id) => {
-                let message = CliSend::CloseTab(id);
+            Request::CloseTab(id, options) => {
+                let message = CliSend::CloseTab(id, options);
                 tx_daemon.send(message).await.context("tx_daemon closed")?;
             }
-            Request::CloseNamedTab(name) => {
-                let message = CliSend::CloseNamedTab(name);
+            Request::CloseNamedTab(name, options) => {
+                let message = CliSend::CloseNamedTab(name, options);
+                tx_daemon.send(message).await.context("tx_daemon closed")?;
+            }
+            Request::RenameTab(id, name) => {
+                debug!("renaming tab {} to {}", id, &name);
+                let message = CliSend::RenameTab(id, name);
+                tx_daemon.send(message).await.context("tx_daemon closed")?;
+            }
+            Request::LogTab(id, path) => {
+                debug!("logging tab {} to {}", id, &path);
+                let message = CliSend::LogTab(id, path);
+                tx_daemon.send(message).await.context("tx_daemon closed")?;
+            }
+            Request::Signal(id, signal) => {
+                debug!("sending {} to tab {}", signal, id);
+                let message = CliSend::Signal(id, signal);
+                tx_daemon.send(message).await.context("tx_daemon closed")?;
+            }
+            Request::RecordTab(id, path) => {
+                debug!("recording tab {} to {}", id, &path);
+                let message = CliSend::RecordTab(id, path);
                 tx_daemon.send(message).await.context("tx_daemon closed")?;
             }
             Request::Retask(id, name) => {
@@ -170,6 +246,12 @@ impl CliService {
             Request::GlobalShutdown => {
                 tx_daemon.send(CliSend::GlobalShutdown).await?;
             }
+            Request::Status => {
+                tx_status
+                    .send(CliStatusRequest {})
+                    .await
+                    .context("tx_status closed")?;
+            }
         }
 
         Ok(())
@@ -187,6 +269,12 @@ impl CliService {
                     .await
                     .context("tx_websocket closed")?;
             }
+            CliRecv::Closing(id, signal) => {
+                tx_websocket
+                    .send(Response::TabClosing(id, signal))
+                    .await
+                    .context("tx_websocket closed")?;
+            }
             CliRecv::TabStopped(id) => {
                 debug!("notifying client of stopped tab: {}", id);
                 tx_websocket
@@ -194,6 +282,12 @@ impl CliService {
                     .await
                     .context("tx_websocket closed")?;
             }
+            CliRecv::Status(status) => {
+                tx_websocket
+                    .send(Response::Status(status))
+                    .await
+                    .context("tx_websocket closed")?;
+            }
         }
         Ok(())
     }
@@ -203,15 +297,16 @@ impl CliService {
 mod request_tests {
     use super::CliService;
     use crate::{
-        bus::CliBus, message::cli::CliSend, message::cli::CliSubscriptionRecv,
+        bus::CliBus, message::cli::CliSend, message::cli::CliStatusRequest,
+        message::cli::CliSubscriptionRecv, state::activity::TabsActivityState,
         state::tab::TabsState,
     };
-    use lifeline::{assert_completes, Bus, Receiver, Sender, Service};
+    use lifeline::{assert_completes, assert_times_out, Bus, Receiver, Sender, Service};
     use std::collections::HashMap;
     use tab_api::{
         chunk::InputChunk,
         client::{InitResponse, Request, Response},
-        tab::{CreateTabMetadata, TabId, TabMetadata},
+        tab::{CloseOptions, CreateTabMetadata, TabActivity, TabId, TabMetadata, TabSignal},
     };
 
     #[tokio::test]
@@ -228,10 +323,25 @@ mod request_tests {
             dimensions: (1, 2),
             shell: "bash".into(),
             dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
         };
         tabs.tabs.insert(tab_id, tab_metadata.clone());
         tx.send(tabs).await?;
 
+        let mut tx_activity = cli_bus.tx::<TabsActivityState>()?;
+        let mut activity = TabsActivityState::default();
+        let tab_activity = TabActivity {
+            unread: true,
+            ..Default::default()
+        };
+        activity.tabs.insert(tab_id, tab_activity.clone());
+        tx_activity.send(activity).await?;
+
         let _service = CliService::spawn(&cli_bus)?;
         let mut rx = cli_bus.rx::<Response>()?;
 
@@ -240,8 +350,10 @@ mod request_tests {
 
             let mut expect_tabs = InitResponse {
                 tabs: HashMap::new(),
+                activity: HashMap::new(),
             };
             expect_tabs.tabs.insert(tab_id, tab_metadata.clone());
+            expect_tabs.activity.insert(tab_id, tab_activity);
             assert_eq!(Some(Response::Init(expect_tabs)), init);
 
             let tab_update = rx.recv().await;
@@ -251,6 +363,47 @@ mod request_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn activity() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+
+        let mut tx = cli_bus.tx::<TabsState>()?;
+        tx.send(TabsState::default()).await?;
+
+        let mut tx_activity = cli_bus.tx::<TabsActivityState>()?;
+        tx_activity.send(TabsActivityState::default()).await?;
+
+        let _service = CliService::spawn(&cli_bus)?;
+        let mut rx = cli_bus.rx::<Response>()?;
+
+        let mut activity = TabsActivityState::default();
+        let tab_activity = TabActivity {
+            unread: true,
+            bell: true,
+            ..Default::default()
+        };
+        activity.tabs.insert(TabId(0), tab_activity.clone());
+        tx_activity.send(activity.clone()).await?;
+
+        // last_output changes, but the flags do not
+        activity.tabs.get_mut(&TabId(0)).unwrap().last_output = Some(1);
+        tx_activity.send(activity).await?;
+
+        assert_completes!(async {
+            let init = rx.recv().await;
+            assert!(matches!(init, Some(Response::Init(_))));
+
+            let msg = rx.recv().await;
+            assert_eq!(Some(Response::TabActivity(TabId(0), tab_activity)), msg);
+        });
+
+        assert_times_out!(async move {
+            rx.recv().await;
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn subscribe() -> anyhow::Result<()> {
         let cli_bus = CliBus::default();
@@ -319,6 +472,11 @@ mod request_tests {
             dimensions: (1, 2),
             shell: "shell".into(),
             dir: "/".into(),
+            record: None,
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
         };
         tx.send(Request::CreateTab(tab.clone())).await?;
 
@@ -356,11 +514,12 @@ mod request_tests {
         let mut tx = cli_bus.tx::<Request>()?;
         let mut rx = cli_bus.rx::<CliSend>()?;
 
-        tx.send(Request::CloseTab(TabId(0))).await?;
+        let options = CloseOptions::default();
+        tx.send(Request::CloseTab(TabId(0), options)).await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert_eq!(Some(CliSend::CloseTab(TabId(0))), msg);
+            assert_eq!(Some(CliSend::CloseTab(TabId(0), options)), msg);
         });
 
         Ok(())
@@ -374,11 +533,109 @@ mod request_tests {
         let mut tx = cli_bus.tx::<Request>()?;
         let mut rx = cli_bus.rx::<CliSend>()?;
 
-        tx.send(Request::CloseNamedTab("tab".into())).await?;
+        let options = CloseOptions {
+            force: true,
+            grace_period: 1,
+        };
+        tx.send(Request::CloseNamedTab("tab".into(), options))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSend::CloseNamedTab("tab".into(), options)), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rename_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+
+        tx.send(Request::RenameTab(TabId(0), "new/".into())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSend::RenameTab(TabId(0), "new/".into())), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+
+        tx.send(Request::RecordTab(TabId(0), "/tab.cast".into()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSend::RecordTab(TabId(0), "/tab.cast".into())), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scrollback() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSubscriptionRecv>()?;
+
+        tx.send(Request::Scrollback(TabId(0))).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSubscriptionRecv::Snapshot(TabId(0))), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log_tab() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+
+        tx.send(Request::LogTab(TabId(0), "/tab.log".into()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliSend::LogTab(TabId(0), "/tab.log".into())), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signal() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliSend>()?;
+
+        tx.send(Request::Signal(TabId(0), TabSignal::Hangup))
+            .await?;
 
         assert_completes!(async move {
             let msg = rx.recv().await;
-            assert_eq!(Some(CliSend::CloseNamedTab("tab".into())), msg);
+            assert_eq!(Some(CliSend::Signal(TabId(0), TabSignal::Hangup)), msg);
         });
 
         Ok(())
@@ -419,6 +676,24 @@ mod request_tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn status() -> anyhow::Result<()> {
+        let cli_bus = CliBus::default();
+        let _service = CliService::spawn(&cli_bus)?;
+
+        let mut tx = cli_bus.tx::<Request>()?;
+        let mut rx = cli_bus.rx::<CliStatusRequest>()?;
+
+        tx.send(Request::Status).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(CliStatusRequest {}), msg);
+        });
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -428,7 +703,7 @@ mod recv_tests {
     use lifeline::{assert_completes, Bus, Receiver, Sender, Service};
     use tab_api::{
         client::Response,
-        tab::{TabId, TabMetadata},
+        tab::{TabId, TabMetadata, TabSignal},
     };
 
     #[tokio::test]
@@ -445,6 +720,12 @@ mod recv_tests {
             dimensions: (1, 2),
             shell: "shell".into(),
             dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
         };
 
         tx.send(CliRecv::TabStarted(metadata.clone())).await?;
@@ -474,4 +755,26 @@ mod recv_tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn closing() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliRecv>()?;
+        let mut rx = bus.rx::<Response>()?;
+
+        tx.send(CliRecv::Closing(TabId(0), TabSignal::Terminate))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(Response::TabClosing(TabId(0), TabSignal::Terminate)),
+                msg
+            );
+        });
+
+        Ok(())
+    }
 }
@@ -1,8 +1,14 @@
-use tab_api::{chunk::OutputChunk, tab::TabId};
+use std::collections::HashSet;
+use tab_api::{chunk::OutputChunk, log_file::LogScope, tab::TabId};
 
 use crate::{
-    message::cli::CliSend, message::cli::CliSubscriptionRecv, message::cli::CliSubscriptionSend,
+    hooks::DetachHook,
+    message::cli::CliSend,
+    message::cli::CliSubscriptionRecv,
+    message::cli::CliSubscriptionSend,
     prelude::*,
+    state::activity::{attachment, Attachment},
+    state::tab::TabsState,
 };
 use anyhow::Context;
 
@@ -15,76 +21,115 @@ impl Service for CliSubscriptionService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let scope = bus.resource::<LogScope>().unwrap_or_default();
+
         let _rx = {
             let mut rx = bus.rx::<CliSubscriptionRecv>()?.log();
             let mut tx = bus.tx::<CliSubscriptionSend>()?;
             let mut tx_daemon = bus.tx::<CliSend>()?;
 
-            Self::try_task("rx", async move {
-                let mut state = SubscriptionState::None;
-                while let Some(msg) = rx.recv().await {
-                    debug!("subscription state: {:?}", &state);
-                    match msg {
-                        CliSubscriptionRecv::Subscribe(id) => {
-                            if state.is_selected(id) {
-                                continue;
-                            }
+            Self::try_task(
+                "rx",
+                scope.scope(async move {
+                    let mut state = SubscriptionState::None;
+                    let mut tabs = TabsState::default();
+                    let mut snapshots = HashSet::new();
+                    let mut _attachment: Option<(Attachment, Option<DetachHook>)> = None;
+                    while let Some(msg) = rx.recv().await {
+                        debug!("subscription state: {:?}", &state);
+                        match msg {
+                            CliSubscriptionRecv::Subscribe(id) => {
+                                if state.is_selected(id) {
+                                    continue;
+                                }
 
-                            tx_daemon.send(CliSend::RequestScrollback(id)).await?;
-                            state = SubscriptionState::AwaitingScrollback(id, Vec::new());
-                        }
-                        CliSubscriptionRecv::Unsubscribe(id) => {
-                            if state.is_selected(id) {
-                                state = SubscriptionState::None;
+                                tx_daemon.send(CliSend::RequestScrollback(id)).await?;
+                                // detach from the previous tab first, so the hooks run in order
+                                _attachment = None;
+                                _attachment = Some(Self::attach(id, &tabs, &mut tx_daemon).await?);
+                                state = SubscriptionState::AwaitingScrollback(id, Vec::new());
                             }
-                        }
-                        CliSubscriptionRecv::Scrollback(scrollback) => {
-                            if !state.is_selected(scrollback.id) {
-                                continue;
+                            CliSubscriptionRecv::Unsubscribe(id) => {
+                                if state.is_selected(id) {
+                                    _attachment = None;
+                                    state = SubscriptionState::None;
+                                }
                             }
-
-                            if let SubscriptionState::AwaitingScrollback(id, buffer) = state {
-                                let mut index = 0usize;
-
-                                for chunk in scrollback.scrollback().await {
-                                    index = Self::send_output(id, index, chunk, &mut tx).await?;
+                            CliSubscriptionRecv::Tabs(update) => {
+                                tabs = update;
+                            }
+                            CliSubscriptionRecv::Snapshot(id) => {
+                                if snapshots.insert(id) {
+                                    tx_daemon.send(CliSend::RequestScrollback(id)).await?;
+                                }
+                            }
+                            CliSubscriptionRecv::Scrollback(scrollback) => {
+                                if snapshots.remove(&scrollback.id) {
+                                    let mut data = Vec::new();
+                                    for mut chunk in scrollback.scrollback().await {
+                                        data.append(&mut chunk.data);
+                                    }
+
+                                    let response =
+                                        CliSubscriptionSend::Snapshot(scrollback.id, data);
+                                    tx.send(response).await?;
                                 }
 
-                                for chunk in buffer {
-                                    index = Self::send_output(id, index, chunk, &mut tx).await?;
+                                if !state.is_selected(scrollback.id) {
+                                    continue;
                                 }
 
-                                state = SubscriptionState::Selected(id, index);
-                            }
-                        }
-                        CliSubscriptionRecv::Retask(from, to) => {
-                            if state.is_selected(from) {
-                                info!("Retasking subscription from {:?} to {:?}", from, to);
+                                if let SubscriptionState::AwaitingScrollback(id, buffer) = state {
+                                    let mut index = 0usize;
+
+                                    for chunk in scrollback.scrollback().await {
+                                        index =
+                                            Self::send_output(id, index, chunk, &mut tx).await?;
+                                    }
 
-                                tx_daemon.send(CliSend::RequestScrollback(to)).await?;
-                                tx.send(CliSubscriptionSend::Retask(to)).await?;
+                                    for chunk in buffer {
+                                        index =
+                                            Self::send_output(id, index, chunk, &mut tx).await?;
+                                    }
 
-                                state = SubscriptionState::AwaitingScrollback(to, Vec::new());
+                                    state = SubscriptionState::Selected(id, index);
+                                }
                             }
-                        }
-                        CliSubscriptionRecv::Output(output) => {
-                            if let SubscriptionState::AwaitingScrollback(id, ref mut buffer) = state
-                            {
-                                if id == output.id {
-                                    let chunk = OutputChunk::clone(output.stdout.as_ref());
-                                    buffer.push(chunk);
+                            CliSubscriptionRecv::Retask(from, to) => {
+                                if state.is_selected(from) {
+                                    info!("Retasking subscription from {:?} to {:?}", from, to);
+
+                                    tx_daemon.send(CliSend::RequestScrollback(to)).await?;
+                                    _attachment = None;
+                                    _attachment =
+                                        Some(Self::attach(to, &tabs, &mut tx_daemon).await?);
+                                    tx.send(CliSubscriptionSend::Retask(to)).await?;
+
+                                    state = SubscriptionState::AwaitingScrollback(to, Vec::new());
                                 }
-                            } else if let SubscriptionState::Selected(id, ref mut index) = state {
-                                if id == output.id {
-                                    let chunk = OutputChunk::clone(output.stdout.as_ref());
-                                    *index = Self::send_output(id, *index, chunk, &mut tx).await?;
+                            }
+                            CliSubscriptionRecv::Output(output) => {
+                                if let SubscriptionState::AwaitingScrollback(id, ref mut buffer) =
+                                    state
+                                {
+                                    if id == output.id {
+                                        let chunk = OutputChunk::clone(output.stdout.as_ref());
+                                        buffer.push(chunk);
+                                    }
+                                } else if let SubscriptionState::Selected(id, ref mut index) = state
+                                {
+                                    if id == output.id {
+                                        let chunk = OutputChunk::clone(output.stdout.as_ref());
+                                        *index =
+                                            Self::send_output(id, *index, chunk, &mut tx).await?;
+                                    }
                                 }
                             }
                         }
                     }
-                }
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         Ok(Self { _rx })
@@ -92,6 +137,21 @@ impl Service for CliSubscriptionService {
 }
 
 impl CliSubscriptionService {
+    /// Attaches to the tab, until the returned value is dropped.
+    /// Runs the attach hook of the tab, and the detach hook when the value is dropped.
+    async fn attach(
+        id: TabId,
+        tabs: &TabsState,
+        tx: &mut impl Sender<CliSend>,
+    ) -> anyhow::Result<(Attachment, Option<DetachHook>)> {
+        let (attachment, watch) = attachment(id);
+        tx.send(CliSend::Attach(watch)).await?;
+
+        let hook = tabs.tabs.get(&id).cloned().map(DetachHook::attach);
+
+        Ok((attachment, hook))
+    }
+
     async fn send_output(
         id: TabId,
         index: usize,
@@ -234,6 +294,47 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn snapshot() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx = bus.rx::<CliSubscriptionSend>()?;
+        let mut rx_daemon = bus.rx::<CliSend>()?;
+
+        tx.send(CliSubscriptionRecv::Snapshot(TabId(0))).await?;
+
+        assert_completes!(async {
+            let msg = rx_daemon.recv().await;
+            assert_eq!(Some(CliSend::RequestScrollback(TabId(0))), msg);
+        });
+
+        let scrollback = TabScrollback::empty(TabId(0));
+        for (index, data) in [(0, vec![1, 2]), (2, vec![3])] {
+            scrollback.push(OutputChunk { index, data }).await;
+        }
+
+        tx.send(CliSubscriptionRecv::Scrollback(scrollback)).await?;
+
+        assert_completes!(async {
+            let msg = rx.recv().await;
+            assert_eq!(
+                Some(CliSubscriptionSend::Snapshot(TabId(0), vec![1, 2, 3])),
+                msg
+            );
+        });
+
+        // the snapshot does not subscribe to the tab
+        tx_chunk(&mut tx, TabId(0), 3, vec![4]).await?;
+
+        assert_times_out!(async {
+            rx.recv().await;
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn scrollback_ignored_unsubscribed() -> anyhow::Result<()> {
         let bus = CliBus::default();
@@ -399,8 +500,46 @@ mod tests {
             let msg = rx_daemon.recv().await;
             assert_eq!(Some(CliSend::RequestScrollback(TabId(0))), msg);
 
+            let msg = rx_daemon.recv().await;
+            assert!(matches!(msg, Some(CliSend::Attach(watch)) if watch.id == TabId(0)));
+
             let msg = rx_daemon.recv().await;
             assert_eq!(Some(CliSend::RequestScrollback(TabId(1))), msg);
+
+            let msg = rx_daemon.recv().await;
+            assert!(matches!(msg, Some(CliSend::Attach(watch)) if watch.id == TabId(1)));
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn attach() -> anyhow::Result<()> {
+        let bus = CliBus::default();
+        let _service = CliSubscriptionService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<CliSubscriptionRecv>()?;
+        let mut rx_daemon = bus.rx::<CliSend>()?;
+
+        tx_subscribe(&mut tx, TabId(0)).await?;
+
+        let watch = assert_completes!(async {
+            let msg = rx_daemon.recv().await;
+            assert_eq!(Some(CliSend::RequestScrollback(TabId(0))), msg);
+
+            match rx_daemon.recv().await {
+                Some(CliSend::Attach(watch)) => watch,
+                msg => panic!("expected attach, received {:?}", msg),
+            }
+        });
+
+        assert!(watch.is_attached());
+
+        tx.send(CliSubscriptionRecv::Unsubscribe(TabId(0))).await?;
+        assert_completes!(async {
+            while watch.is_attached() {
+                tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+            }
         });
 
         Ok(())
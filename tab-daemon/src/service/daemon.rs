@@ -6,9 +6,13 @@ use time::Duration;
 use tokio::time;
 
 mod listener;
+mod pty_pool;
 mod retask;
+mod status;
+mod tab_activity;
 mod tab_assignment;
 mod tab_manager;
+mod tab_record;
 
 /// The main service for a tab-daemon service.  Spawns websocket listeners, and manages shutdown.
 pub struct DaemonService {
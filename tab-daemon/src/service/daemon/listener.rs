@@ -1,18 +1,30 @@
 use super::{
-    retask::RetaskService, tab_assignment::TabAssignmentService, tab_manager::TabManagerService,
+    pty_pool::PtyPoolService,
+    retask::RetaskService,
+    status::StatusService,
+    tab_activity::{ActivitySummaryService, TabActivityService},
+    tab_assignment::TabAssignmentService,
+    tab_manager::TabManagerService,
+    tab_record::TabRecordService,
 };
 use crate::{
     message::{
         cli::CliShutdown,
         pty::{PtyRecv, PtySend, PtyShutdown},
+        status::StatusRecv,
         tab::{TabRecv, TabSend},
     },
     service::{cli::CliService, pty::PtyService},
+    state::pty::PtyState,
 };
 use crate::{prelude::*, service::cli::subscription::CliSubscriptionService};
 use anyhow::Context;
 
 use lifeline::dyn_bus::DynBus;
+use tab_api::{
+    config::DaemonConfig,
+    log_file::{with_context, LogContext, LogScope},
+};
 use tab_websocket::{
     bus::{WebsocketCarrier, WebsocketListenerBus},
     message::listener::WebsocketConnectionMessage,
@@ -35,7 +47,12 @@ pub struct ListenerService {
     _new_session: Lifeline,
     _tabs: TabManagerService,
     _tab_assignments: TabAssignmentService,
+    _pty_pool: PtyPoolService,
     _retask: RetaskService,
+    _tab_record: TabRecordService,
+    _tab_activity: TabActivityService,
+    _activity_summary: ActivitySummaryService,
+    _status: StatusService,
     _connection_carrier: ConnectionMessageCarrier,
     _daemon_carrier: ListenerDaemonCarrier,
 }
@@ -56,8 +73,9 @@ impl Service for ListenerService {
         let _listener = WebsocketListenerService::spawn(&websocket_bus)?;
 
         let listener_bus = ListenerBus::default();
-        listener_bus.capacity::<TabSend>(128)?;
-        listener_bus.capacity::<TabRecv>(128)?;
+        listener_bus.capacity::<TabSend>(TAB_CHANNEL_CAPACITY)?;
+        listener_bus.capacity::<TabRecv>(TAB_CHANNEL_CAPACITY)?;
+        listener_bus.store_resource(bus.resource::<DaemonConfig>()?);
 
         let _daemon_carrier = listener_bus.carry_from(bus)?;
         let _connection_carrier = listener_bus.carry_from(&websocket_bus)?;
@@ -65,6 +83,11 @@ impl Service for ListenerService {
         let _tab_assignments = TabAssignmentService::spawn(&listener_bus)?;
         let _tabs = TabManagerService::spawn(&listener_bus)?;
         let _retask = RetaskService::spawn(&listener_bus)?;
+        let _tab_record = TabRecordService::spawn(&listener_bus)?;
+        let _tab_activity = TabActivityService::spawn(&listener_bus)?;
+        let _activity_summary = ActivitySummaryService::spawn(&listener_bus)?;
+        let _status = StatusService::spawn(&listener_bus)?;
+        let _pty_pool = PtyPoolService::spawn(&listener_bus)?;
 
         let _new_session = Self::try_task("new_session", Self::new_session(listener_bus));
 
@@ -74,8 +97,13 @@ impl Service for ListenerService {
             _connection_carrier,
             _daemon_carrier,
             _retask,
+            _tab_record,
+            _tab_activity,
+            _activity_summary,
+            _status,
             _tabs,
             _tab_assignments,
+            _pty_pool,
         })
     }
 }
@@ -93,16 +121,26 @@ impl ListenerService {
 
         while let Some(msg) = rx_conn.recv().await {
             let name = format!("connection_{}", index);
-            debug!(
-                "opening connection {}, from HTTP {} {}",
-                name, msg.request.method, msg.request.uri
-            );
+
+            // the tasks of the connection log within its scope, which gains the tab when a pty is assigned
+            let scope = LogScope::new(LogContext {
+                connection: Some(name.clone()),
+                tab: None,
+            });
+
+            with_context(scope.context(), || {
+                debug!(
+                    "opening connection {}, from HTTP {} {}",
+                    name, msg.request.method, msg.request.uri
+                )
+            });
 
             let lifeline = match msg.request.uri.to_string().as_str() {
                 "/cli" => {
                     let cli_bus = CliBus::default();
-                    cli_bus.capacity::<Request>(128)?;
-                    cli_bus.capacity::<Response>(256)?;
+                    cli_bus.capacity::<Request>(REQUEST_CHANNEL_CAPACITY)?;
+                    cli_bus.capacity::<Response>(RESPONSE_CHANNEL_CAPACITY)?;
+                    cli_bus.store_resource(scope.clone());
 
                     let _listener_carrier = cli_bus.carry_from(&bus)?;
                     let _websocket_carrier = cli_bus.carry_into(&msg.bus)?;
@@ -112,15 +150,17 @@ impl ListenerService {
                         _websocket_carrier,
                     };
 
+                    let tx_status = bus.tx::<StatusRecv>()?;
                     Self::try_task(
-                        (name + "/cli").as_str(),
-                        Self::run_cli(cli_bus, _connection),
+                        (name.clone() + "/cli").as_str(),
+                        scope.scope(Self::run_cli(name, cli_bus, _connection, tx_status)),
                     )
                 }
                 "/pty" => {
                     let pty_bus = PtyBus::default();
-                    pty_bus.capacity::<PtySend>(128)?;
-                    pty_bus.capacity::<PtyRecv>(128)?;
+                    pty_bus.capacity::<PtySend>(PTY_CHANNEL_CAPACITY)?;
+                    pty_bus.capacity::<PtyRecv>(PTY_CHANNEL_CAPACITY)?;
+                    pty_bus.store_resource(scope.clone());
 
                     let _listener_carrier = pty_bus.carry_from(&bus)?;
                     let _websocket_carrier = pty_bus.carry_into(&msg.bus)?;
@@ -129,9 +169,10 @@ impl ListenerService {
                         _listener_carrier,
                         _websocket_carrier,
                     };
+                    let tx_status = bus.tx::<StatusRecv>()?;
                     Self::try_task(
-                        (name + "/pty").as_str(),
-                        Self::run_pty(pty_bus, _pty_lifeline),
+                        (name.clone() + "/pty").as_str(),
+                        scope.scope(Self::run_pty(name, pty_bus, _pty_lifeline, tx_status)),
                     )
                 }
                 _ => {
@@ -147,7 +188,12 @@ impl ListenerService {
         Ok(())
     }
 
-    async fn run_cli(bus: CliBus, _connection: CliLifeline) -> anyhow::Result<()> {
+    async fn run_cli(
+        name: String,
+        bus: CliBus,
+        _connection: CliLifeline,
+        mut tx_status: impl Sender<StatusRecv>,
+    ) -> anyhow::Result<()> {
         let mut shutdown = bus.rx::<CliShutdown>()?;
 
         // keep service alive until we get a shutdown signal
@@ -155,25 +201,46 @@ impl ListenerService {
         let _subscription = CliSubscriptionService::spawn(&bus)?;
         drop(bus);
 
-        shutdown
-            .recv()
-            .await
-            .context("rx ConnectionShutdown closed")?;
+        tx_status.send(StatusRecv::CliConnected).await.ok();
+        let shutdown = shutdown.recv().await;
+        tx_status.send(StatusRecv::CliDisconnected).await.ok();
+
+        debug!("closing connection {}", name);
+
+        shutdown.context("rx ConnectionShutdown closed")?;
 
         Ok(())
     }
 
-    async fn run_pty(bus: PtyBus, _connection: PtyLifeline) -> anyhow::Result<()> {
+    async fn run_pty(
+        name: String,
+        bus: PtyBus,
+        _connection: PtyLifeline,
+        mut tx_status: impl Sender<StatusRecv>,
+    ) -> anyhow::Result<()> {
         let mut shutdown = bus.rx::<PtyShutdown>()?;
+        let rx_state = bus.rx::<PtyState>()?.into_inner();
 
         // keep service alive until we get a shutdown signal
         let _service = PtyService::spawn(&bus)?;
         drop(bus);
 
-        shutdown
-            .recv()
+        tx_status.send(StatusRecv::PtyConnected).await.ok();
+        let shutdown = shutdown.recv().await;
+
+        let assigned = match *rx_state.borrow() {
+            PtyState::Assigned(id) => Some(id),
+            PtyState::None => None,
+        };
+
+        debug!("closing connection {}", name);
+
+        tx_status
+            .send(StatusRecv::PtyDisconnected(assigned))
             .await
-            .context("rx ConnectionShutdown closed")?;
+            .ok();
+
+        shutdown.context("rx ConnectionShutdown closed")?;
 
         Ok(())
     }
@@ -0,0 +1,224 @@
+use crate::prelude::*;
+
+use std::time::{Duration, Instant};
+use tab_api::{client::DaemonStatus, launch::launch_pty};
+use tokio::{stream::StreamExt, time};
+
+static DEFAULT_POOL_SIZE: usize = 2;
+static LAUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The number of idle pty processes to keep ready, configured with `TAB_PTY_POOL` (default 2).
+/// If zero, pty processes are launched on demand, when tabs are created.
+pub fn pty_pool_size() -> usize {
+    std::env::var("TAB_PTY_POOL")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_POOL_SIZE)
+}
+
+/// Launches tab-pty processes, which connect to the daemon and accept tab assignments.
+///
+/// A pool of idle, connected ptys is kept ready, so new tabs are assigned without waiting for a process to launch.
+/// The pool is refilled in the background as ptys are assigned, and additional ptys are launched for tabs waiting for assignment.
+pub struct PtyPoolService {
+    _pool: Lifeline,
+}
+
+enum Event {
+    Status(DaemonStatus),
+    Tick,
+}
+
+impl Service for PtyPoolService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let rx_status = bus.rx::<DaemonStatus>()?.into_inner();
+        let size = pty_pool_size();
+
+        let _pool = Self::try_task("pool", async move {
+            let mut events = {
+                let status = rx_status.map(Event::Status);
+                let ticks = time::interval(Duration::from_secs(1)).map(|_| Event::Tick);
+                status.merge(ticks)
+            };
+
+            let mut pool = PtyPool::new(size);
+            let mut status = DaemonStatus::default();
+
+            while let Some(event) = events.next().await {
+                if let Event::Status(update) = event {
+                    status = update;
+                }
+
+                let launches = pool.update(&status, Instant::now());
+                for _ in 0..launches {
+                    debug!("launching pty process");
+                    if let Err(e) = launch_pty() {
+                        error!("failed to launch pty process: {}", e);
+                        pool.failed();
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _pool })
+    }
+}
+
+/// Counts the idle & launching pty processes, and decides how many ptys to launch
+#[derive(Debug)]
+struct PtyPool {
+    size: usize,
+    connections: usize,
+    launching: usize,
+    launched: Option<Instant>,
+}
+
+impl PtyPool {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            connections: 0,
+            launching: 0,
+            launched: None,
+        }
+    }
+
+    /// Updates the pool with the daemon status, and returns the number of ptys which should be launched.
+    /// The pool targets `size` idle ptys, plus one pty for each tab which is waiting for assignment.
+    pub fn update(&mut self, status: &DaemonStatus, now: Instant) -> usize {
+        // launched ptys are no longer launching once they connect
+        let connected = status.pty_connections.saturating_sub(self.connections);
+        self.connections = status.pty_connections;
+        self.launching = self.launching.saturating_sub(connected);
+
+        // ptys which fail to connect within the timeout are launched again
+        let expired = self
+            .launched
+            .map(|launched| now.duration_since(launched) > LAUNCH_TIMEOUT)
+            .unwrap_or(false);
+        if expired {
+            self.launching = 0;
+            self.launched = None;
+        }
+
+        let waiting = status.tabs.iter().filter(|tab| !tab.assigned).count();
+        let target = self.size + waiting;
+        let available = status.idle_ptys + self.launching;
+        let launches = target.saturating_sub(available);
+
+        if launches > 0 {
+            self.launching += launches;
+            self.launched = Some(now);
+        }
+
+        launches
+    }
+
+    /// Records a pty process which failed to launch
+    pub fn failed(&mut self) {
+        self.launching = self.launching.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PtyPool, LAUNCH_TIMEOUT};
+    use std::time::{Duration, Instant};
+    use tab_api::{
+        client::{DaemonStatus, TabStatus},
+        tab::TabId,
+    };
+
+    fn status(pty_connections: usize, idle_ptys: usize, tabs: &[bool]) -> DaemonStatus {
+        let tabs = tabs
+            .iter()
+            .enumerate()
+            .map(|(id, assigned)| TabStatus {
+                id: TabId(id as u16),
+                name: format!("tab-{}/", id),
+                assigned: *assigned,
+                pty: None,
+            })
+            .collect();
+
+        DaemonStatus {
+            pty_connections,
+            idle_ptys,
+            tabs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn prewarm() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(2);
+
+        assert_eq!(2, pool.update(&status(0, 0, &[]), now));
+        assert_eq!(0, pool.update(&status(1, 1, &[]), now));
+        assert_eq!(0, pool.update(&status(2, 2, &[]), now));
+    }
+
+    #[test]
+    fn refill() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(2);
+        pool.update(&status(0, 0, &[]), now);
+        pool.update(&status(2, 2, &[]), now);
+
+        // a tab is created, and assigned to an idle pty
+        assert_eq!(1, pool.update(&status(2, 2, &[false]), now));
+        assert_eq!(0, pool.update(&status(2, 1, &[true]), now));
+        assert_eq!(0, pool.update(&status(3, 2, &[true]), now));
+    }
+
+    #[test]
+    fn waiting_tabs() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(1);
+        pool.update(&status(0, 0, &[]), now);
+        pool.update(&status(1, 1, &[]), now);
+
+        // more tabs are created than there are idle ptys
+        assert_eq!(3, pool.update(&status(1, 1, &[false, false, false]), now));
+        assert_eq!(0, pool.update(&status(1, 0, &[true, false, false]), now));
+    }
+
+    #[test]
+    fn on_demand() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(0);
+
+        assert_eq!(0, pool.update(&status(0, 0, &[]), now));
+        assert_eq!(1, pool.update(&status(0, 0, &[false]), now));
+        assert_eq!(0, pool.update(&status(0, 0, &[false]), now));
+        assert_eq!(0, pool.update(&status(1, 0, &[true]), now));
+    }
+
+    #[test]
+    fn launch_timeout() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(1);
+
+        assert_eq!(1, pool.update(&status(0, 0, &[]), now));
+        assert_eq!(0, pool.update(&status(0, 0, &[]), now + LAUNCH_TIMEOUT));
+
+        let later = now + LAUNCH_TIMEOUT + Duration::from_millis(1);
+        assert_eq!(1, pool.update(&status(0, 0, &[]), later));
+    }
+
+    #[test]
+    fn launch_failure() {
+        let now = Instant::now();
+        let mut pool = PtyPool::new(1);
+
+        assert_eq!(1, pool.update(&status(0, 0, &[]), now));
+        pool.failed();
+        assert_eq!(1, pool.update(&status(0, 0, &[]), now));
+    }
+}
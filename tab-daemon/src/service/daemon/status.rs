@@ -0,0 +1,248 @@
+use super::pty_pool::pty_pool_size;
+use crate::{message::status::StatusRecv, prelude::*, state::tab::TabsState};
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tab_api::{
+    client::{ChannelStatus, DaemonStatus, TabStatus},
+    config::DaemonConfig,
+    tab::TabId,
+};
+use tokio::stream::StreamExt;
+
+/// Tracks CLI & PTY connections, and the pty assignments of running tabs.
+/// Publishes the `DaemonStatus`, which is requested by `tab --status`.
+pub struct StatusService {
+    _status: Lifeline,
+}
+
+enum Event {
+    Status(StatusRecv),
+    Tabs(TabsState),
+}
+
+impl Service for StatusService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let config = bus.resource::<DaemonConfig>()?;
+        let rx_status = bus.rx::<StatusRecv>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+        let mut tx = bus.tx::<DaemonStatus>()?;
+        let pty_pool = pty_pool_size();
+
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let _status = Self::try_task("status", async move {
+            let mut events = {
+                let status = rx_status.map(Event::Status);
+                let tabs = rx_tabs_state.map(Event::Tabs);
+                status.merge(tabs)
+            };
+
+            let mut tracker = StatusTracker::default();
+            let mut tabs = TabsState::default();
+
+            while let Some(event) = events.next().await {
+                match event {
+                    Event::Status(msg) => tracker.recv(msg),
+                    Event::Tabs(state) => tabs = state,
+                }
+
+                let mut status = tracker.status(&config, started, &tabs);
+                status.pty_pool = pty_pool;
+                tx.send(status).await?;
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _status })
+    }
+}
+
+/// Counts the open connections, and the process ids of ptys which have been assigned tabs
+#[derive(Debug, Default)]
+struct StatusTracker {
+    cli_connections: usize,
+    pty_connections: usize,
+    ptys: HashMap<TabId, Option<u32>>,
+}
+
+impl StatusTracker {
+    pub fn recv(&mut self, msg: StatusRecv) {
+        match msg {
+            StatusRecv::CliConnected => self.cli_connections += 1,
+            StatusRecv::CliDisconnected => {
+                self.cli_connections = self.cli_connections.saturating_sub(1)
+            }
+            StatusRecv::PtyConnected => self.pty_connections += 1,
+            StatusRecv::PtyDisconnected(id) => {
+                self.pty_connections = self.pty_connections.saturating_sub(1);
+                if let Some(id) = id {
+                    self.ptys.remove(&id);
+                }
+            }
+            StatusRecv::PtyAssigned(id) => {
+                self.ptys.entry(id).or_insert(None);
+            }
+            StatusRecv::PtyStarted(id, pid) => {
+                self.ptys.insert(id, pid);
+            }
+        }
+    }
+
+    pub fn status(&self, config: &DaemonConfig, started: u64, tabs: &TabsState) -> DaemonStatus {
+        let mut tab_status: Vec<TabStatus> = tabs
+            .tabs
+            .values()
+            .map(|tab| TabStatus {
+                id: tab.id,
+                name: tab.name.clone(),
+                assigned: self.ptys.contains_key(&tab.id),
+                pty: self.ptys.get(&tab.id).copied().flatten(),
+            })
+            .collect();
+        tab_status.sort_by_key(|tab| tab.id.0);
+
+        DaemonStatus {
+            pid: config.pid,
+            version: config.version.clone(),
+            started,
+            port: config.port,
+            cli_connections: self.cli_connections,
+            pty_connections: self.pty_connections,
+            idle_ptys: self.pty_connections.saturating_sub(self.ptys.len()),
+            pty_pool: 0,
+            tabs: tab_status,
+            channels: channels(),
+        }
+    }
+}
+
+/// The bounded channels which are configured with explicit capacities
+fn channels() -> Vec<ChannelStatus> {
+    let channel = |bus: &str, message: &str, capacity: usize| ChannelStatus {
+        bus: bus.to_string(),
+        message: message.to_string(),
+        capacity,
+    };
+
+    vec![
+        channel("ListenerBus", "TabSend", TAB_CHANNEL_CAPACITY),
+        channel("ListenerBus", "TabRecv", TAB_CHANNEL_CAPACITY),
+        channel("CliBus", "Request", REQUEST_CHANNEL_CAPACITY),
+        channel("CliBus", "Response", RESPONSE_CHANNEL_CAPACITY),
+        channel("PtyBus", "PtySend", PTY_CHANNEL_CAPACITY),
+        channel("PtyBus", "PtyRecv", PTY_CHANNEL_CAPACITY),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusTracker;
+    use crate::{message::status::StatusRecv, state::tab::TabsState};
+    use tab_api::{
+        client::TabStatus,
+        config::DaemonConfig,
+        tab::{TabId, TabMetadata},
+    };
+
+    fn config() -> DaemonConfig {
+        DaemonConfig {
+            pid: 100,
+            port: 1000,
+            auth_token: "token".to_string(),
+            version: "0.1.0".to_string(),
+        }
+    }
+
+    fn tabs(ids: &[u16]) -> TabsState {
+        let mut tabs = TabsState::default();
+        for id in ids {
+            let tab = TabMetadata {
+                id: TabId(*id),
+                name: format!("tab-{}/", id),
+                dimensions: (1, 1),
+                shell: "bash".to_string(),
+                dir: "/".to_string(),
+                log: None,
+                hooks: Default::default(),
+                tags: Vec::new(),
+                idle: Default::default(),
+                process: None,
+                stats: None,
+            };
+
+            tabs.tabs.insert(TabId(*id), tab);
+        }
+
+        tabs
+    }
+
+    #[test]
+    fn connections() {
+        let mut tracker = StatusTracker::default();
+        tracker.recv(StatusRecv::CliConnected);
+        tracker.recv(StatusRecv::CliConnected);
+        tracker.recv(StatusRecv::PtyConnected);
+        tracker.recv(StatusRecv::CliDisconnected);
+
+        let status = tracker.status(&config(), 5, &tabs(&[]));
+        assert_eq!(100, status.pid);
+        assert_eq!(1000, status.port);
+        assert_eq!("0.1.0", status.version);
+        assert_eq!(5, status.started);
+        assert_eq!(1, status.cli_connections);
+        assert_eq!(1, status.pty_connections);
+        assert_eq!(1, status.idle_ptys);
+        assert!(!status.channels.is_empty());
+    }
+
+    #[test]
+    fn assignments() {
+        let mut tracker = StatusTracker::default();
+        tracker.recv(StatusRecv::PtyConnected);
+        tracker.recv(StatusRecv::PtyAssigned(TabId(1)));
+        tracker.recv(StatusRecv::PtyStarted(TabId(1), Some(200)));
+        tracker.recv(StatusRecv::PtyConnected);
+        tracker.recv(StatusRecv::PtyAssigned(TabId(0)));
+        tracker.recv(StatusRecv::PtyConnected);
+
+        let status = tracker.status(&config(), 0, &tabs(&[2, 1, 0]));
+        let expected = vec![
+            TabStatus {
+                id: TabId(0),
+                name: "tab-0/".to_string(),
+                assigned: true,
+                pty: None,
+            },
+            TabStatus {
+                id: TabId(1),
+                name: "tab-1/".to_string(),
+                assigned: true,
+                pty: Some(200),
+            },
+            TabStatus {
+                id: TabId(2),
+                name: "tab-2/".to_string(),
+                assigned: false,
+                pty: None,
+            },
+        ];
+        assert_eq!(expected, status.tabs);
+        assert_eq!(1, status.idle_ptys);
+
+        tracker.recv(StatusRecv::PtyDisconnected(Some(TabId(1))));
+        let status = tracker.status(&config(), 0, &tabs(&[1]));
+        assert_eq!(2, status.pty_connections);
+        assert_eq!(1, status.idle_ptys);
+        assert!(!status.tabs[0].assigned);
+    }
+}
@@ -0,0 +1,660 @@
+use crate::{
+    message::{
+        tab::{TabRecv, TabSend},
+        tab_manager::TabManagerRecv,
+    },
+    prelude::*,
+    state::{
+        activity::{AttachmentWatch, TabsActivityState},
+        tab::TabsState,
+    },
+};
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tab_api::{
+    ansi::AnsiStripper,
+    config::activity_path,
+    tab::{parse_duration, CloseOptions, IdleAction, TabActivity, TabId, TabMetadata},
+};
+use tokio::{process::Command, stream::StreamExt, sync::watch, task, time};
+
+static DEFAULT_SILENCE_SECS: u64 = 10;
+
+/// Tracks output activity on running tabs, and publishes `TabsActivityState`.
+///
+/// Tabs are marked unread when they produce output while no client is attached,
+/// and marked silent if that output stops for `TAB_SILENCE_SECS` (default 10) seconds.
+/// If `TAB_NOTIFY_COMMAND` is set, it is executed when a background tab rings the bell, or goes silent.
+///
+/// Tabs which have an idle timeout (or `TAB_IDLE_TIMEOUT`) are closed, or marked stale,
+/// once they have had no input, output, or attached clients for the timeout.
+pub struct TabActivityService {
+    _activity: Lifeline,
+}
+
+enum Event {
+    Attach(AttachmentWatch),
+    Tab(TabSend),
+    Input(TabId),
+    Tick,
+}
+
+impl Service for TabActivityService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let rx_attach = bus.rx::<AttachmentWatch>()?;
+        let rx_tab = bus.rx::<TabSend>()?.into_inner().filter_map(Result::ok);
+        let rx_input = bus
+            .rx::<TabRecv>()?
+            .into_inner()
+            .filter_map(|msg| match msg {
+                Ok(TabRecv::Input(input)) => Some(input.id),
+                _ => None,
+            });
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+        let mut tx_activity = bus.tx::<TabsActivityState>()?;
+        let mut tx_manager = bus.tx::<TabManagerRecv>()?;
+
+        let silence = std::env::var("TAB_SILENCE_SECS")
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .unwrap_or(DEFAULT_SILENCE_SECS);
+        let silence = Duration::from_secs(silence);
+        let notify_command = std::env::var("TAB_NOTIFY_COMMAND").ok();
+
+        let idle_timeout = std::env::var("TAB_IDLE_TIMEOUT")
+            .ok()
+            .and_then(|timeout| parse_duration(timeout.as_str()));
+        let idle_action = std::env::var("TAB_IDLE_ACTION")
+            .ok()
+            .and_then(|action| IdleAction::parse(action.as_str()))
+            .unwrap_or_default();
+
+        let _activity = Self::try_task("activity", async move {
+            let mut events = {
+                let attachments = rx_attach.map(Event::Attach);
+                let tabs = rx_tab.map(Event::Tab);
+                let input = rx_input.map(Event::Input);
+                let ticks = time::interval(Duration::from_secs(1)).map(|_| Event::Tick);
+                attachments.merge(tabs).merge(input).merge(ticks)
+            };
+
+            let mut tracker = ActivityTracker::new(silence).idle(idle_timeout, idle_action);
+            let mut published = TabsActivityState::default();
+
+            while let Some(event) = events.next().await {
+                let notifications = match event {
+                    Event::Attach(attachment) => {
+                        tracker.attach(attachment);
+                        Vec::new()
+                    }
+                    Event::Tab(TabSend::Started(metadata)) => {
+                        tracker.start(metadata.id);
+                        Vec::new()
+                    }
+                    Event::Tab(TabSend::Stopped(id)) => {
+                        tracker.stop(id);
+                        Vec::new()
+                    }
+                    Event::Tab(TabSend::Output(output)) => {
+                        tracker.output(output.id, output.stdout.data.as_slice())
+                    }
+                    Event::Tab(_) => Vec::new(),
+                    Event::Input(id) => {
+                        tracker.input(id);
+                        Vec::new()
+                    }
+                    Event::Tick => {
+                        let now = Instant::now();
+                        let idle = tracker.reap(now, &rx_tabs_state.borrow().tabs);
+                        for id in idle {
+                            Self::close_idle(id, &rx_tabs_state);
+                            let close = TabManagerRecv::CloseTab(id, CloseOptions::default());
+                            tx_manager.send(close).await?;
+                        }
+
+                        tracker.tick(now)
+                    }
+                };
+
+                for (id, notification) in notifications {
+                    Self::notify(id, notification, &rx_tabs_state, notify_command.as_ref());
+                }
+
+                let state = tracker.state();
+                if state != published {
+                    tx_activity.send(state.clone()).await?;
+                    published = state;
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _activity })
+    }
+}
+
+impl TabActivityService {
+    fn close_idle(id: TabId, rx_tabs_state: &watch::Receiver<TabsState>) {
+        if let Some(tab) = rx_tabs_state.borrow().tabs.get(&id) {
+            info!("closing idle tab {}", tab.name);
+        }
+    }
+
+    fn notify(
+        id: TabId,
+        notification: Notification,
+        rx_tabs_state: &watch::Receiver<TabsState>,
+        command: Option<&String>,
+    ) {
+        let name = match rx_tabs_state.borrow().tabs.get(&id) {
+            Some(tab) => tab.name.clone(),
+            None => return,
+        };
+
+        let message = notification.message(name.as_str());
+        info!("{}", message);
+
+        let command = match command {
+            Some(command) => command,
+            None => return,
+        };
+
+        let mut child = Command::new("sh");
+        child
+            .args(["-c", command.as_str()])
+            .env("TAB", name.as_str())
+            .env("TAB_ID", id.0.to_string())
+            .env("TAB_EVENT", notification.event())
+            .env("TAB_NOTIFICATION", message)
+            .kill_on_drop(false);
+
+        if let Err(err) = child.spawn() {
+            error!("failed to execute TAB_NOTIFY_COMMAND: {}", err);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Notification {
+    Bell,
+    Silence,
+}
+
+impl Notification {
+    pub fn event(&self) -> &'static str {
+        match self {
+            Notification::Bell => "bell",
+            Notification::Silence => "silence",
+        }
+    }
+
+    pub fn message(&self, name: &str) -> String {
+        match self {
+            Notification::Bell => format!("tab {} rang the bell", name),
+            Notification::Silence => format!("tab {} has new output, and is now silent", name),
+        }
+    }
+}
+
+/// The activity of a single tab
+#[derive(Debug, Default)]
+struct TabTracker {
+    activity: TabActivity,
+    started: Option<Instant>,
+    last_input: Option<Instant>,
+    last_output: Option<Instant>,
+    last_attach: Option<Instant>,
+    attachments: Vec<AttachmentWatch>,
+    parser: AnsiStripper,
+    /// The tab has been closed by the idle policy
+    closing: bool,
+}
+
+impl TabTracker {
+    pub fn is_attached(&mut self) -> bool {
+        self.attachments.retain(AttachmentWatch::is_attached);
+        !self.attachments.is_empty()
+    }
+
+    /// The most recent input, output, or attachment, or the time the tab started
+    pub fn last_active(&self) -> Option<Instant> {
+        let times = [
+            self.started,
+            self.last_input,
+            self.last_output,
+            self.last_attach,
+        ];
+
+        times.iter().copied().flatten().max()
+    }
+}
+
+/// Maintains the activity of running tabs, and generates notifications when background tabs ring the bell, or go silent.
+struct ActivityTracker {
+    silence: Duration,
+    idle_timeout: Option<u64>,
+    idle_action: IdleAction,
+    tabs: HashMap<TabId, TabTracker>,
+}
+
+impl ActivityTracker {
+    pub fn new(silence: Duration) -> Self {
+        Self {
+            silence,
+            idle_timeout: None,
+            idle_action: IdleAction::default(),
+            tabs: HashMap::new(),
+        }
+    }
+
+    /// Sets the default idle policy, for tabs which don't configure one
+    pub fn idle(mut self, timeout: Option<u64>, action: IdleAction) -> Self {
+        self.idle_timeout = timeout;
+        self.idle_action = action;
+        self
+    }
+
+    pub fn start(&mut self, id: TabId) {
+        let tab = self.tabs.entry(id).or_default();
+        tab.started = Some(Instant::now());
+    }
+
+    pub fn stop(&mut self, id: TabId) {
+        self.tabs.remove(&id);
+    }
+
+    /// A client has attached to the tab, and has seen the output
+    pub fn attach(&mut self, attachment: AttachmentWatch) {
+        let tab = self.tabs.entry(attachment.id).or_default();
+        tab.attachments.push(attachment);
+        tab.last_attach = Some(Instant::now());
+
+        tab.activity.unread = false;
+        tab.activity.bell = false;
+        tab.activity.silent = false;
+        tab.activity.stale = false;
+    }
+
+    /// A client has sent input to the tab
+    pub fn input(&mut self, id: TabId) {
+        let tab = self.tabs.entry(id).or_default();
+        tab.last_input = Some(Instant::now());
+        tab.activity.stale = false;
+    }
+
+    pub fn output(&mut self, id: TabId, data: &[u8]) -> Vec<(TabId, Notification)> {
+        let tab = self.tabs.entry(id).or_default();
+        tab.last_output = Some(Instant::now());
+        tab.activity.last_output = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|time| time.as_secs());
+        tab.activity.silent = false;
+        tab.activity.stale = false;
+
+        let bell = tab.parser.contains_bell(data);
+        if tab.is_attached() {
+            return Vec::new();
+        }
+
+        tab.activity.unread = true;
+
+        if bell && !tab.activity.bell {
+            tab.activity.bell = true;
+            return vec![(id, Notification::Bell)];
+        }
+
+        Vec::new()
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Vec<(TabId, Notification)> {
+        let mut notifications = Vec::new();
+        let silence = self.silence;
+
+        for (id, tab) in self.tabs.iter_mut() {
+            if !tab.activity.unread || tab.activity.silent {
+                continue;
+            }
+
+            let silent = tab
+                .last_output
+                .map(|last| now.duration_since(last) >= silence)
+                .unwrap_or(false);
+
+            if silent && !tab.is_attached() {
+                tab.activity.silent = true;
+                notifications.push((*id, Notification::Silence));
+            }
+        }
+
+        notifications
+    }
+
+    /// Applies the idle policy to the running tabs.  Marks idle tabs as stale, and returns the idle tabs which should be closed.
+    /// Tabs are skipped while a client is attached, or the foreground process is not the shell.
+    pub fn reap(&mut self, now: Instant, tabs: &HashMap<TabId, TabMetadata>) -> Vec<TabId> {
+        let mut close = Vec::new();
+
+        for (id, tab) in self.tabs.iter_mut() {
+            let metadata = match tabs.get(id) {
+                Some(metadata) => metadata,
+                None => continue,
+            };
+
+            let timeout = metadata.idle.timeout.or(self.idle_timeout).unwrap_or(0);
+            let action = metadata.idle.action.unwrap_or(self.idle_action);
+
+            if tab.is_attached() {
+                tab.last_attach = Some(now);
+            }
+
+            let idle = match tab.last_active() {
+                Some(last_active) => now.saturating_duration_since(last_active),
+                None => {
+                    tab.started = Some(now);
+                    Duration::from_secs(0)
+                }
+            };
+
+            let stale = timeout > 0
+                && idle >= Duration::from_secs(timeout)
+                && metadata.foreground_process().is_none();
+
+            match action {
+                IdleAction::Stale => tab.activity.stale = stale,
+                IdleAction::Close if stale && !tab.closing => {
+                    tab.closing = true;
+                    close.push(*id);
+                }
+                IdleAction::Close => {}
+            }
+        }
+
+        close.sort_by_key(|id| id.0);
+        close
+    }
+
+    pub fn state(&self) -> TabsActivityState {
+        let tabs = self
+            .tabs
+            .iter()
+            .map(|(id, tab)| (*id, tab.activity.clone()))
+            .collect();
+
+        TabsActivityState { tabs }
+    }
+}
+
+/// Writes a summary of tabs with unread output to the activity file, for statusline integrations.
+/// Each line contains the tab name, followed by the `unread`, `bell`, and `silent` flags.
+pub struct ActivitySummaryService {
+    _write: Lifeline,
+}
+
+impl Service for ActivitySummaryService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let mut rx_activity = bus.rx::<TabsActivityState>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        let _write = Self::try_task("write", async move {
+            let path = activity_path()?;
+            let mut written = None;
+
+            while let Some(activity) = rx_activity.recv().await {
+                let summary = Self::summary(&activity, &rx_tabs_state.borrow());
+
+                if written.as_ref() != Some(&summary) {
+                    let write = {
+                        let path = path.clone();
+                        let summary = summary.clone();
+                        task::spawn_blocking(move || std::fs::write(path, summary)).await?
+                    };
+
+                    if let Err(err) = write {
+                        error!(
+                            "failed to write activity to {}: {}",
+                            path.to_string_lossy(),
+                            err
+                        );
+                    }
+
+                    written = Some(summary);
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _write })
+    }
+}
+
+impl ActivitySummaryService {
+    fn summary(activity: &TabsActivityState, tabs: &TabsState) -> String {
+        let mut lines: Vec<String> = activity
+            .tabs
+            .iter()
+            .filter(|(_id, activity)| activity.unread)
+            .filter_map(|(id, activity)| {
+                let tab = tabs.tabs.get(id)?;
+
+                let mut line = tab.name.clone();
+                line += " unread";
+
+                if activity.bell {
+                    line += " bell";
+                }
+
+                if activity.silent {
+                    line += " silent";
+                }
+
+                Some(line)
+            })
+            .collect();
+
+        lines.sort();
+
+        lines
+            .into_iter()
+            .map(|line| line + "\n")
+            .collect::<Vec<String>>()
+            .concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ActivityTracker, Notification};
+    use crate::state::activity::attachment;
+    use std::{
+        collections::HashMap,
+        time::{Duration, Instant},
+    };
+    use tab_api::tab::{IdleAction, TabId, TabIdle, TabMetadata, TabProcess};
+
+    fn tabs(idle: TabIdle, process: &str) -> HashMap<TabId, TabMetadata> {
+        let tab = TabMetadata {
+            id: TabId(0),
+            name: "idle/".to_string(),
+            dimensions: (1, 1),
+            shell: "/bin/bash".to_string(),
+            dir: "/".to_string(),
+            log: None,
+            hooks: Box::default(),
+            tags: Vec::new(),
+            idle: Box::new(idle),
+            process: Some(TabProcess {
+                name: process.to_string(),
+                cwd: "/".to_string(),
+            }),
+            stats: None,
+        };
+
+        let mut tabs = HashMap::new();
+        tabs.insert(TabId(0), tab);
+        tabs
+    }
+
+    #[test]
+    fn unread() {
+        let mut tracker = ActivityTracker::new(Duration::from_secs(10));
+        tracker.start(TabId(0));
+
+        assert!(tracker.output(TabId(0), b"hello").is_empty());
+        let state = tracker.state();
+        assert!(state.tabs[&TabId(0)].unread);
+        assert!(state.tabs[&TabId(0)].last_output.is_some());
+
+        let (_attachment, watch) = attachment(TabId(0));
+        tracker.attach(watch);
+        assert!(!tracker.state().tabs[&TabId(0)].unread);
+    }
+
+    #[test]
+    fn attached_output_is_read() {
+        let mut tracker = ActivityTracker::new(Duration::from_secs(10));
+
+        let (attachment, watch) = attachment(TabId(0));
+        tracker.attach(watch);
+
+        tracker.output(TabId(0), b"hello\x07");
+        assert!(!tracker.state().tabs[&TabId(0)].unread);
+        assert!(!tracker.state().tabs[&TabId(0)].bell);
+
+        drop(attachment);
+        tracker.output(TabId(0), b"hello");
+        assert!(tracker.state().tabs[&TabId(0)].unread);
+    }
+
+    #[test]
+    fn bell() {
+        let mut tracker = ActivityTracker::new(Duration::from_secs(10));
+
+        let notifications = tracker.output(TabId(0), b"\x1b]0;title\x07$ ");
+        assert!(notifications.is_empty());
+
+        let notifications = tracker.output(TabId(0), b"done\x07");
+        assert_eq!(vec![(TabId(0), Notification::Bell)], notifications);
+        assert!(tracker.state().tabs[&TabId(0)].bell);
+
+        let notifications = tracker.output(TabId(0), b"\x07");
+        assert!(notifications.is_empty());
+    }
+
+    #[test]
+    fn silence() {
+        let mut tracker = ActivityTracker::new(Duration::from_secs(10));
+        tracker.output(TabId(0), b"building...");
+
+        let now = Instant::now();
+        assert!(tracker.tick(now).is_empty());
+
+        let notifications = tracker.tick(now + Duration::from_secs(11));
+        assert_eq!(vec![(TabId(0), Notification::Silence)], notifications);
+        assert!(tracker.state().tabs[&TabId(0)].silent);
+
+        assert!(tracker.tick(now + Duration::from_secs(12)).is_empty());
+
+        tracker.output(TabId(0), b"more output");
+        assert!(!tracker.state().tabs[&TabId(0)].silent);
+    }
+
+    #[test]
+    fn idle_close() {
+        let mut tracker =
+            ActivityTracker::new(Duration::from_secs(10)).idle(Some(60), IdleAction::Close);
+        tracker.start(TabId(0));
+        let tabs = tabs(TabIdle::default(), "bash");
+
+        let now = Instant::now();
+        assert!(tracker
+            .reap(now + Duration::from_secs(30), &tabs)
+            .is_empty());
+
+        let closed = tracker.reap(now + Duration::from_secs(61), &tabs);
+        assert_eq!(vec![TabId(0)], closed);
+
+        // the close is only requested once
+        assert!(tracker
+            .reap(now + Duration::from_secs(62), &tabs)
+            .is_empty());
+    }
+
+    #[test]
+    fn idle_stale() {
+        let mut tracker = ActivityTracker::new(Duration::from_secs(10));
+        tracker.start(TabId(0));
+        let idle = TabIdle {
+            timeout: Some(60),
+            action: Some(IdleAction::Stale),
+        };
+        let tabs = tabs(idle, "bash");
+
+        let now = Instant::now();
+        assert!(tracker
+            .reap(now + Duration::from_secs(61), &tabs)
+            .is_empty());
+        assert!(tracker.state().tabs[&TabId(0)].stale);
+
+        tracker.input(TabId(0));
+        assert!(!tracker.state().tabs[&TabId(0)].stale);
+        tracker.reap(now + Duration::from_secs(30), &tabs);
+        assert!(!tracker.state().tabs[&TabId(0)].stale);
+    }
+
+    #[test]
+    fn idle_skips_attached_and_busy_tabs() {
+        let mut tracker =
+            ActivityTracker::new(Duration::from_secs(10)).idle(Some(60), IdleAction::Close);
+        tracker.start(TabId(0));
+        let now = Instant::now() + Duration::from_secs(61);
+
+        // the foreground process is not the shell
+        let busy = tabs(TabIdle::default(), "cargo");
+        assert!(tracker.reap(now, &busy).is_empty());
+
+        // a client is attached
+        let (attachment, watch) = attachment(TabId(0));
+        tracker.attach(watch);
+        let tabs = tabs(TabIdle::default(), "bash");
+        assert!(tracker.reap(now, &tabs).is_empty());
+
+        // the timeout restarts when the client detaches
+        drop(attachment);
+        assert!(tracker
+            .reap(now + Duration::from_secs(30), &tabs)
+            .is_empty());
+        assert_eq!(
+            vec![TabId(0)],
+            tracker.reap(now + Duration::from_secs(61), &tabs)
+        );
+    }
+
+    #[test]
+    fn idle_timeout_disabled() {
+        let mut tracker =
+            ActivityTracker::new(Duration::from_secs(10)).idle(Some(60), IdleAction::Close);
+        tracker.start(TabId(0));
+        let idle = TabIdle {
+            timeout: Some(0),
+            action: None,
+        };
+        let tabs = tabs(idle, "bash");
+
+        let now = Instant::now();
+        assert!(tracker
+            .reap(now + Duration::from_secs(3600), &tabs)
+            .is_empty());
+    }
+}
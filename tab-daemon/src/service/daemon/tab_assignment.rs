@@ -1,20 +1,19 @@
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 use anyhow::Context;
-use tab_api::launch::launch_pty;
 use tokio::time;
 
 use crate::{
     message::tab::TabRecv, message::tab_assignment::AssignTab,
-    message::tab_assignment::TabAssignmentRetraction, prelude::*, state::assignment::assignment,
+    message::tab_assignment::TabAssignmentRetraction, message::tab_manager::TabManagerRecv,
+    prelude::*, state::assignment::assignment, state::tab::TabsState,
 };
 
-const SPAWN_DELAY: Duration = Duration::from_millis(500);
-
+/// Offers new tabs to connected PTY processes, which atomically accept the assignment.
+/// Offers which are not accepted are retracted and reissued, until a PTY launched by the `PtyPoolService` connects.
 pub struct TabAssignmentService {
     _recv_assign: Lifeline,
     _reassign: Lifeline,
-    _spawn_pty: Lifeline,
 }
 
 impl Service for TabAssignmentService {
@@ -46,7 +45,9 @@ impl Service for TabAssignmentService {
 
         let _reassign = {
             let mut rx = bus.rx::<TabAssignmentRetraction>()?;
+            let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
             let mut tx_assign = bus.tx::<AssignTab>()?;
+            let mut tx_tab_manager = bus.tx::<TabManagerRecv>()?;
             Self::try_task("reassign", async move {
                 'retractions: while let Some(retraction) = rx.recv().await {
                     let retraction = retraction.0;
@@ -62,32 +63,17 @@ impl Service for TabAssignmentService {
                     }
 
                     let metadata = retracted.unwrap();
-                    tx_assign.send(AssignTab(metadata)).await?;
-                }
-
-                Ok(())
-            })
-        };
 
-        let _spawn_pty = {
-            let mut rx = bus.rx::<TabAssignmentRetraction>()?;
-            Self::try_task("spawn_pty", async move {
-                let mut last_spawn: Option<Instant> = None;
-                while let Some(_) = rx.recv().await {
-                    if last_spawn
-                        .map(|inst| Instant::now().duration_since(inst) > SPAWN_DELAY)
-                        .unwrap_or(true)
-                    {
-                        debug!("launching pty process");
-                        launch_pty()?;
-
-                        while let Ok(_) = rx.inner_mut().try_recv() {
-                            debug!("launching pty process");
-                            launch_pty()?;
-                        }
-
-                        last_spawn = Some(Instant::now());
+                    // the tab was closed before a pty accepted it, so the id can be released
+                    if !rx_tabs_state.borrow().tabs.contains_key(&metadata.id) {
+                        debug!("cancelling assignment of closed tab {}", metadata.id);
+                        tx_tab_manager
+                            .send(TabManagerRecv::AssignmentCancelled(metadata.id))
+                            .await?;
+                        continue;
                     }
+
+                    tx_assign.send(AssignTab(metadata)).await?;
                 }
 
                 Ok(())
@@ -97,7 +83,6 @@ impl Service for TabAssignmentService {
         Ok(Self {
             _recv_assign,
             _reassign,
-            _spawn_pty,
         })
     }
 }
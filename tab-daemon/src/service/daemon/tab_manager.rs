@@ -1,29 +1,39 @@
-use crate::{message::tab_assignment::AssignTab, prelude::*};
+use crate::{
+    hooks::run_hook,
+    message::{tab_assignment::AssignTab, tab_record::RecordTab},
+    prelude::*,
+};
 use crate::{
     message::{
-        tab::TabRecv,
+        tab::{TabRecv, TabSend},
         tab_manager::{TabManagerRecv, TabManagerSend},
     },
-    state::tab::TabsState,
+    state::{tab::TabsState, tab_id::TabIds},
 };
 use anyhow::Context;
 
 use std::{
-    collections::HashMap,
-    sync::atomic::{AtomicUsize, Ordering},
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::Duration,
 };
-use tab_api::tab::{TabId, TabMetadata};
+use tab_api::tab::{CloseOptions, TabHook, TabId, TabMetadata, TabSelector};
+use tokio::time;
+
+/// The time to wait after the final signal of a graceful close, before the tab is removed without a response from the pty
+static CLOSE_DEADLINE_SLACK: Duration = Duration::from_secs(5);
 
 /// Manages the currently running tabs.  This is a point-of-contact between the tab-command and tab-pty clients.
 ///
 /// - Serves 'create tab' requests from the tab-command client.
 /// - Spawns tab-pty processes (OS processes), and issues offers of tab assignment to connected pty clients.
-/// - Terminates tabs when requested by the tab-command client.
+/// - Terminates and renames tabs when requested by the tab-command client, including groups of tabs selected by prefix or tag.
+/// - Allocates tab ids, which are held until the pty stops, or the assignment of a closed tab is cancelled.
+/// - Removes gracefully closed tabs if the pty does not stop by the close deadline.
 pub struct TabManagerService {
     _recv: Lifeline,
 }
 
-static TAB_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
 // working on a bug here where all the ptys disconnect, and TabRecv goes dead.
 impl Service for TabManagerService {
     type Bus = ListenerBus;
@@ -35,10 +45,23 @@ impl Service for TabManagerService {
 
             let mut tx = bus.tx::<TabManagerSend>()?;
             let mut tx_tabs = bus.tx::<TabRecv>()?;
+            let mut tx_tab_updates = bus.tx::<TabSend>()?;
             let mut tx_tabs_state = bus.tx::<TabsState>()?;
             let mut tx_assign_tab = bus.tx::<AssignTab>()?;
+            let mut tx_record = bus.tx::<RecordTab>()?;
+            let tx_expired = bus.tx::<TabManagerRecv>()?;
 
             let mut tabs: HashMap<TabId, TabMetadata> = HashMap::new();
+            let mut ids = TabIds::default();
+
+            // tabs which have been accepted by a pty, and can respond to a graceful close
+            let mut assigned: HashSet<TabId> = HashSet::new();
+
+            // timers for gracefully closed tabs, which are cancelled when the pty stops
+            let mut deadlines: HashMap<TabId, Lifeline> = HashMap::new();
+
+            // tabs which were forcibly closed, and run the close hook when the pty stops
+            let mut closing: HashMap<TabId, TabMetadata> = HashMap::new();
 
             Self::try_task("recv", async move {
                 'msg: while let Some(msg) = rx.recv().await {
@@ -51,30 +74,214 @@ impl Service for TabManagerService {
                             }
 
                             debug!("recieved request to create tab {}", &create.name);
-                            let id = TAB_ID_COUNTER.fetch_add(1, Ordering::SeqCst) as u16;
-                            let tab_id = TabId(id);
+                            let tab_id = match ids.allocate() {
+                                Some(id) => id,
+                                None => {
+                                    error!(
+                                        "cannot create tab {}, as all tab ids are in use",
+                                        &create.name
+                                    );
+                                    continue 'msg;
+                                }
+                            };
+
+                            let record = create.record.clone();
                             let tab_metadata = TabMetadata::create(tab_id, create);
 
+                            // the tab is published before it is assigned, so the assignment is not cancelled
+                            tabs.insert(tab_id, tab_metadata.clone());
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+
                             tx_assign_tab.send(AssignTab(tab_metadata.clone())).await?;
+                            run_hook(TabHook::Create, &tab_metadata);
 
-                            tabs.insert(tab_id, tab_metadata);
-                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            if let Some(path) = record {
+                                let path = PathBuf::from(path);
+                                tx_record.send(RecordTab { id: tab_id, path }).await?;
+                            }
                         }
-                        TabManagerRecv::CloseNamedTab(name) => {
-                            let close_tab = tabs.values().find(|t| t.name == name);
-                            if let Some(tab) = close_tab {
-                                Self::close_tab(
-                                    tab.id,
+                        TabManagerRecv::CloseNamedTab(selector, options) => {
+                            // the group is resolved when the message is received, so it is closed as a whole
+                            let selector = TabSelector::parse(selector.as_str());
+                            let close_tabs: Vec<TabId> = tabs
+                                .values()
+                                .filter(|tab| selector.matches(tab.name.as_str(), &tab.tags))
+                                .map(|tab| tab.id)
+                                .collect();
+
+                            if selector.is_group() {
+                                info!("closing {} tabs matching {}", close_tabs.len(), selector);
+                            }
+
+                            for id in close_tabs {
+                                let options = Self::close_options(options, assigned.contains(&id));
+                                Self::request_close(
+                                    id,
+                                    options,
                                     &mut tabs,
+                                    &mut closing,
                                     &mut tx,
                                     &mut tx_tabs,
                                     &mut tx_tabs_state,
                                 )
                                 .await?;
+
+                                if !options.force {
+                                    let deadline =
+                                        Self::close_deadline(id, options, tx_expired.clone());
+                                    deadlines.insert(id, deadline);
+                                }
+                            }
+                        }
+                        TabManagerRecv::ResumeTab(resume) => {
+                            let metadata = resume.metadata.clone();
+                            if !can_resume(&tabs, &closing, &metadata) {
+                                warn!(
+                                    "rejecting resumed tab {} ({}), which conflicts with a running tab",
+                                    &metadata.name, metadata.id
+                                );
+                                resume.reject();
+                                continue 'msg;
+                            }
+
+                            debug!("resuming tab {} ({})", &metadata.name, metadata.id);
+
+                            // new tabs must not be assigned the id of a resumed tab
+                            ids.hold(metadata.id);
+                            assigned.insert(metadata.id);
+
+                            tabs.insert(metadata.id, metadata);
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            resume.accept();
+                        }
+                        TabManagerRecv::RenameTab(id, name) => {
+                            if let Some(existing) = tabs.values().find(|tab| tab.name == name) {
+                                warn!(
+                                    "cannot rename tab {} to {}, which conflicts with running tab {}",
+                                    id, &name, existing.id
+                                );
+                                continue 'msg;
+                            }
+
+                            let metadata = match tabs.get_mut(&id) {
+                                Some(metadata) => metadata,
+                                None => continue 'msg,
+                            };
+
+                            info!("renaming tab {} from {} to {}", id, &metadata.name, &name);
+                            metadata.name = name.clone();
+
+                            let metadata = metadata.clone();
+                            tx_tabs.send(TabRecv::Rename(id, name)).await?;
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            tx_tab_updates.send(TabSend::Updated(metadata)).await?;
+                        }
+                        TabManagerRecv::UpdateProcess(id, process) => {
+                            let metadata = match tabs.get_mut(&id) {
+                                Some(metadata) => metadata,
+                                None => continue 'msg,
+                            };
+
+                            if metadata.process.as_ref() == Some(&process) {
+                                continue 'msg;
+                            }
+
+                            metadata.process = Some(process);
+
+                            let metadata = metadata.clone();
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            tx_tab_updates.send(TabSend::Updated(metadata)).await?;
+                        }
+                        TabManagerRecv::UpdateStats(id, stats) => {
+                            let metadata = match tabs.get_mut(&id) {
+                                Some(metadata) => metadata,
+                                None => continue 'msg,
+                            };
+
+                            if let Some(ref last) = metadata.stats {
+                                if !stats.is_significant_change(last) {
+                                    continue 'msg;
+                                }
+                            }
+
+                            metadata.stats = Some(Box::new(stats));
+
+                            let metadata = metadata.clone();
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            tx_tab_updates.send(TabSend::Updated(metadata)).await?;
+                        }
+                        TabManagerRecv::UpdateLog(id, path) => {
+                            let metadata = match tabs.get_mut(&id) {
+                                Some(metadata) => metadata,
+                                None => continue 'msg,
+                            };
+
+                            metadata.log = Some(path);
+
+                            let metadata = metadata.clone();
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
+                            tx_tab_updates.send(TabSend::Updated(metadata)).await?;
+                        }
+                        TabManagerRecv::CloseTab(id, options) => {
+                            if !tabs.contains_key(&id) {
+                                continue 'msg;
+                            }
+
+                            let options = Self::close_options(options, assigned.contains(&id));
+                            Self::request_close(
+                                id,
+                                options,
+                                &mut tabs,
+                                &mut closing,
+                                &mut tx,
+                                &mut tx_tabs,
+                                &mut tx_tabs_state,
+                            )
+                            .await?;
+
+                            if !options.force {
+                                let deadline =
+                                    Self::close_deadline(id, options, tx_expired.clone());
+                                deadlines.insert(id, deadline);
                             }
                         }
-                        TabManagerRecv::CloseTab(close) => {
-                            Self::close_tab(
+                        TabManagerRecv::TabAssigned(id) => {
+                            if tabs.contains_key(&id) {
+                                assigned.insert(id);
+                            }
+                        }
+                        TabManagerRecv::CloseExpired(id) => {
+                            deadlines.remove(&id);
+                            if !tabs.contains_key(&id) {
+                                continue 'msg;
+                            }
+
+                            warn!(
+                                "tab {} did not stop by the close deadline, and is being removed",
+                                id
+                            );
+                            assigned.remove(&id);
+
+                            let tab = Self::close_tab(
+                                id,
+                                &mut tabs,
+                                &mut tx,
+                                &mut tx_tabs,
+                                &mut tx_tabs_state,
+                            )
+                            .await?;
+
+                            if let Some(tab) = tab {
+                                run_hook(TabHook::Close, &tab);
+                            }
+
+                            ids.release(id);
+                        }
+                        TabManagerRecv::TabStopped(close) => {
+                            assigned.remove(&close);
+                            deadlines.remove(&close);
+
+                            let tab = Self::close_tab(
                                 close,
                                 &mut tabs,
                                 &mut tx,
@@ -82,6 +289,37 @@ impl Service for TabManagerService {
                                 &mut tx_tabs_state,
                             )
                             .await?;
+
+                            if let Some(tab) = tab.or_else(|| closing.remove(&close)) {
+                                run_hook(TabHook::Close, &tab);
+                            }
+
+                            ids.release(close);
+                        }
+                        TabManagerRecv::AssignmentCancelled(id) => {
+                            if !tabs.contains_key(&id) {
+                                // the tab was closed before a pty started, so it won't stop
+                                if let Some(tab) = closing.remove(&id) {
+                                    run_hook(TabHook::Close, &tab);
+                                }
+
+                                ids.release(id);
+                            }
+                        }
+                        TabManagerRecv::Shutdown => {
+                            // the ptys are terminated with the daemon, and may not report that they stopped
+                            let mut stopped: Vec<TabMetadata> = tabs
+                                .drain()
+                                .chain(closing.drain())
+                                .map(|(_, tab)| tab)
+                                .collect();
+                            stopped.sort_by_key(|tab| tab.id.0);
+
+                            for tab in stopped {
+                                run_hook(TabHook::Close, &tab);
+                            }
+
+                            tx_tabs_state.send(TabsState::new(&tabs)).await?;
                         }
                     }
                 }
@@ -94,14 +332,66 @@ impl Service for TabManagerService {
 }
 
 impl TabManagerService {
-    async fn close_tab(
+    /// A tab without a pty can't respond to a graceful close, so it is closed immediately
+    fn close_options(options: CloseOptions, assigned: bool) -> CloseOptions {
+        if assigned {
+            options
+        } else {
+            CloseOptions {
+                force: true,
+                ..options
+            }
+        }
+    }
+
+    /// Sends `CloseExpired` if the tab is still running after the final signal, and the slack period.
+    /// SIGKILL is sent after two grace periods.  The timer is cancelled if the lifeline is dropped.
+    fn close_deadline(
+        id: TabId,
+        options: CloseOptions,
+        mut tx: impl Sender<TabManagerRecv> + Send + 'static,
+    ) -> Lifeline {
+        let deadline = Duration::from_secs(2 * options.grace_period) + CLOSE_DEADLINE_SLACK;
+
+        Self::try_task("close_deadline", async move {
+            time::delay_for(deadline).await;
+            tx.send(TabManagerRecv::CloseExpired(id)).await?;
+            Ok(())
+        })
+    }
+
+    /// Requests that the pty close the tab, escalating from SIGHUP to SIGTERM to SIGKILL.
+    /// The tab is removed when the pty stops (or misses the close deadline), or immediately if the close is forced.
+    /// The tab id remains held, and the close hook is deferred, until the pty stops.
+    async fn request_close(
         id: TabId,
+        options: CloseOptions,
         tabs: &mut HashMap<TabId, TabMetadata>,
+        closing: &mut HashMap<TabId, TabMetadata>,
         tx: &mut impl Sender<TabManagerSend>,
         tx_close: &mut impl Sender<TabRecv>,
         tx_tabs_state: &mut impl Sender<TabsState>,
     ) -> anyhow::Result<()> {
-        tabs.remove(&id);
+        tx_close.send(TabRecv::Close(id, options)).await.ok();
+
+        if options.force {
+            if let Some(tab) = Self::close_tab(id, tabs, tx, tx_close, tx_tabs_state).await? {
+                closing.insert(id, tab);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the tab, and notifies the clients & ptys.  Returns the metadata of the removed tab.
+    async fn close_tab(
+        id: TabId,
+        tabs: &mut HashMap<TabId, TabMetadata>,
+        tx: &mut impl Sender<TabManagerSend>,
+        tx_close: &mut impl Sender<TabRecv>,
+        tx_tabs_state: &mut impl Sender<TabsState>,
+    ) -> anyhow::Result<Option<TabMetadata>> {
+        let tab = tabs.remove(&id);
 
         tx.send(TabManagerSend::TabTerminated(id))
             .await
@@ -114,6 +404,353 @@ impl TabManagerService {
             .context("tx_tabs_state TabsState")
             .ok();
 
+        Ok(tab)
+    }
+}
+
+/// Whether a tab from a previous daemon can be resumed, without conflicting with a running or closing tab.
+/// A tab which is already registered with the same id and name can be resumed.
+fn can_resume(
+    tabs: &HashMap<TabId, TabMetadata>,
+    closing: &HashMap<TabId, TabMetadata>,
+    metadata: &TabMetadata,
+) -> bool {
+    if closing.contains_key(&metadata.id) {
+        return false;
+    }
+
+    tabs.values().all(|tab| {
+        let same_id = tab.id == metadata.id;
+        let same_name = tab.name == metadata.name;
+        same_id == same_name
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TabManagerService;
+    use crate::{
+        bus::ListenerBus,
+        message::{
+            tab_assignment::AssignTab,
+            tab_manager::{ResumeTab, TabManagerRecv},
+        },
+        state::tab::TabsState,
+    };
+    use lifeline::{assert_completes, Bus, Receiver, Sender, Service};
+    use std::{
+        path::{Path, PathBuf},
+        time::{Duration, Instant},
+    };
+    use tab_api::tab::{CloseOptions, CreateTabMetadata, TabId, TabMetadata};
+    use tokio::time;
+
+    fn create(name: &str) -> CreateTabMetadata {
+        CreateTabMetadata {
+            name: name.into(),
+            dimensions: (1, 2),
+            shell: "shell".into(),
+            dir: "/".into(),
+            record: None,
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+        }
+    }
+
+    async fn assigned(rx: &mut impl Receiver<AssignTab>) -> Option<TabId> {
+        rx.recv().await.map(|assign| assign.0.id)
+    }
+
+    const FORCE: CloseOptions = CloseOptions {
+        force: true,
+        grace_period: 0,
+    };
+
+    fn hook_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("tab-manager-test-{}-{}", test, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A tab which appends its name to `closed.out` in the directory, when it closes
+    fn closing(name: &str, dir: &Path) -> CreateTabMetadata {
+        let mut create = create(name);
+        create.dir = dir.to_string_lossy().to_string();
+        create.hooks.on_close = Some("echo $TAB >> closed.out".to_string());
+        create
+    }
+
+    /// Waits for the close hooks to write the expected number of lines, and returns the sorted lines
+    async fn closed(dir: &Path, lines: usize) -> Vec<String> {
+        let start = Instant::now();
+        loop {
+            let contents = std::fs::read_to_string(dir.join("closed.out")).unwrap_or_default();
+            let mut closed: Vec<String> = contents.lines().map(str::to_string).collect();
+
+            if closed.len() >= lines || start.elapsed() > Duration::from_secs(5) {
+                closed.sort();
+                return closed;
+            }
+
+            time::delay_for(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn tab_ids() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let _rx_tabs_state = bus.rx::<TabsState>()?;
+
+        tx.send(TabManagerRecv::CreateTab(create("a/"))).await?;
+        tx.send(TabManagerRecv::CreateTab(create("b/"))).await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+            assert_eq!(Some(TabId(1)), assigned(&mut rx).await);
+        });
+
+        // the id of a stopped tab is not reused immediately
+        tx.send(TabManagerRecv::TabStopped(TabId(0))).await?;
+        tx.send(TabManagerRecv::CreateTab(create("c/"))).await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(2)), assigned(&mut rx).await);
+        });
+
+        // the id of a resumed tab is held
+        let (resumed, rx_resumed) = ResumeTab::new(TabMetadata::create(TabId(3), create("d/")));
+        tx.send(TabManagerRecv::ResumeTab(resumed)).await?;
+        tx.send(TabManagerRecv::CreateTab(create("e/"))).await?;
+        assert_completes!(async {
+            assert_eq!(Ok(true), rx_resumed.await);
+            assert_eq!(Some(TabId(4)), assigned(&mut rx).await);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_conflict() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        let (resumed, rx_resumed) = ResumeTab::new(TabMetadata::create(TabId(1), create("a/")));
+        tx.send(TabManagerRecv::ResumeTab(resumed)).await?;
+        assert_completes!(async {
+            assert_eq!(Ok(true), rx_resumed.await);
+        });
+
+        // the same tab id, with a different name
+        let (resumed, rx_resumed) = ResumeTab::new(TabMetadata::create(TabId(1), create("b/")));
+        tx.send(TabManagerRecv::ResumeTab(resumed)).await?;
+        assert_completes!(async {
+            assert_eq!(Ok(false), rx_resumed.await);
+        });
+
+        // the same tab name, with a different id
+        let (resumed, rx_resumed) = ResumeTab::new(TabMetadata::create(TabId(2), create("a/")));
+        tx.send(TabManagerRecv::ResumeTab(resumed)).await?;
+        assert_completes!(async {
+            assert_eq!(Ok(false), rx_resumed.await);
+        });
+
+        // a pty reconnecting for the running tab is accepted
+        let (resumed, rx_resumed) = ResumeTab::new(TabMetadata::create(TabId(1), create("a/")));
+        tx.send(TabManagerRecv::ResumeTab(resumed)).await?;
+        assert_completes!(async {
+            assert_eq!(Ok(true), rx_resumed.await);
+        });
+
+        let tabs = rx_tabs_state.borrow().tabs.clone();
+        assert_eq!(1, tabs.len());
+        assert_eq!("a/", tabs[&TabId(1)].name);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn forced_close_holds_id() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        tx.send(TabManagerRecv::CreateTab(create("a/"))).await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+        });
+
+        tx.send(TabManagerRecv::CloseTab(TabId(0), FORCE)).await?;
+
+        // the pty may still be running, so the id is skipped when the allocation wraps around
+        for index in 1..=u16::MAX {
+            tx.send(TabManagerRecv::CreateTab(create(&format!("{}/", index))))
+                .await?;
+            assert_completes!(async {
+                assert_eq!(Some(TabId(index)), assigned(&mut rx).await);
+            });
+            tx.send(TabManagerRecv::TabStopped(TabId(index))).await?;
+        }
+
+        tx.send(TabManagerRecv::CreateTab(create("b/"))).await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(1)), assigned(&mut rx).await);
+        });
+
+        assert!(!rx_tabs_state.borrow().tabs.contains_key(&TabId(0)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn forced_close_hook_waits_for_pty() -> anyhow::Result<()> {
+        let dir = hook_dir("forced");
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let _rx_tabs_state = bus.rx::<TabsState>()?;
+
+        tx.send(TabManagerRecv::CreateTab(closing("a/", &dir)))
+            .await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+        });
+
+        // the pty may still be running, so the hook is deferred
+        tx.send(TabManagerRecv::CloseTab(TabId(0), FORCE)).await?;
+        time::delay_for(Duration::from_millis(200)).await;
+        assert!(!dir.join("closed.out").exists());
+
+        tx.send(TabManagerRecv::TabStopped(TabId(0))).await?;
+        let closed = closed(&dir, 1).await;
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(vec!["a/".to_string()], closed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close_without_pty() -> anyhow::Result<()> {
+        let dir = hook_dir("unassigned");
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        tx.send(TabManagerRecv::CreateTab(closing("a/", &dir)))
+            .await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+        });
+
+        // no pty has accepted the tab, so it is closed without waiting for the pty to stop
+        tx.send(TabManagerRecv::CloseTab(TabId(0), CloseOptions::default()))
+            .await?;
+        time::delay_for(Duration::from_millis(200)).await;
+        assert!(!rx_tabs_state.borrow().tabs.contains_key(&TabId(0)));
+
+        tx.send(TabManagerRecv::AssignmentCancelled(TabId(0)))
+            .await?;
+        let closed = closed(&dir, 1).await;
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(vec!["a/".to_string()], closed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close_deadline() -> anyhow::Result<()> {
+        let dir = hook_dir("deadline");
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        tx.send(TabManagerRecv::CreateTab(closing("a/", &dir)))
+            .await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+        });
+        tx.send(TabManagerRecv::TabAssigned(TabId(0))).await?;
+
+        // the pty is given time to stop
+        let graceful = CloseOptions {
+            force: false,
+            grace_period: 0,
+        };
+        tx.send(TabManagerRecv::CloseTab(TabId(0), graceful))
+            .await?;
+        time::delay_for(Duration::from_millis(200)).await;
+        assert!(rx_tabs_state.borrow().tabs.contains_key(&TabId(0)));
+
+        // but it never reports that it stopped, so the tab is removed after the deadline
+        let start = Instant::now();
+        while rx_tabs_state.borrow().tabs.contains_key(&TabId(0))
+            && start.elapsed() < super::CLOSE_DEADLINE_SLACK * 2
+        {
+            time::delay_for(Duration::from_millis(50)).await;
+        }
+        assert!(!rx_tabs_state.borrow().tabs.contains_key(&TabId(0)));
+
+        let closed = closed(&dir, 1).await;
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(vec!["a/".to_string()], closed);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shutdown_runs_close_hooks() -> anyhow::Result<()> {
+        let dir = hook_dir("shutdown");
+        let bus = ListenerBus::default();
+        let _service = TabManagerService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<TabManagerRecv>()?;
+        let mut rx = bus.rx::<AssignTab>()?;
+        let _rx_tabs_state = bus.rx::<TabsState>()?;
+
+        tx.send(TabManagerRecv::CreateTab(closing("a/", &dir)))
+            .await?;
+        tx.send(TabManagerRecv::CreateTab(closing("b/", &dir)))
+            .await?;
+        assert_completes!(async {
+            assert_eq!(Some(TabId(0)), assigned(&mut rx).await);
+            assert_eq!(Some(TabId(1)), assigned(&mut rx).await);
+        });
+
+        tx.send(TabManagerRecv::CloseTab(TabId(1), FORCE)).await?;
+        tx.send(TabManagerRecv::Shutdown).await?;
+        let shutdown = closed(&dir, 2).await;
+
+        // the hooks run once, even if the ptys report that they stopped
+        tx.send(TabManagerRecv::TabStopped(TabId(0))).await?;
+        tx.send(TabManagerRecv::TabStopped(TabId(1))).await?;
+        time::delay_for(Duration::from_millis(200)).await;
+        let stopped = closed(&dir, 2).await;
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(vec!["a/".to_string(), "b/".to_string()], shutdown);
+        assert_eq!(shutdown, stopped);
+
         Ok(())
     }
 }
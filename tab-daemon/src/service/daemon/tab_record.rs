@@ -0,0 +1,324 @@
+use crate::{
+    message::{
+        tab::{TabRecv, TabSend},
+        tab_record::RecordTab,
+    },
+    prelude::*,
+    state::tab::TabsState,
+};
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use tab_api::{
+    asciicast::{Event as CastEvent, Header, OutputDecoder},
+    tab::TabId,
+};
+use tokio::{stream::StreamExt, task};
+
+static DEFAULT_DIMENSIONS: (u16, u16) = (80, 24);
+
+/// Records tab output & resize events to files, in asciicast v2 format.
+/// Recordings are started by `RecordTab` messages, and finished when the tab stops.
+pub struct TabRecordService {
+    _record: Lifeline,
+}
+
+enum Event {
+    Record(RecordTab),
+    Tab(TabSend),
+    Resize(TabId, (u16, u16)),
+}
+
+impl Service for TabRecordService {
+    type Bus = ListenerBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let rx_record = bus.rx::<RecordTab>()?;
+        let rx_tab_send = bus.rx::<TabSend>()?.into_inner().filter_map(Result::ok);
+        let rx_tab_recv = bus
+            .rx::<TabRecv>()?
+            .into_inner()
+            .filter_map(|msg| match msg {
+                Ok(TabRecv::Resize(id, dimensions)) => Some((id, dimensions)),
+                _ => None,
+            });
+        let rx_tabs_state = bus.rx::<TabsState>()?.into_inner();
+
+        let _record = Self::try_task("record", async move {
+            let mut events = {
+                let records = rx_record.map(Event::Record);
+                let tab_send = rx_tab_send.map(Event::Tab);
+                let resizes = rx_tab_recv.map(|(id, dimensions)| Event::Resize(id, dimensions));
+                records.merge(tab_send).merge(resizes)
+            };
+
+            let mut recordings: HashMap<TabId, Recording> = HashMap::new();
+            let mut dimensions: HashMap<TabId, (u16, u16)> = HashMap::new();
+
+            while let Some(event) = events.next().await {
+                match event {
+                    Event::Record(record) => {
+                        let size = dimensions.get(&record.id).copied().unwrap_or_else(|| {
+                            rx_tabs_state
+                                .borrow()
+                                .tabs
+                                .get(&record.id)
+                                .map(|tab| tab.dimensions)
+                                .unwrap_or(DEFAULT_DIMENSIONS)
+                        });
+
+                        match Recording::create_blocking(record.path.clone(), size).await {
+                            Ok(recording) => {
+                                info!(
+                                    "recording tab {} to {}",
+                                    record.id,
+                                    record.path.to_string_lossy()
+                                );
+                                recordings.insert(record.id, recording);
+                            }
+                            Err(err) => {
+                                error!(
+                                    "failed to start recording at {}: {}",
+                                    record.path.to_string_lossy(),
+                                    err
+                                );
+                            }
+                        }
+                    }
+                    Event::Tab(TabSend::Output(output)) => {
+                        if let Some(recording) = recordings.remove(&output.id) {
+                            let data = output.stdout.data.clone();
+                            match recording
+                                .blocking(move |rec| rec.output(data.as_slice()))
+                                .await
+                            {
+                                Ok(recording) => {
+                                    recordings.insert(output.id, recording);
+                                }
+                                Err(err) => error!("failed to record tab {}: {}", output.id, err),
+                            }
+                        }
+                    }
+                    Event::Tab(TabSend::Stopped(id)) => {
+                        if recordings.remove(&id).is_some() {
+                            info!("finished recording tab {}", id);
+                        }
+
+                        dimensions.remove(&id);
+                    }
+                    Event::Tab(_) => {}
+                    Event::Resize(id, size) => {
+                        dimensions.insert(id, size);
+
+                        if let Some(recording) = recordings.remove(&id) {
+                            match recording.blocking(move |rec| rec.resize(size)).await {
+                                Ok(recording) => {
+                                    recordings.insert(id, recording);
+                                }
+                                Err(err) => error!("failed to record tab {}: {}", id, err),
+                            }
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        });
+
+        Ok(Self { _record })
+    }
+}
+
+/// An active recording, which writes one asciicast line per event.
+struct Recording {
+    file: LineWriter<File>,
+    start: Instant,
+    decoder: OutputDecoder,
+}
+
+impl Recording {
+    pub fn create(path: &Path, dimensions: (u16, u16)) -> anyhow::Result<Self> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let mut header = Header::new(dimensions, timestamp);
+        if let Ok(term) = std::env::var("TERM") {
+            header.env.insert("TERM".to_string(), term);
+        }
+
+        let mut file = LineWriter::new(File::create(path)?);
+        writeln!(file, "{}", serde_json::to_string(&header)?)?;
+
+        Ok(Self {
+            file,
+            start: Instant::now(),
+            decoder: OutputDecoder::default(),
+        })
+    }
+
+    /// Creates the recording on a blocking thread
+    pub async fn create_blocking(path: PathBuf, dimensions: (u16, u16)) -> anyhow::Result<Self> {
+        task::spawn_blocking(move || Self::create(path.as_path(), dimensions)).await?
+    }
+
+    /// Runs the write on a blocking thread, and returns the recording if the write succeeds
+    pub async fn blocking(
+        mut self,
+        write: impl FnOnce(&mut Self) -> anyhow::Result<()> + Send + 'static,
+    ) -> anyhow::Result<Self> {
+        task::spawn_blocking(move || {
+            write(&mut self)?;
+            Ok(self)
+        })
+        .await?
+    }
+
+    pub fn output(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let data = self.decoder.decode(data);
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.write(CastEvent::Output(self.elapsed(), data))
+    }
+
+    pub fn resize(&mut self, dimensions: (u16, u16)) -> anyhow::Result<()> {
+        self.write(CastEvent::Resize(self.elapsed(), dimensions))
+    }
+
+    fn elapsed(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    fn write(&mut self, event: CastEvent) -> anyhow::Result<()> {
+        writeln!(self.file, "{}", event.to_line()?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TabRecordService;
+    use crate::{
+        bus::ListenerBus,
+        message::{
+            tab::{TabOutput, TabRecv, TabSend},
+            tab_record::RecordTab,
+        },
+    };
+    use lifeline::{assert_completes, Bus, Sender, Service};
+    use std::{path::PathBuf, sync::Arc, time::Duration};
+    use tab_api::{
+        asciicast::{parse, Event},
+        chunk::OutputChunk,
+        tab::TabId,
+    };
+    use tokio::time;
+
+    fn recording_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("tab-record-{}-{}.cast", name, std::process::id()));
+        path
+    }
+
+    async fn await_events(path: &PathBuf, count: usize) -> Vec<Event> {
+        loop {
+            if let Ok(recording) = std::fs::read_to_string(path) {
+                if let Ok((_header, events)) = parse(recording.as_str()) {
+                    if events.len() >= count {
+                        return events;
+                    }
+                }
+            }
+
+            time::delay_for(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn record() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let _service = TabRecordService::spawn(&bus)?;
+
+        let mut tx_record = bus.tx::<RecordTab>()?;
+        let mut tx_send = bus.tx::<TabSend>()?;
+        let mut tx_recv = bus.tx::<TabRecv>()?;
+
+        let path = recording_path("record");
+        tx_record
+            .send(RecordTab {
+                id: TabId(0),
+                path: path.clone(),
+            })
+            .await?;
+
+        time::delay_for(Duration::from_millis(10)).await;
+
+        tx_send
+            .send(TabSend::Output(TabOutput {
+                id: TabId(0),
+                stdout: Arc::new(OutputChunk {
+                    index: 0,
+                    data: b"hello".to_vec(),
+                }),
+            }))
+            .await?;
+
+        tx_recv.send(TabRecv::Resize(TabId(0), (100, 30))).await?;
+
+        assert_completes!(async {
+            let events = await_events(&path, 2).await;
+            assert!(matches!(&events[0], Event::Output(_, data) if data == "hello"));
+            assert!(matches!(&events[1], Event::Resize(_, (100, 30))));
+        });
+
+        std::fs::remove_file(path).ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ignores_other_tabs() -> anyhow::Result<()> {
+        let bus = ListenerBus::default();
+        let _service = TabRecordService::spawn(&bus)?;
+
+        let mut tx_record = bus.tx::<RecordTab>()?;
+        let mut tx_send = bus.tx::<TabSend>()?;
+
+        let path = recording_path("ignores_other_tabs");
+        tx_record
+            .send(RecordTab {
+                id: TabId(0),
+                path: path.clone(),
+            })
+            .await?;
+
+        time::delay_for(Duration::from_millis(10)).await;
+
+        for (id, data) in &[(1, "other"), (0, "recorded")] {
+            tx_send
+                .send(TabSend::Output(TabOutput {
+                    id: TabId(*id),
+                    stdout: Arc::new(OutputChunk {
+                        index: 0,
+                        data: data.as_bytes().to_vec(),
+                    }),
+                }))
+                .await?;
+        }
+
+        assert_completes!(async {
+            let events = await_events(&path, 1).await;
+            assert_eq!(1, events.len());
+            assert!(matches!(&events[0], Event::Output(_, data) if data == "recorded"));
+        });
+
+        std::fs::remove_file(path).ok();
+
+        Ok(())
+    }
+}
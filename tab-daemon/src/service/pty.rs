@@ -1,3 +1,4 @@
+pub mod output_log;
 pub mod scrollback;
 
 // mod session;
@@ -5,10 +6,14 @@ pub mod scrollback;
 use crate::message::pty::{PtyRecv, PtySend, PtyShutdown};
 use crate::prelude::*;
 
-use tab_api::pty::{PtyWebsocketRequest, PtyWebsocketResponse};
+use tab_api::{
+    log_file::LogScope,
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+};
 
 use tokio::time;
 
+use output_log::PtyLogService;
 use scrollback::PtyScrollbackService;
 use time::Duration;
 
@@ -17,6 +22,7 @@ pub struct PtyService {
     _websocket: Lifeline,
     _daemon: Lifeline,
     _scrollback: PtyScrollbackService,
+    _log: PtyLogService,
 }
 
 impl Service for PtyService {
@@ -24,6 +30,8 @@ impl Service for PtyService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let scope = bus.resource::<LogScope>().unwrap_or_default();
+
         // rx/tx from websocket
         // keep track of current tab
         // notify the tab manager of status
@@ -33,27 +41,51 @@ impl Service for PtyService {
             let mut tx_daemon = bus.tx::<PtySend>()?;
             let mut tx_shutdown = bus.tx::<PtyShutdown>()?;
 
-            Self::try_task("websocket", async move {
-                while let Some(msg) = rx_websocket.recv().await {
-                    match msg {
-                        PtyWebsocketResponse::Started(metadata) => {
-                            tx_daemon.send(PtySend::Started(metadata)).await?;
-                        }
-                        PtyWebsocketResponse::Output(output) => {
-                            tx_daemon.send(PtySend::Output(output)).await?;
-                        }
-                        PtyWebsocketResponse::Stopped => {
-                            debug!("received pty shutdown notification");
-                            tx_daemon.send(PtySend::Stopped).await?;
-                            time::delay_for(Duration::from_millis(100)).await;
-                            tx_shutdown.send(PtyShutdown {}).await?;
-                            break;
+            Self::try_task(
+                "websocket",
+                scope.scope(async move {
+                    while let Some(msg) = rx_websocket.recv().await {
+                        match msg {
+                            PtyWebsocketResponse::Started(metadata) => {
+                                tx_daemon.send(PtySend::Started(metadata)).await?;
+                            }
+                            PtyWebsocketResponse::Output(output) => {
+                                tx_daemon.send(PtySend::Output(output)).await?;
+                            }
+                            PtyWebsocketResponse::Replay(output) => {
+                                tx_daemon.send(PtySend::Replay(output)).await?;
+                            }
+                            PtyWebsocketResponse::Process(process) => {
+                                tx_daemon.send(PtySend::Process(process)).await?;
+                            }
+                            PtyWebsocketResponse::Stats(stats) => {
+                                tx_daemon.send(PtySend::Stats(stats)).await?;
+                            }
+                            PtyWebsocketResponse::Closing(signal) => {
+                                debug!("closing tab with {}", signal);
+                                tx_daemon.send(PtySend::Closing(signal)).await?;
+                            }
+                            PtyWebsocketResponse::Stopped => {
+                                debug!("received pty shutdown notification");
+                                tx_daemon.send(PtySend::Stopped).await?;
+                                time::delay_for(Duration::from_millis(100)).await;
+                                tx_shutdown.send(PtyShutdown {}).await?;
+                                break;
+                            }
+                            PtyWebsocketResponse::Resume(metadata) => {
+                                debug!("received resume request for tab {}", metadata.id);
+                                tx_daemon.send(PtySend::Resume(metadata)).await?;
+                            }
+                            PtyWebsocketResponse::Pid(pid) => {
+                                debug!("pty connected with pid {}", pid);
+                                tx_daemon.send(PtySend::Pid(pid)).await?;
+                            }
                         }
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _daemon = {
@@ -61,46 +93,68 @@ impl Service for PtyService {
             let mut tx_websocket = bus.tx::<PtyWebsocketRequest>()?;
             let mut tx_shutdown = bus.tx::<PtyShutdown>()?;
 
-            Self::try_task("daemon", async move {
-                while let Some(msg) = rx_daemon.recv().await {
-                    match msg {
-                        PtyRecv::Init(init) => {
-                            info!("PTY initialized on tab {}", init.id);
-                            let message = PtyWebsocketRequest::Init(init);
-                            tx_websocket.send(message).await?;
-                        }
-                        PtyRecv::Input(input) => {
-                            let message = PtyWebsocketRequest::Input(input);
-                            tx_websocket.send(message).await?;
-                        }
-                        PtyRecv::Resize(dimensions) => {
-                            debug!("resizing pty to {:?}", &dimensions);
-                            let message = PtyWebsocketRequest::Resize(dimensions);
-                            tx_websocket.send(message).await?;
-                        }
-                        PtyRecv::Terminate => {
-                            info!("PTY process terminating due to shell process shutdown");
-                            tx_websocket.send(PtyWebsocketRequest::Terminate).await?;
-
-                            time::delay_for(Duration::from_millis(50)).await;
-
-                            tx_shutdown.send(PtyShutdown {}).await?;
-                            break;
+            Self::try_task(
+                "daemon",
+                scope.scope(async move {
+                    while let Some(msg) = rx_daemon.recv().await {
+                        match msg {
+                            PtyRecv::Init(init) => {
+                                info!("PTY initialized on tab {}", init.id);
+                                let message = PtyWebsocketRequest::Init(init);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Input(input) => {
+                                let message = PtyWebsocketRequest::Input(input);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Resize(dimensions) => {
+                                debug!("resizing pty to {:?}", &dimensions);
+                                let message = PtyWebsocketRequest::Resize(dimensions);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Signal(signal) => {
+                                let message = PtyWebsocketRequest::Signal(signal);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Rename(name) => {
+                                let message = PtyWebsocketRequest::Rename(name);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Close(options) => {
+                                let message = PtyWebsocketRequest::Close(options);
+                                tx_websocket.send(message).await?;
+                            }
+                            PtyRecv::Terminate => {
+                                info!("PTY process terminating due to shell process shutdown");
+                                tx_websocket.send(PtyWebsocketRequest::Terminate).await?;
+
+                                time::delay_for(Duration::from_millis(50)).await;
+
+                                tx_shutdown.send(PtyShutdown {}).await?;
+                                break;
+                            }
+                            PtyRecv::Scrollback => {}
+                            PtyRecv::Log(path) => {
+                                // the pty retains the path, so the log continues if the daemon restarts
+                                let path = path.to_string_lossy().to_string();
+                                tx_websocket.send(PtyWebsocketRequest::Log(path)).await?;
+                            }
                         }
-                        PtyRecv::Scrollback => {}
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _scrollback = PtyScrollbackService::spawn(bus)?;
+        let _log = PtyLogService::spawn(bus)?;
 
         Ok(PtyService {
             _websocket,
             _daemon,
             _scrollback,
+            _log,
         })
     }
 }
@@ -116,7 +170,7 @@ mod websocket_tests {
     use tab_api::{
         chunk::OutputChunk,
         pty::PtyWebsocketResponse,
-        tab::{TabId, TabMetadata},
+        tab::{TabId, TabMetadata, TabProcess, TabSignal},
     };
 
     #[tokio::test]
@@ -133,6 +187,12 @@ mod websocket_tests {
             dimensions: (1, 2),
             shell: "shell".into(),
             dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
         };
         tx.send(PtyWebsocketResponse::Started(tab.clone())).await?;
 
@@ -167,6 +227,79 @@ mod websocket_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn process() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyWebsocketResponse>()?;
+        let mut rx = bus.rx::<PtySend>()?;
+
+        let process = TabProcess {
+            name: "cargo".into(),
+            cwd: "/src".into(),
+        };
+        tx.send(PtyWebsocketResponse::Process(process.clone()))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtySend::Process(process)), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn closing() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyWebsocketResponse>()?;
+        let mut rx = bus.rx::<PtySend>()?;
+
+        tx.send(PtyWebsocketResponse::Closing(TabSignal::Hangup))
+            .await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtySend::Closing(TabSignal::Hangup)), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyWebsocketResponse>()?;
+        let mut rx = bus.rx::<PtySend>()?;
+
+        let tab = TabMetadata {
+            id: TabId(2),
+            name: "name".into(),
+            dimensions: (1, 2),
+            shell: "shell".into(),
+            dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        };
+        tx.send(PtyWebsocketResponse::Resume(tab.clone())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtySend::Resume(tab)), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn stopped() -> anyhow::Result<()> {
         let bus = PtyBus::default();
@@ -222,7 +355,7 @@ mod daemon_tests {
     use tab_api::{
         chunk::InputChunk,
         pty::PtyWebsocketRequest,
-        tab::{TabId, TabMetadata},
+        tab::{CloseOptions, TabId, TabMetadata},
     };
 
     #[tokio::test]
@@ -239,6 +372,12 @@ mod daemon_tests {
             dimensions: (1, 2),
             shell: "shell".into(),
             dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
         };
         tx.send(PtyRecv::Init(tab.clone())).await?;
 
@@ -287,6 +426,61 @@ mod daemon_tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rename() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyRecv>()?;
+        let mut rx = bus.rx::<PtyWebsocketRequest>()?;
+
+        tx.send(PtyRecv::Rename("new/".into())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtyWebsocketRequest::Rename("new/".into())), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyRecv>()?;
+        let mut rx = bus.rx::<PtyWebsocketRequest>()?;
+
+        tx.send(PtyRecv::Log("/tab.log".into())).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtyWebsocketRequest::Log("/tab.log".into())), msg);
+        });
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn close() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyService::spawn(&bus)?;
+
+        let mut tx = bus.tx::<PtyRecv>()?;
+        let mut rx = bus.rx::<PtyWebsocketRequest>()?;
+
+        let options = CloseOptions::default();
+        tx.send(PtyRecv::Close(options)).await?;
+
+        assert_completes!(async move {
+            let msg = rx.recv().await;
+            assert_eq!(Some(PtyWebsocketRequest::Close(options)), msg);
+        });
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn terminate() -> anyhow::Result<()> {
         let bus = PtyBus::default();
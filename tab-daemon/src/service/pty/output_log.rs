@@ -0,0 +1,261 @@
+use crate::{
+    message::pty::{PtyRecv, PtySend},
+    prelude::*,
+};
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tab_api::{
+    ansi::AnsiStripper,
+    chunk::OutputChunk,
+    log_file::{LogScope, RotatingFile},
+};
+use tokio::{stream::StreamExt, task};
+
+static MAX_LOG_SIZE: u64 = 10 * 1024 * 1024;
+static MAX_LOG_FILES: usize = 5;
+
+/// Spawns with a pty connection, and appends tab output to a log file, with ANSI escape sequences removed.
+/// Logs are opened from the `log` tab metadata field, or a `PtyRecv::Log` request.
+///
+/// The log runs in the daemon, so it continues when no clients are attached.
+/// Output which is replayed when a tab resumes was logged by the previous daemon, so it is skipped.
+pub struct PtyLogService {
+    _log: Lifeline,
+}
+
+enum Event {
+    Open(PathBuf),
+    Output(OutputChunk),
+}
+
+impl Service for PtyLogService {
+    type Bus = PtyBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let scope = bus.resource::<LogScope>().unwrap_or_default();
+
+        let rx_open = bus
+            .rx::<PtyRecv>()?
+            .into_inner()
+            .filter_map(|msg| match msg {
+                Ok(PtyRecv::Init(metadata)) => metadata.log.map(PathBuf::from),
+                Ok(PtyRecv::Log(path)) => Some(path),
+                _ => None,
+            });
+
+        let rx_output = bus
+            .rx::<PtySend>()?
+            .into_inner()
+            .filter_map(|msg| match msg {
+                Ok(PtySend::Output(output)) => Some(output),
+                _ => None,
+            });
+
+        let _log = Self::try_task(
+            "log",
+            scope.scope(async move {
+                let mut events = {
+                    let opens = rx_open.map(Event::Open);
+                    let outputs = rx_output.map(Event::Output);
+                    opens.merge(outputs)
+                };
+
+                let mut log: Option<LogFile> = None;
+
+                while let Some(event) = events.next().await {
+                    match event {
+                        Event::Open(path) => match LogFile::open_blocking(path.clone()).await {
+                            Ok(file) => {
+                                info!("logging tab output to {}", path.to_string_lossy());
+                                log = Some(file);
+                            }
+                            Err(err) => {
+                                error!(
+                                    "failed to open tab log {}: {}",
+                                    path.to_string_lossy(),
+                                    err
+                                );
+                            }
+                        },
+                        Event::Output(output) => {
+                            if let Some(file) = log.take() {
+                                match file.write_blocking(output.data).await {
+                                    Ok(file) => log = Some(file),
+                                    Err(err) => error!("failed to write tab log: {}", err),
+                                }
+                            }
+                        }
+                    }
+                }
+
+                Ok(())
+            }),
+        );
+
+        Ok(Self { _log })
+    }
+}
+
+/// An append-only log file, with ANSI escape sequences removed.
+/// The file is rotated when it exceeds the max size, and rotated logs are renamed to `<path>.1`, `<path>.2`, up to `<path>.5`.
+struct LogFile {
+    file: RotatingFile,
+    stripper: AnsiStripper,
+}
+
+impl LogFile {
+    pub fn open(path: &Path, max_size: u64) -> anyhow::Result<Self> {
+        let file = RotatingFile::with_limits(path.to_path_buf(), max_size, MAX_LOG_FILES)?;
+
+        Ok(Self {
+            file,
+            stripper: AnsiStripper::default(),
+        })
+    }
+
+    /// Opens the log on a blocking thread
+    pub async fn open_blocking(path: PathBuf) -> anyhow::Result<Self> {
+        task::spawn_blocking(move || Self::open(path.as_path(), MAX_LOG_SIZE)).await?
+    }
+
+    /// Writes the data on a blocking thread, and returns the log if the write succeeds
+    pub async fn write_blocking(mut self, data: Vec<u8>) -> anyhow::Result<Self> {
+        task::spawn_blocking(move || {
+            self.write(data.as_slice())?;
+            Ok(self)
+        })
+        .await?
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> anyhow::Result<()> {
+        let data = self.stripper.strip(data);
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        self.file.write_all(data.as_slice())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogFile, PtyLogService};
+    use crate::{
+        bus::PtyBus,
+        message::pty::{PtyRecv, PtySend},
+    };
+    use lifeline::{assert_completes, Bus, Sender, Service};
+    use std::{path::PathBuf, time::Duration};
+    use tab_api::{chunk::OutputChunk, log_file::rotated_path};
+    use tokio::time;
+
+    fn log_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("tab-log-{}-{}.log", name, std::process::id()));
+        path
+    }
+
+    #[test]
+    fn rotate() -> anyhow::Result<()> {
+        let path = log_path("rotate");
+        let rotated = rotated_path(path.as_path(), 1);
+
+        let mut log = LogFile::open(path.as_path(), 8)?;
+        log.write(b"12345\n")?;
+        log.write(b"678\n")?;
+
+        assert_eq!("12345\n", std::fs::read_to_string(&rotated)?);
+        assert_eq!("678\n", std::fs::read_to_string(&path)?);
+
+        std::fs::remove_file(path).ok();
+        std::fs::remove_file(rotated).ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn log() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyLogService::spawn(&bus)?;
+
+        let mut tx_recv = bus.tx::<PtyRecv>()?;
+        let mut tx_send = bus.tx::<PtySend>()?;
+
+        let path = log_path("log");
+        tx_recv.send(PtyRecv::Log(path.clone())).await?;
+        time::delay_for(Duration::from_millis(10)).await;
+
+        tx_send
+            .send(PtySend::Output(OutputChunk {
+                index: 0,
+                data: b"\x1b[31mhello\x1b[0m\r\n".to_vec(),
+            }))
+            .await?;
+
+        assert_completes!(async {
+            loop {
+                if let Ok(log) = std::fs::read_to_string(&path) {
+                    if !log.is_empty() {
+                        assert_eq!("hello\n", log);
+                        break;
+                    }
+                }
+
+                time::delay_for(Duration::from_millis(5)).await;
+            }
+        });
+
+        std::fs::remove_file(path).ok();
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skips_replay() -> anyhow::Result<()> {
+        let bus = PtyBus::default();
+        let _service = PtyLogService::spawn(&bus)?;
+
+        let mut tx_recv = bus.tx::<PtyRecv>()?;
+        let mut tx_send = bus.tx::<PtySend>()?;
+
+        let path = log_path("replay");
+        tx_recv.send(PtyRecv::Log(path.clone())).await?;
+        time::delay_for(Duration::from_millis(10)).await;
+
+        tx_send
+            .send(PtySend::Replay(OutputChunk {
+                index: 0,
+                data: b"logged\n".to_vec(),
+            }))
+            .await?;
+        tx_send
+            .send(PtySend::Output(OutputChunk {
+                index: 7,
+                data: b"new\n".to_vec(),
+            }))
+            .await?;
+
+        assert_completes!(async {
+            loop {
+                if let Ok(log) = std::fs::read_to_string(&path) {
+                    if !log.is_empty() {
+                        assert_eq!("new\n", log);
+                        break;
+                    }
+                }
+
+                time::delay_for(Duration::from_millis(5)).await;
+            }
+        });
+
+        std::fs::remove_file(path).ok();
+
+        Ok(())
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
 };
 
 use std::{collections::VecDeque, sync::Arc};
-use tab_api::chunk::OutputChunk;
+use tab_api::{chunk::OutputChunk, log_file::LogScope};
 use tokio::sync::Mutex;
 
 static MIN_CAPACITY: usize = 32768;
@@ -22,6 +22,8 @@ impl Service for PtyScrollbackService {
     type Lifeline = anyhow::Result<Self>;
 
     fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let scope = bus.resource::<LogScope>().unwrap_or_default();
+
         let buffer = ScrollbackManager::new();
 
         let _serve = {
@@ -29,31 +31,37 @@ impl Service for PtyScrollbackService {
             let mut tx = bus.tx::<PtySend>()?;
             let serve_scrollback = buffer.clone();
 
-            Self::try_task("serve", async move {
-                while let Some(msg) = rx.recv().await {
-                    if let PtyRecv::Scrollback = msg {
-                        let scrollback = serve_scrollback.handle();
-                        let response = PtySend::Scrollback(scrollback);
-                        tx.send(response).await?;
+            Self::try_task(
+                "serve",
+                scope.scope(async move {
+                    while let Some(msg) = rx.recv().await {
+                        if let PtyRecv::Scrollback = msg {
+                            let scrollback = serve_scrollback.handle();
+                            let response = PtySend::Scrollback(scrollback);
+                            tx.send(response).await?;
+                        }
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         let _update = {
             let mut rx = bus.rx::<PtySend>()?;
 
-            Self::try_task("serve", async move {
-                while let Some(msg) = rx.recv().await {
-                    if let PtySend::Output(output) = msg {
-                        buffer.push(output).await;
+            Self::try_task(
+                "serve",
+                scope.scope(async move {
+                    while let Some(msg) = rx.recv().await {
+                        if let PtySend::Output(output) | PtySend::Replay(output) = msg {
+                            buffer.push(output).await;
+                        }
                     }
-                }
 
-                Ok(())
-            })
+                    Ok(())
+                }),
+            )
         };
 
         Ok(Self { _serve, _update })
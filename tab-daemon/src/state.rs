@@ -1,3 +1,5 @@
+pub mod activity;
 pub mod assignment;
 pub mod pty;
 pub mod tab;
+pub mod tab_id;
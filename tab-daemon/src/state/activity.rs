@@ -0,0 +1,71 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+};
+use tab_api::tab::{TabActivity, TabId};
+
+/// The activity of all running tabs, maintained by the `TabActivityService`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabsActivityState {
+    pub tabs: HashMap<TabId, TabActivity>,
+}
+
+/// Creates an attachment to the given tab.
+///
+/// Returns an (attachment, watch) pair.
+///
+/// The attachment is held by the client connection, for as long as it is viewing the tab.
+///
+/// The watch can be forwarded to the daemon, which considers the tab attached until the attachment is dropped.
+/// This includes connections which are closed abruptly.
+pub fn attachment(id: TabId) -> (Attachment, AttachmentWatch) {
+    let state = Arc::new(id);
+    let watch = AttachmentWatch {
+        id,
+        state: Arc::downgrade(&state),
+    };
+
+    (Attachment { _state: state }, watch)
+}
+
+/// The attachment half of the attachment pair.  The tab is detached when this value is dropped.
+#[derive(Debug)]
+pub struct Attachment {
+    _state: Arc<TabId>,
+}
+
+/// The watch half of the attachment pair.  Can be used to determine whether the attachment is still held.
+#[derive(Debug, Clone)]
+pub struct AttachmentWatch {
+    pub id: TabId,
+    state: Weak<TabId>,
+}
+
+impl AttachmentWatch {
+    pub fn is_attached(&self) -> bool {
+        self.state.strong_count() > 0
+    }
+}
+
+impl PartialEq for AttachmentWatch {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.state.ptr_eq(&other.state)
+    }
+}
+
+impl Eq for AttachmentWatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::attachment;
+    use tab_api::tab::TabId;
+
+    #[test]
+    fn attached_until_dropped() {
+        let (attachment, watch) = attachment(TabId(0));
+        assert!(watch.is_attached());
+
+        drop(attachment);
+        assert!(!watch.is_attached());
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+use tab_api::tab::TabId;
+
+/// Allocates the ids of new tabs.
+///
+/// Ids are allocated in order, and wrap around after `u16::MAX`.  An id is held from allocation until it is released,
+/// and held ids are skipped, so an id is never reused while a running tab, a closing pty, or an in-flight assignment holds it.
+/// As allocation continues from the last id, a released id is only reused after the other free ids have been allocated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabIds {
+    next: u16,
+    held: HashSet<TabId>,
+}
+
+impl TabIds {
+    /// Allocates the next free id, or returns None if every id is held
+    pub fn allocate(&mut self) -> Option<TabId> {
+        for offset in 0..=u16::MAX {
+            let id = TabId(self.next.wrapping_add(offset));
+            if self.held.insert(id) {
+                self.next = id.0.wrapping_add(1);
+                return Some(id);
+            }
+        }
+
+        None
+    }
+
+    /// Holds an id which was allocated elsewhere, such as a tab resumed from a previous daemon
+    pub fn hold(&mut self, id: TabId) {
+        self.held.insert(id);
+    }
+
+    /// Releases the id, so it can be allocated again
+    pub fn release(&mut self, id: TabId) {
+        self.held.remove(&id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TabIds;
+    use tab_api::tab::TabId;
+
+    #[test]
+    fn allocates_in_order() {
+        let mut ids = TabIds::default();
+        assert_eq!(Some(TabId(0)), ids.allocate());
+        assert_eq!(Some(TabId(1)), ids.allocate());
+
+        // released ids are not reused until the allocation wraps around
+        ids.release(TabId(0));
+        assert_eq!(Some(TabId(2)), ids.allocate());
+    }
+
+    #[test]
+    fn skips_held_ids() {
+        let mut ids = TabIds::default();
+        ids.hold(TabId(1));
+        ids.hold(TabId(2));
+
+        assert_eq!(Some(TabId(0)), ids.allocate());
+        assert_eq!(Some(TabId(3)), ids.allocate());
+    }
+
+    #[test]
+    fn wraps_without_reusing_live_ids() {
+        let mut ids = TabIds::default();
+        let live = ids.allocate().unwrap();
+
+        // short-lived tabs, which are released when they stop
+        for _ in 0..200_000 {
+            let id = ids.allocate().unwrap();
+            assert_ne!(live, id);
+            ids.release(id);
+        }
+    }
+
+    #[test]
+    fn exhausted() {
+        let mut ids = TabIds::default();
+        for _ in 0..=u16::MAX {
+            assert!(ids.allocate().is_some());
+        }
+
+        assert_eq!(None, ids.allocate());
+
+        ids.release(TabId(100));
+        assert_eq!(Some(TabId(100)), ids.allocate());
+    }
+}
@@ -414,6 +414,9 @@ pub trait PtyMaster: PollPtyMaster {
 
     /// Retrieves the size of the pty
     async fn size(&self) -> Result<(u16, u16), io::Error>;
+
+    /// Retrieves the id of the foreground process group of the pty
+    async fn foreground_process_group(&self) -> Result<u32, io::Error>;
 }
 
 #[async_trait]
@@ -434,6 +437,10 @@ where
     async fn size(&self) -> Result<(u16, u16), io::Error> {
         GetSize(self).await
     }
+
+    async fn foreground_process_group(&self) -> Result<u32, io::Error> {
+        GetForegroundProcessGroup(self).await
+    }
 }
 /// Trait containing generalized methods for PTYs
 pub trait PollPtyMaster {
@@ -450,6 +457,9 @@ pub trait PollPtyMaster {
 
     /// Get the PTY size
     fn poll_winsize(&self, cx: &mut Context<'_>) -> Poll<Result<(c_ushort, c_ushort), io::Error>>;
+
+    /// Get the id of the foreground process group
+    fn poll_foreground_process_group(&self, cx: &mut Context<'_>) -> Poll<Result<u32, io::Error>>;
 }
 
 impl<T: AsAsyncPtyFd> PollPtyMaster for T {
@@ -484,6 +494,15 @@ impl<T: AsAsyncPtyFd> PollPtyMaster for T {
         Poll::Ready(Ok((winsz.ws_col, winsz.ws_row)))
     }
 
+    fn poll_foreground_process_group(&self, cx: &mut Context<'_>) -> Poll<Result<u32, io::Error>> {
+        let fd = futures::ready!(self.as_async_pty_fd(cx));
+        let pgrp = unsafe { libc::tcgetpgrp(fd) };
+        if pgrp < 0 {
+            return Poll::Ready(Err(io::Error::last_os_error()));
+        }
+        Poll::Ready(Ok(pgrp as u32))
+    }
+
     fn poll_resize(
         &self,
         cx: &mut Context<'_>,
@@ -609,6 +628,14 @@ impl<'a, T: PtyMaster + Send> Future for GetSize<'a, T> {
     }
 }
 
+struct GetForegroundProcessGroup<'a, T: PtyMaster + Send>(&'a T);
+impl<'a, T: PtyMaster + Send> Future for GetForegroundProcessGroup<'a, T> {
+    type Output = io::Result<u32>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_foreground_process_group(cx)
+    }
+}
+
 struct Resize<'a, T: PtyMaster + Send> {
     pub pty: &'a T,
     pub rows: c_ushort,
@@ -718,6 +745,26 @@ mod tests {
         child.kill().expect("Could not kill child");
     }
 
+    #[tokio::test]
+    async fn test_foreground_process_group() {
+        let master = AsyncPtyMaster::open().expect("Could not open the PTY");
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn_pty_async(&master)
+            .expect("Could not spawn child");
+
+        // the child is a session leader, and becomes the foreground process group of the pty
+        let pgrp = master
+            .foreground_process_group()
+            .await
+            .expect("Could not get the foreground process group");
+
+        assert_eq!(pgrp, child.id());
+
+        child.kill().expect("Could not kill child");
+    }
+
     #[tokio::test]
     async fn test_from_fd() {
         let master = AsyncPtyMaster::open().expect("Could not open the PTY");
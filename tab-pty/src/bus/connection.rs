@@ -0,0 +1,20 @@
+use crate::prelude::*;
+use tab_api::pty::{PtyWebsocketRequest, PtyWebsocketResponse};
+use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
+use tokio::sync::{broadcast, mpsc};
+
+lifeline_bus!(pub struct ConnectionBus);
+
+impl Message<ConnectionBus> for PtyWebsocketRequest {
+    type Channel = broadcast::Sender<Self>;
+}
+
+impl Message<ConnectionBus> for PtyWebsocketResponse {
+    type Channel = mpsc::Sender<Self>;
+}
+
+impl Resource<ConnectionBus> for WebsocketResource {}
+impl WebsocketMessageBus for ConnectionBus {
+    type Send = PtyWebsocketResponse;
+    type Recv = PtyWebsocketRequest;
+}
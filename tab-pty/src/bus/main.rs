@@ -3,7 +3,6 @@ use tab_api::{
     config::DaemonConfig,
     pty::{PtyWebsocketRequest, PtyWebsocketResponse},
 };
-use tab_websocket::{bus::WebsocketMessageBus, resource::connection::WebsocketResource};
 use tokio::sync::{broadcast, mpsc};
 
 lifeline_bus!(pub struct MainBus);
@@ -21,8 +20,3 @@ impl Message<MainBus> for MainShutdown {
 }
 
 impl Resource<MainBus> for DaemonConfig {}
-impl Resource<MainBus> for WebsocketResource {}
-impl WebsocketMessageBus for MainBus {
-    type Send = PtyWebsocketResponse;
-    type Recv = PtyWebsocketRequest;
-}
@@ -1,18 +1,23 @@
 use crate::prelude::*;
 
 use message::pty::MainShutdown;
-use simplelog::{CombinedLogger, TermLogger, TerminalMode};
-use std::time::Duration;
-use tab_api::{launch::*, log::get_level, pty::PtyWebsocketRequest};
+use simplelog::{CombinedLogger, SharedLogger, TermLogger, TerminalMode};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tab_api::{
+    config::pty_log, launch::*, log::get_level, log_file::file_logger, pty::PtyWebsocketRequest,
+};
 
 use lifeline::dyn_bus::DynBus;
 use service::main::MainService;
-use tab_websocket::resource::connection::WebsocketResource;
 
 mod bus;
 mod message;
 mod prelude;
 mod service;
+mod state;
 
 pub fn pty_main() -> anyhow::Result<()> {
     debug!("pty process started");
@@ -33,17 +38,64 @@ pub fn pty_main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// The number of pty log files which are retained, including the log of the current process
+static MAX_PTY_LOGS: usize = 100;
+
 fn init() {
     let level = get_level().unwrap_or(LevelFilter::Info);
 
-    CombinedLogger::init(vec![TermLogger::new(
+    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![TermLogger::new(
         level,
         simplelog::ConfigBuilder::new()
             .set_time_format_str("%H:%M:%S%.3f PTY")
             .build(),
         TerminalMode::Stderr,
-    )])
-    .unwrap();
+    )];
+
+    let file_config = simplelog::ConfigBuilder::new()
+        .set_time_format_str("%Y-%m-%d %H:%M:%S%.3f PTY")
+        .build();
+
+    match pty_log(std::process::id()) {
+        Ok(path) => {
+            prune_logs(path.as_path());
+
+            match file_logger(level, file_config, "pty", path) {
+                Ok(logger) => loggers.push(logger),
+                Err(e) => eprintln!("failed to open the pty log file: {}", e),
+            }
+        }
+        Err(e) => eprintln!("failed to resolve the pty log file: {}", e),
+    }
+
+    CombinedLogger::init(loggers).unwrap();
+}
+
+/// Removes the oldest pty log files, so the logs of short-lived tabs don't accumulate
+fn prune_logs(path: &Path) {
+    let dir = match path.parent().and_then(|dir| std::fs::read_dir(dir).ok()) {
+        Some(dir) => dir,
+        None => return,
+    };
+
+    let mut logs: Vec<(SystemTime, PathBuf)> = dir
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("pty-"))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+
+    if logs.len() < MAX_PTY_LOGS {
+        return;
+    }
+
+    logs.sort();
+    let remove = logs.len() + 1 - MAX_PTY_LOGS;
+    for (_modified, log) in logs.into_iter().take(remove) {
+        std::fs::remove_file(log).ok();
+    }
 }
 
 async fn main_async() -> anyhow::Result<()> {
@@ -64,10 +116,6 @@ async fn spawn() -> anyhow::Result<(
 
     let bus = MainBus::default();
     bus.capacity::<PtyWebsocketRequest>(64)?;
-
-    let ws_url = format!("ws://127.0.0.1:{}/pty", config.port);
-    let websocket = tab_websocket::connect_authorized(ws_url, config.auth_token.clone()).await?;
-    bus.store_resource(WebsocketResource(websocket));
     bus.store_resource(config);
 
     let main = MainService::spawn(&bus)?;
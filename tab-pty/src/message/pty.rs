@@ -1,6 +1,9 @@
 use lifeline::impl_storage_clone;
 use std::{collections::HashMap, path::PathBuf, process::ExitStatus};
-use tab_api::chunk::{InputChunk, OutputChunk};
+use tab_api::{
+    chunk::{InputChunk, OutputChunk},
+    tab::{TabProcess, TabSignal, TabStats},
+};
 
 /// Terminates the process, websocket connection, and via cancellation the connected PTY shell session
 #[derive(Debug, Clone)]
@@ -21,6 +24,10 @@ pub struct PtyShutdown {}
 pub enum PtyRequest {
     Resize((u16, u16)),
     Input(InputChunk),
+    /// Delivers the signal to the foreground process group
+    Signal(TabSignal),
+    /// Delivers the signal to the shell and the foreground process group, as the tab closes
+    Close(TabSignal),
     Shutdown,
 }
 
@@ -30,10 +37,16 @@ pub enum PtyRequest {
 ///
 /// Usage:
 /// - Rx into the `ClientSessionService`, to forward messages along the websocket to the daemon.
-/// - Tx from the `PtyService`, to forward stdout and termination messages.
+/// - Tx from the `PtyService`, to forward stdout, foreground process, resource usage, and termination messages.
 #[derive(Debug, Clone)]
 pub enum PtyResponse {
     Output(OutputChunk),
+    /// The foreground process or working directory of the shell has changed
+    Process(TabProcess),
+    /// The resource usage of the processes in the tab has changed
+    Stats(TabStats),
+    /// The signal has been delivered to the processes in the closing tab
+    Closing(TabSignal),
     Terminated(ExitStatus),
 }
 
@@ -3,15 +3,13 @@ use crate::prelude::*;
 
 use super::pty::PtyService;
 use lifeline::dyn_bus::DynBus;
-use std::{
-    collections::{hash_map::DefaultHasher, HashMap},
-    hash::{Hash, Hasher},
-    path::PathBuf,
-};
+use std::{collections::HashMap, path::PathBuf};
 use tab_api::{
-    config::history_path,
+    config::{fish_history, history_path, tab_name_path},
     env::is_raw_mode,
+    log_file::{set_context, LogContext},
     pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+    tab::{CloseOptions, TabId, TabMetadata, TabSignal},
 };
 use time::Duration;
 use tokio::time;
@@ -53,12 +51,22 @@ impl ClientService {
         // TODO: handle ptyshutdown here.
         // it should cancel the session lifeline
         let mut _session = None;
+        let mut tab: Option<TabMetadata> = None;
         while let Some(msg) = rx.recv().await {
             match msg {
                 PtyWebsocketRequest::Init(create) => {
+                    set_context(LogContext {
+                        connection: None,
+                        tab: Some(create.id),
+                    });
                     debug!("initializing on tab {}", create.id);
                     let name = create.name.clone();
 
+                    // a previous tab with the same id may have been renamed
+                    if let Ok(path) = tab_name_path(create.id) {
+                        std::fs::remove_file(path).ok();
+                    }
+
                     let mut env = HashMap::new();
                     env.insert("SHELL".to_string(), create.shell.clone());
                     env.insert("TAB".to_string(), create.name.clone());
@@ -87,13 +95,7 @@ impl ClientService {
                             env.insert("HISTFILE".to_string(), home.to_string_lossy().to_string());
                         }
                         Shell::Fish => {
-                            let mut hasher = DefaultHasher::new();
-                            name.hash(&mut hasher);
-                            let id = hasher.finish();
-
-                            let history = format!("tab_{}", id);
-
-                            env.insert("fish_history".to_string(), history);
+                            env.insert("fish_history".to_string(), fish_history(name.as_str()));
                         }
                         Shell::Unknown => {}
                     }
@@ -131,10 +133,29 @@ impl ClientService {
                     _session = Some(session);
 
                     debug!("tab initialized, name {}", name);
+                    tab = Some(create.clone());
                     tx.send(PtyWebsocketResponse::Started(create)).await?;
                 }
+                PtyWebsocketRequest::Rename(name) => {
+                    if let Some(ref mut tab) = tab {
+                        info!("renaming tab {} to {}", tab.name, name);
+
+                        if let Err(e) = migrate_history(tab.shell.as_str(), &tab.name, &name) {
+                            warn!("failed to migrate the history of tab {}: {}", tab.name, e);
+                        }
+
+                        if let Err(e) = write_name(tab.id, name.as_str()) {
+                            warn!("failed to write the name of tab {}: {}", tab.id, e);
+                        }
+
+                        tab.name = name;
+                    }
+                }
                 PtyWebsocketRequest::Input(_) => {}
                 PtyWebsocketRequest::Resize(_) => {}
+                PtyWebsocketRequest::Signal(_) => {}
+                PtyWebsocketRequest::Close(_) => {}
+                PtyWebsocketRequest::Log(_) => {}
                 PtyWebsocketRequest::Terminate => {
                     // in case we somehow get a pty termination request, but don't have a session running,
                     // send a main shutdown message
@@ -191,9 +212,12 @@ impl Service for ClientSessionService {
 impl ClientSessionService {
     async fn input(
         mut rx: impl Receiver<PtyWebsocketRequest>,
-        mut tx_pty: impl Sender<PtyRequest>,
+        mut tx_pty: impl Sender<PtyRequest> + Clone + Send + 'static,
         mut tx_shutdown: impl Sender<PtyShutdown>,
     ) -> anyhow::Result<()> {
+        // the escalation is cancelled when the session ends
+        let mut _close = None;
+
         while let Some(request) = rx.recv().await {
             match request {
                 PtyWebsocketRequest::Input(input) => {
@@ -214,6 +238,19 @@ impl ClientSessionService {
 
                     tx_pty.send(PtyRequest::Resize(dimensions)).await.ok();
                 }
+                PtyWebsocketRequest::Signal(signal) => {
+                    debug!("received signal request: {}", signal);
+
+                    tx_pty.send(PtyRequest::Signal(signal)).await.ok();
+                }
+                PtyWebsocketRequest::Close(options) => {
+                    info!("Closing due to command request: {:?}", options);
+
+                    // a forced close replaces an escalation which is in progress
+                    if _close.is_none() || options.force {
+                        _close = Some(Self::task("close", Self::close(options, tx_pty.clone())));
+                    }
+                }
                 _ => {}
             }
         }
@@ -221,6 +258,23 @@ impl ClientSessionService {
         Ok(())
     }
 
+    /// Sends SIGHUP, then SIGTERM after the grace period, then SIGKILL after another grace period.
+    /// The session (and this task) ends when the shell exits.
+    async fn close(options: CloseOptions, mut tx_pty: impl Sender<PtyRequest>) {
+        if options.force {
+            tx_pty.send(PtyRequest::Close(TabSignal::Kill)).await.ok();
+            return;
+        }
+
+        let grace_period = Duration::from_secs(options.grace_period);
+        for signal in &[TabSignal::Hangup, TabSignal::Terminate] {
+            tx_pty.send(PtyRequest::Close(*signal)).await.ok();
+            time::delay_for(grace_period).await;
+        }
+
+        tx_pty.send(PtyRequest::Close(TabSignal::Kill)).await.ok();
+    }
+
     async fn output(
         mut rx: impl Receiver<PtyResponse>,
         mut tx: impl Sender<PtyWebsocketResponse>,
@@ -231,6 +285,15 @@ impl ClientSessionService {
                 PtyResponse::Output(out) => {
                     tx.send(PtyWebsocketResponse::Output(out)).await?;
                 }
+                PtyResponse::Process(process) => {
+                    tx.send(PtyWebsocketResponse::Process(process)).await?;
+                }
+                PtyResponse::Stats(stats) => {
+                    tx.send(PtyWebsocketResponse::Stats(stats)).await?;
+                }
+                PtyResponse::Closing(signal) => {
+                    tx.send(PtyWebsocketResponse::Closing(signal)).await?;
+                }
                 PtyResponse::Terminated(code) => {
                     debug!("pty child process terminated with status: {:?}", &code);
 
@@ -255,6 +318,52 @@ pub enum Shell {
     Unknown,
 }
 
+/// Moves the shell history of a renamed tab, so the history follows the tab
+fn migrate_history(shell: &str, from: &str, to: &str) -> anyhow::Result<()> {
+    let (from, to) = match resolve_shell(shell) {
+        Shell::Sh => (history_path("sh", from)?, history_path("sh", to)?),
+        Shell::Zsh => (history_path("zsh", from)?, history_path("zsh", to)?),
+        Shell::Bash => (history_path("bash", from)?, history_path("bash", to)?),
+        Shell::Fish => (fish_history_path(from)?, fish_history_path(to)?),
+        Shell::Unknown => return Ok(()),
+    };
+
+    if from.is_file() && !to.exists() {
+        debug!("moving history file {:?} to {:?}", &from, &to);
+        std::fs::rename(from, to)?;
+    }
+
+    Ok(())
+}
+
+/// The fish history file for the tab, which fish stores in its own data directory
+fn fish_history_path(name: &str) -> anyhow::Result<PathBuf> {
+    let mut path = match std::env::var("XDG_DATA_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => {
+            let mut home =
+                dirs::home_dir().ok_or_else(|| anyhow::format_err!("home dir not found"))?;
+            home.push(".local");
+            home.push("share");
+            home
+        }
+    };
+
+    path.push("fish");
+    path.push(format!("{}_history", fish_history(name)));
+
+    Ok(path)
+}
+
+/// Writes the new name of the tab, so shell integrations can update `$TAB`
+fn write_name(id: TabId, name: &str) -> anyhow::Result<()> {
+    let path = tab_name_path(id)?;
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(path, name)?;
+
+    Ok(())
+}
+
 pub fn resolve_shell(command: &str) -> Shell {
     for fragment in command.split(|c| c == '/' || c == ' ' || c == '\\') {
         let fragment = fragment.trim();
@@ -271,3 +380,48 @@ pub fn resolve_shell(command: &str) -> Shell {
 
     Shell::Unknown
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ClientSessionService;
+    use crate::message::pty::PtyRequest;
+    use tab_api::tab::{CloseOptions, TabSignal};
+    use tokio::sync::mpsc;
+
+    async fn close(options: CloseOptions) -> Vec<TabSignal> {
+        let (tx, mut rx) = mpsc::channel(4);
+        ClientSessionService::close(options, tx).await;
+
+        let mut signals = Vec::new();
+        while let Some(request) = rx.recv().await {
+            if let PtyRequest::Close(signal) = request {
+                signals.push(signal);
+            }
+        }
+
+        signals
+    }
+
+    #[tokio::test]
+    async fn close_escalates() {
+        let options = CloseOptions {
+            force: false,
+            grace_period: 0,
+        };
+
+        assert_eq!(
+            vec![TabSignal::Hangup, TabSignal::Terminate, TabSignal::Kill],
+            close(options).await
+        );
+    }
+
+    #[tokio::test]
+    async fn close_force() {
+        let options = CloseOptions {
+            force: true,
+            grace_period: 5,
+        };
+
+        assert_eq!(vec![TabSignal::Kill], close(options).await);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{message::pty::MainShutdown, prelude::*, state::session::SessionState};
+
+use lifeline::dyn_bus::DynBus;
+use tab_api::{
+    config::{is_running, load_daemon_file, DaemonConfig},
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+};
+use tab_websocket::{bus::WebsocketConnectionBus, resource::connection::WebsocketResource};
+use time::{Duration, Instant};
+use tokio::{select, time};
+
+static RECONNECT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Maintains the websocket connection to the daemon.
+///
+/// If the daemon terminates, the shell session is kept alive while polling for a new daemon.
+/// The running tab is then re-registered with the new daemon, and the session scrollback is replayed.
+pub struct ConnectionService {
+    _run: Lifeline,
+}
+
+impl Service for ConnectionService {
+    type Bus = MainBus;
+    type Lifeline = anyhow::Result<Self>;
+
+    fn spawn(bus: &Self::Bus) -> Self::Lifeline {
+        let config = bus.resource::<DaemonConfig>()?;
+        let rx_response = bus.rx::<PtyWebsocketResponse>()?;
+        let tx_request = bus.tx::<PtyWebsocketRequest>()?;
+        let tx_shutdown = bus.tx::<MainShutdown>()?;
+
+        let _run = Self::try_task(
+            "run",
+            Self::run(config, rx_response, tx_request, tx_shutdown),
+        );
+
+        Ok(Self { _run })
+    }
+}
+
+/// The reason a daemon connection ended
+enum Disconnect {
+    /// The websocket connection to the daemon was closed
+    Daemon,
+    /// The shell session has ended, and no more responses will be sent
+    Session,
+}
+
+impl ConnectionService {
+    async fn run(
+        config: DaemonConfig,
+        mut rx_response: impl Receiver<PtyWebsocketResponse>,
+        mut tx_request: impl Sender<PtyWebsocketRequest>,
+        mut tx_shutdown: impl Sender<MainShutdown>,
+    ) -> anyhow::Result<()> {
+        let mut session = SessionState::default();
+        let mut next_config = Some(config);
+
+        loop {
+            let config = match next_config.take() {
+                Some(config) => config,
+                None => match Self::await_daemon(&mut rx_response, &mut session).await {
+                    Some(config) => config,
+                    None => break,
+                },
+            };
+
+            let disconnect =
+                Self::connect(&config, &mut rx_response, &mut tx_request, &mut session).await;
+
+            match disconnect {
+                Ok(Disconnect::Session) => break,
+                Ok(Disconnect::Daemon) => {
+                    info!("daemon connection closed");
+                }
+                Err(e) => {
+                    warn!("daemon connection failed: {}", e);
+                }
+            }
+
+            if session.metadata.is_none() {
+                // no tab has been assigned, so there is nothing to preserve.
+                info!("PTY shutdown triggered by daemon disconnection");
+                tx_shutdown.send(MainShutdown {}).await.ok();
+                break;
+            }
+
+            info!("waiting for a new daemon to resume the tab session");
+        }
+
+        Ok(())
+    }
+
+    /// Connects to the daemon, and forwards messages until the websocket or the session is closed.
+    /// If a tab is running, re-registers it with the daemon, and replays the scrollback once acknowledged.
+    async fn connect(
+        config: &DaemonConfig,
+        rx_response: &mut impl Receiver<PtyWebsocketResponse>,
+        tx_request: &mut impl Sender<PtyWebsocketRequest>,
+        session: &mut SessionState,
+    ) -> anyhow::Result<Disconnect> {
+        let ws_url = format!("ws://127.0.0.1:{}/pty", config.port);
+        let websocket =
+            tab_websocket::connect_authorized(ws_url, config.auth_token.clone()).await?;
+
+        let bus = ConnectionBus::default();
+        bus.capacity::<PtyWebsocketRequest>(64)?;
+
+        let websocket_bus = WebsocketConnectionBus::default();
+        websocket_bus.store_resource(WebsocketResource(websocket));
+        let _carrier = websocket_bus.carry_from(&bus)?;
+
+        let mut rx_daemon = bus.rx::<PtyWebsocketRequest>()?;
+        let mut tx_daemon = bus.tx::<PtyWebsocketResponse>()?;
+
+        // the channels close when the websocket disconnects, as long as the buses are dropped.
+        drop(bus);
+        drop(websocket_bus);
+
+        tx_daemon
+            .send(PtyWebsocketResponse::Pid(std::process::id()))
+            .await?;
+
+        let mut resuming = false;
+        if let Some(ref metadata) = session.metadata {
+            info!("resuming tab {} with the daemon", metadata.name);
+            tx_daemon
+                .send(PtyWebsocketResponse::Resume(metadata.clone()))
+                .await?;
+            resuming = true;
+        }
+
+        loop {
+            select! {
+                request = rx_daemon.recv() => match request {
+                    Some(PtyWebsocketRequest::Init(metadata)) if resuming => {
+                        debug!("daemon acknowledged resumed tab {}", metadata.id);
+                        resuming = false;
+
+                        tx_daemon
+                            .send(PtyWebsocketResponse::Started(metadata.clone()))
+                            .await?;
+
+                        // output which was sent to the previous daemon is replayed, so it isn't logged twice
+                        for response in session.replay() {
+                            session.sent(&response);
+                            tx_daemon.send(response).await?;
+                        }
+
+                        session.metadata = Some(metadata);
+                    }
+                    Some(request) => {
+                        session.request(&request);
+                        tx_request.send(request).await?;
+                    }
+                    None => return Ok(Disconnect::Daemon),
+                },
+                response = rx_response.recv() => match response {
+                    Some(response) => {
+                        session.update(&response);
+
+                        // while resuming, output is retained in the session scrollback, and replayed after the ack
+                        if !resuming {
+                            session.sent(&response);
+                            tx_daemon.send(response).await.ok();
+                        }
+                    }
+                    None => return Ok(Disconnect::Session),
+                }
+            }
+        }
+    }
+
+    /// Polls the daemon file until a running daemon is found, retaining session output in the meantime.
+    /// Returns None if the session ends while waiting.
+    async fn await_daemon(
+        rx_response: &mut impl Receiver<PtyWebsocketResponse>,
+        session: &mut SessionState,
+    ) -> Option<DaemonConfig> {
+        let start = Instant::now() + RECONNECT_INTERVAL;
+        let mut interval = time::interval_at(start, RECONNECT_INTERVAL);
+
+        loop {
+            select! {
+                _ = interval.tick() => {
+                    match load_daemon_file() {
+                        Ok(Some(config)) if is_running(&config) => return Some(config),
+                        Ok(_) => {}
+                        Err(e) => debug!("failed to load daemon file: {}", e),
+                    }
+                },
+                response = rx_response.recv() => match response {
+                    Some(response) => session.update(&response),
+                    None => return None,
+                }
+            }
+        }
+    }
+}
@@ -6,6 +6,7 @@ use std::process::{Command, Stdio};
 use tab_api::{
     chunk::{InputChunk, OutputChunk},
     env::forward_env_std,
+    tab::{TabSignal, TabStats},
 };
 use tab_pty_process::CommandExt;
 use tab_pty_process::{
@@ -14,14 +15,17 @@ use tab_pty_process::{
 use time::Duration;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
-    time,
+    select, task, time,
 };
 
 static CHUNK_LEN: usize = 2048;
 static OUTPUT_CHANNEL_SIZE: usize = 32;
 static STDIN_CHANNEL_SIZE: usize = 32;
+static PROCESS_INTERVAL: Duration = Duration::from_secs(1);
+static STATS_INTERVAL: Duration = Duration::from_secs(2);
 
-// mod process;
+mod process;
+mod stats;
 // mod receiver;
 // mod sender;
 
@@ -61,9 +65,14 @@ impl PtyService {
         tx_response: impl Sender<PtyResponse> + Clone + Send + 'static,
     ) -> anyhow::Result<()> {
         let (child, read, write) = Self::create_pty(options).await?;
+        let shell = child.id();
         // stdout reader
         let _output = Self::task("output", Self::read_output(read, tx_response.clone()));
-        let _input = Self::task("input", Self::write_input(write, rx_request));
+        let _input = Self::task(
+            "input",
+            Self::write_input(write, shell, rx_request, tx_response.clone()),
+        );
+        let _stats = Self::task("stats", Self::sample_stats(shell, tx_response.clone()));
 
         let mut tx_exit = tx_response.clone();
 
@@ -133,8 +142,85 @@ impl PtyService {
         }
     }
 
-    async fn write_input(mut stdin: AsyncPtyMasterWriteHalf, mut rx: impl Receiver<PtyRequest>) {
-        while let Some(request) = rx.recv().await {
+    /// Samples the resource usage of the processes in the pty, and sends a response when it changes significantly.
+    /// Sampling reads the process table, so it runs on a blocking thread.
+    async fn sample_stats(shell: u32, mut tx: impl Sender<PtyResponse>) {
+        let mut sampler = stats::StatsSampler::new(shell);
+        let mut interval = time::interval(STATS_INTERVAL);
+        let mut last: Option<TabStats> = None;
+
+        loop {
+            interval.tick().await;
+
+            let sample = task::spawn_blocking(move || {
+                let stats = sampler.sample();
+                (sampler, stats)
+            })
+            .await;
+
+            let stats = match sample {
+                Ok((returned, Some(stats))) => {
+                    sampler = returned;
+                    stats
+                }
+                Ok((_, None)) => break,
+                Err(e) => {
+                    error!("stats sampling failed: {}", e);
+                    break;
+                }
+            };
+
+            if let Some(ref last) = last {
+                if !stats.is_significant_change(last) {
+                    continue;
+                }
+            }
+
+            last = Some(stats);
+            if tx.send(PtyResponse::Stats(stats)).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Writes input and resize requests to the pty.
+    /// Also polls the foreground process of the pty, and sends a response when it changes.
+    async fn write_input(
+        mut stdin: AsyncPtyMasterWriteHalf,
+        shell: u32,
+        mut rx: impl Receiver<PtyRequest>,
+        mut tx: impl Sender<PtyResponse>,
+    ) {
+        let mut interval = time::interval(PROCESS_INTERVAL);
+        let mut foreground = None;
+
+        loop {
+            let request = select! {
+                request = rx.recv() => request,
+                _ = interval.tick() => {
+                    let process = match stdin.foreground_process_group().await {
+                        Ok(pgrp) => task::spawn_blocking(move || process::read_process(pgrp))
+                            .await
+                            .unwrap_or(None),
+                        Err(_) => None,
+                    };
+
+                    if let Some(process) = process {
+                        if foreground.as_ref() != Some(&process) {
+                            foreground = Some(process.clone());
+                            tx.send(PtyResponse::Process(process)).await.ok();
+                        }
+                    }
+
+                    continue;
+                }
+            };
+
+            let request = match request {
+                Some(request) => request,
+                None => break,
+            };
+
             match request {
                 PtyRequest::Resize(dimensions) => {
                     if let Err(e) = stdin.resize(dimensions).await {
@@ -144,6 +230,25 @@ impl PtyService {
                     debug!("resized to dimensions: {:?}", &dimensions);
                 }
                 PtyRequest::Input(chunk) => Self::write_stdin(&mut stdin, chunk).await,
+                PtyRequest::Signal(signal) => {
+                    if let Err(e) = Self::signal(&stdin, signal).await {
+                        error!("failed to send {}: {:?}", signal, e);
+                    }
+                }
+                PtyRequest::Close(signal) => {
+                    info!("closing tab with {}", signal);
+
+                    // the shell may be in the background, behind an editor or a server
+                    if let Err(e) = Self::signal(&stdin, signal).await {
+                        debug!("failed to send {} to the foreground: {:?}", signal, e);
+                    }
+
+                    if let Err(e) = Self::killpg(shell, signal) {
+                        debug!("failed to send {} to the shell: {:?}", signal, e);
+                    }
+
+                    tx.send(PtyResponse::Closing(signal)).await.ok();
+                }
                 PtyRequest::Shutdown => {
                     debug!("terminating pty");
                     stdin.shutdown();
@@ -154,6 +259,37 @@ impl PtyService {
         debug!("stdin loop terminated");
     }
 
+    /// Delivers the signal to the foreground process group of the pty
+    async fn signal(stdin: &AsyncPtyMasterWriteHalf, signal: TabSignal) -> std::io::Result<()> {
+        let pgrp = stdin.foreground_process_group().await?;
+        Self::killpg(pgrp, signal)
+    }
+
+    /// Delivers the signal to the process group.
+    /// Group 0 is the caller's own group, and group 1 is init, so they are rejected.
+    fn killpg(pgrp: u32, signal: TabSignal) -> std::io::Result<()> {
+        if pgrp <= 1 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("refusing to signal process group {}", pgrp),
+            ));
+        }
+
+        let signal = match signal {
+            TabSignal::Hangup => libc::SIGHUP,
+            TabSignal::Interrupt => libc::SIGINT,
+            TabSignal::Terminate => libc::SIGTERM,
+            TabSignal::Kill => libc::SIGKILL,
+        };
+
+        debug!("sending signal {} to process group {}", signal, pgrp);
+        if unsafe { libc::killpg(pgrp as libc::pid_t, signal) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
     async fn write_stdin(mut stdin: impl AsyncWriteExt + Unpin, mut chunk: InputChunk) {
         debug!("writing stdin chunk: {:?}", &chunk);
 
@@ -166,3 +302,27 @@ impl PtyService {
         stdin.flush().await.expect("stdin flush failed");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::PtyService;
+    use tab_api::tab::TabSignal;
+
+    #[test]
+    fn killpg_rejects_own_group() {
+        let result = PtyService::killpg(0, TabSignal::Kill);
+        assert_eq!(
+            Some(std::io::ErrorKind::InvalidInput),
+            result.err().map(|e| e.kind())
+        );
+    }
+
+    #[test]
+    fn killpg_rejects_init() {
+        let result = PtyService::killpg(1, TabSignal::Kill);
+        assert_eq!(
+            Some(std::io::ErrorKind::InvalidInput),
+            result.err().map(|e| e.kind())
+        );
+    }
+}
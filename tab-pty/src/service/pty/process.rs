@@ -0,0 +1,42 @@
+//! Inspects the foreground process of the pty, using the `/proc` filesystem.
+
+use std::path::Path;
+use tab_api::tab::TabProcess;
+
+/// Reads the command name and working directory of the process.
+/// Returns None if the process has exited, or if `/proc` is not available on this platform.
+pub fn read_process(pid: u32) -> Option<TabProcess> {
+    read_process_in(Path::new("/proc"), pid)
+}
+
+fn read_process_in(proc: &Path, pid: u32) -> Option<TabProcess> {
+    let dir = proc.join(pid.to_string());
+
+    let name = std::fs::read_to_string(dir.join("comm")).ok()?;
+    let cwd = std::fs::read_link(dir.join("cwd")).ok()?;
+
+    Some(TabProcess {
+        name: name.trim().to_string(),
+        cwd: cwd.to_string_lossy().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_process;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn current_process() {
+        let process = read_process(std::process::id()).expect("process should be found");
+        let cwd = std::env::current_dir().unwrap();
+
+        assert!(!process.name.is_empty());
+        assert_eq!(cwd.to_string_lossy(), process.cwd);
+    }
+
+    #[test]
+    fn missing_process() {
+        assert_eq!(None, read_process(u32::MAX));
+    }
+}
@@ -0,0 +1,127 @@
+//! Samples the resource usage of the processes in the pty, using sysinfo.
+
+use sysinfo::{Pid, ProcessExt, RefreshKind, System, SystemExt};
+use tab_api::tab::TabStats;
+
+/// Samples the resource usage of the shell, and all of its descendants.
+/// CPU usage is measured between samples, so the first sample reports zero.
+pub struct StatsSampler {
+    system: System,
+    shell: Pid,
+}
+
+impl StatsSampler {
+    pub fn new(shell: u32) -> Self {
+        Self {
+            system: System::new_with_specifics(RefreshKind::new()),
+            shell: shell as Pid,
+        }
+    }
+
+    /// Refreshes the process table, and returns the usage of the process tree.
+    /// Returns None if the shell has exited.
+    pub fn sample(&mut self) -> Option<TabStats> {
+        self.system.refresh_processes();
+
+        let samples: Vec<Sample> = self
+            .system
+            .get_processes()
+            .values()
+            .map(|process| Sample {
+                pid: process.pid(),
+                parent: process.parent(),
+                cpu: process.cpu_usage(),
+                memory: process.memory(),
+            })
+            .collect();
+
+        tree_stats(samples.as_slice(), self.shell)
+    }
+}
+
+/// The usage of a single process
+struct Sample {
+    pid: Pid,
+    parent: Option<Pid>,
+    cpu: f32,
+    memory: u64,
+}
+
+/// Sums the usage of the root process and its descendants
+fn tree_stats(samples: &[Sample], root: Pid) -> Option<TabStats> {
+    if !samples.iter().any(|sample| sample.pid == root) {
+        return None;
+    }
+
+    let mut tree = vec![root];
+    let mut cpu = 0.0;
+    let mut stats = TabStats::default();
+
+    let mut index = 0;
+    while index < tree.len() {
+        let pid = tree[index];
+        index += 1;
+
+        for sample in samples.iter() {
+            if sample.pid == pid {
+                cpu += sample.cpu;
+                stats.memory += sample.memory;
+                stats.processes += 1;
+            } else if sample.parent == Some(pid) {
+                tree.push(sample.pid);
+            }
+        }
+    }
+
+    stats.cpu = cpu.round() as u32;
+    Some(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tree_stats, Sample, StatsSampler};
+    use tab_api::tab::TabStats;
+
+    fn sample(pid: i32, parent: Option<i32>, cpu: f32, memory: u64) -> Sample {
+        Sample {
+            pid,
+            parent,
+            cpu,
+            memory,
+        }
+    }
+
+    #[test]
+    fn sums_the_process_tree() {
+        let samples = vec![
+            sample(1, None, 50.0, 1000),
+            sample(10, Some(1), 1.2, 100),
+            sample(11, Some(10), 20.4, 2000),
+            sample(12, Some(11), 0.0, 30),
+            sample(20, Some(1), 5.0, 50),
+        ];
+
+        let expected = TabStats {
+            cpu: 22,
+            memory: 2130,
+            processes: 3,
+        };
+        assert_eq!(Some(expected), tree_stats(samples.as_slice(), 10));
+    }
+
+    #[test]
+    fn missing_root() {
+        let samples = vec![sample(1, None, 50.0, 1000)];
+        assert_eq!(None, tree_stats(samples.as_slice(), 10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn current_process() {
+        let mut sampler = StatsSampler::new(std::process::id());
+        let stats = sampler.sample().expect("process should be found");
+
+        assert!(stats.memory > 0);
+        assert!(stats.processes >= 1);
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::VecDeque;
+use tab_api::{
+    chunk::OutputChunk,
+    pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+    tab::TabMetadata,
+};
+
+static MIN_CAPACITY: usize = 32768;
+
+/// The tab session running in this pty process, retained across daemon connections.
+/// If the daemon restarts, the session is used to re-register the tab and replay scrollback.
+#[derive(Debug, Clone, Default)]
+pub struct SessionState {
+    /// The metadata of the running tab, if the shell has started.
+    pub metadata: Option<TabMetadata>,
+    /// Output chunks which have been generated by the shell, bounded by a minimum capacity in bytes.
+    pub scrollback: SessionScrollback,
+    /// The index of the last output chunk which was sent to a daemon
+    pub sent: Option<usize>,
+}
+
+impl SessionState {
+    /// Updates the session with a request from the daemon, which is being sent to the shell.
+    pub fn request(&mut self, request: &PtyWebsocketRequest) {
+        match request {
+            PtyWebsocketRequest::Rename(name) => {
+                // the name is retained, so a restarted daemon resumes the tab with the new name
+                if let Some(ref mut metadata) = self.metadata {
+                    metadata.name = name.clone();
+                }
+            }
+            PtyWebsocketRequest::Log(path) => {
+                // the log is retained, so a restarted daemon continues the log
+                if let Some(ref mut metadata) = self.metadata {
+                    metadata.log = Some(path.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the session with a response from the shell, which is being sent to the daemon.
+    pub fn update(&mut self, response: &PtyWebsocketResponse) {
+        match response {
+            PtyWebsocketResponse::Started(metadata) => {
+                self.metadata = Some(metadata.clone());
+            }
+            PtyWebsocketResponse::Output(chunk) => {
+                self.scrollback.push(chunk.clone());
+            }
+            PtyWebsocketResponse::Process(process) => {
+                // the process is retained, so a restarted daemon receives the latest process and working directory
+                if let Some(ref mut metadata) = self.metadata {
+                    metadata.process = Some(process.clone());
+                }
+            }
+            PtyWebsocketResponse::Stats(stats) => {
+                if let Some(ref mut metadata) = self.metadata {
+                    metadata.stats = Some(Box::new(*stats));
+                }
+            }
+            PtyWebsocketResponse::Stopped => {
+                self.metadata = None;
+            }
+            PtyWebsocketResponse::Closing(_) => {}
+            PtyWebsocketResponse::Resume(_) => {}
+            PtyWebsocketResponse::Pid(_) => {}
+            PtyWebsocketResponse::Replay(_) => {}
+        }
+    }
+
+    /// Records that the response has been sent to the daemon
+    pub fn sent(&mut self, response: &PtyWebsocketResponse) {
+        if let PtyWebsocketResponse::Output(chunk) = response {
+            self.sent = Some(chunk.index);
+        }
+    }
+
+    /// The scrollback to replay to a new daemon.  Chunks which were sent to a previous daemon are replayed,
+    /// and chunks which were generated while the daemon was unavailable are sent as output.
+    pub fn replay(&self) -> Vec<PtyWebsocketResponse> {
+        self.scrollback
+            .iter()
+            .map(|chunk| match self.sent {
+                Some(sent) if chunk.index <= sent => PtyWebsocketResponse::Replay(chunk.clone()),
+                _ => PtyWebsocketResponse::Output(chunk.clone()),
+            })
+            .collect()
+    }
+}
+
+/// A bounded buffer of shell output, which can be replayed to a new daemon.
+#[derive(Debug, Clone, Default)]
+pub struct SessionScrollback {
+    size: usize,
+    queue: VecDeque<OutputChunk>,
+}
+
+impl SessionScrollback {
+    pub fn push(&mut self, chunk: OutputChunk) {
+        self.size += chunk.len();
+        self.queue.push_back(chunk);
+
+        while let Some(front_len) = self.queue.front().map(OutputChunk::len) {
+            if self.size - front_len < MIN_CAPACITY {
+                break;
+            }
+
+            self.size -= front_len;
+            self.queue.pop_front();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OutputChunk> {
+        self.queue.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionScrollback, SessionState, MIN_CAPACITY};
+    use tab_api::{
+        chunk::OutputChunk,
+        pty::{PtyWebsocketRequest, PtyWebsocketResponse},
+        tab::{TabId, TabMetadata},
+    };
+
+    #[test]
+    fn rename() {
+        let mut session = SessionState::default();
+        session.update(&PtyWebsocketResponse::Started(TabMetadata {
+            id: TabId(0),
+            name: "old/".into(),
+            dimensions: (1, 2),
+            shell: "bash".into(),
+            dir: "/".into(),
+            log: None,
+            hooks: Default::default(),
+            tags: Vec::new(),
+            idle: Default::default(),
+            process: None,
+            stats: None,
+        }));
+
+        session.request(&PtyWebsocketRequest::Rename("new/".into()));
+        session.request(&PtyWebsocketRequest::Log("/tab.log".into()));
+
+        let metadata = session.metadata.unwrap();
+        assert_eq!("new/", metadata.name);
+        assert_eq!(Some("/tab.log".to_string()), metadata.log);
+    }
+
+    #[test]
+    fn replay() {
+        let chunk = |index: usize| OutputChunk {
+            index,
+            data: vec![0],
+        };
+
+        let mut session = SessionState::default();
+        for index in 0..2 {
+            let response = PtyWebsocketResponse::Output(chunk(index));
+            session.update(&response);
+            session.sent(&response);
+        }
+
+        // output retained while the daemon was unavailable
+        session.update(&PtyWebsocketResponse::Output(chunk(2)));
+
+        assert_eq!(
+            vec![
+                PtyWebsocketResponse::Replay(chunk(0)),
+                PtyWebsocketResponse::Replay(chunk(1)),
+                PtyWebsocketResponse::Output(chunk(2)),
+            ],
+            session.replay()
+        );
+    }
+
+    #[test]
+    fn push() {
+        let mut scrollback = SessionScrollback::default();
+        scrollback.push(OutputChunk {
+            index: 0,
+            data: vec![1, 2],
+        });
+        scrollback.push(OutputChunk {
+            index: 2,
+            data: vec![3],
+        });
+
+        let indices: Vec<usize> = scrollback.iter().map(|chunk| chunk.index).collect();
+        assert_eq!(vec![0, 2], indices);
+    }
+
+    #[test]
+    fn push_evicts_oldest() {
+        let mut scrollback = SessionScrollback::default();
+        scrollback.push(OutputChunk {
+            index: 0,
+            data: vec![0; 16],
+        });
+        scrollback.push(OutputChunk {
+            index: 16,
+            data: vec![0; MIN_CAPACITY],
+        });
+
+        let indices: Vec<usize> = scrollback.iter().map(|chunk| chunk.index).collect();
+        assert_eq!(vec![16], indices);
+    }
+
+    #[test]
+    fn push_retains_capacity() {
+        let mut scrollback = SessionScrollback::default();
+        scrollback.push(OutputChunk {
+            index: 0,
+            data: vec![0; 16],
+        });
+        scrollback.push(OutputChunk {
+            index: 16,
+            data: vec![0; MIN_CAPACITY - 16],
+        });
+
+        assert_eq!(2, scrollback.iter().count());
+    }
+}
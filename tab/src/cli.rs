@@ -6,7 +6,7 @@ pub fn init() -> ArgMatches<'static> {
     app().get_matches()
 }
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
 fn app() -> App<'static, 'static> {
     App::new("Terminal Multiplexer")
@@ -39,6 +39,13 @@ fn app() -> App<'static, 'static> {
                 .takes_value(false)
                 .help("Autocompletes for the `tab -w <CLOSE>` command"),
         )
+        .arg(
+            Arg::with_name("AUTOCOMPLETE-LAYOUT")
+                .long("_autocomplete_layout")
+                .hidden(true)
+                .takes_value(false)
+                .help("Autocompletes for the `tab --up <LAYOUT>` command"),
+        )
         .arg(
             Arg::with_name("HISTFILE-SHELL")
                 .long("_histfile")
@@ -46,12 +53,66 @@ fn app() -> App<'static, 'static> {
                 .takes_value(true)
                 .help("Generates a histfile for the given shell, and the tab in argument 1"),
         )
+        .arg(
+            Arg::with_name("RENAME-ENV")
+                .long("_rename_env")
+                .hidden(true)
+                .takes_value(true)
+                .possible_values(&["bash", "fish", "sh", "zsh"])
+                .help("Prints commands which update $TAB in the given shell, if the current tab has been renamed"),
+        )
         .arg(
             Arg::with_name("STARSHIP")
                 .long("starship")
                 .takes_value(false)
                 .hidden(true)
         )
+        .arg(
+            Arg::with_name("ACTIVITY")
+                .long("activity")
+                .takes_value(false)
+                .hidden(true)
+        )
+        .arg(
+            Arg::with_name("CHECK-CONFIG")
+                .long("check-config")
+                .takes_value(false)
+                .help("Checks the tab.yml configurations for the current directory, and reports any problems")
+        )
+        .arg(
+            Arg::with_name("PRINT-SCHEMA")
+                .long("print-schema")
+                .takes_value(false)
+                .help("Prints the JSON Schema for tab.yml configurations, for editor autocompletion & validation")
+        )
+        .arg(
+            Arg::with_name("STATUS")
+                .long("status")
+                .takes_value(false)
+                .help("Prints the state of the daemon, including connections, pty assignments, and channel capacities")
+        )
+        .arg(
+            Arg::with_name("LOGS")
+                .long("logs")
+                .takes_value(true)
+                .min_values(0)
+                .max_values(1)
+                .value_name("daemon|TAB")
+                .help("Prints the daemon log, or the pty log of a running tab.  Defaults to the daemon log")
+        )
+        .arg(
+            Arg::with_name("FOLLOW")
+                .long("follow")
+                .takes_value(false)
+                .requires("LOGS")
+                .help("With --logs, prints new log records as they are written")
+        )
+        .arg(
+            Arg::with_name("DOCTOR")
+                .long("doctor")
+                .takes_value(false)
+                .help("Checks for a stale or outdated daemon, an unwritable data directory, and broken shell history")
+        )
         .arg(
             Arg::with_name("INSTALL")
                 .long("install")
@@ -69,12 +130,36 @@ fn app() -> App<'static, 'static> {
                 .possible_values(&["trace", "debug", "info", "warn", "error", "off"])
                 .help("Sets the log level.  Transitive to the daemon & pty, if launched."),
         )
+        .arg(
+            Arg::with_name("RECENT")
+                .long("recent")
+                .takes_value(false)
+                .requires("LIST")
+                .help("With --list, orders the tabs by the most recent selection")
+        )
+        .arg(
+            Arg::with_name("STATS")
+                .long("stats")
+                .takes_value(false)
+                .requires("LIST")
+                .help("With --list, shows the CPU usage, memory, and process count of the running tabs")
+        )
+        .arg(
+            Arg::with_name("TOP")
+                .long("top")
+                .takes_value(false)
+                .help("Displays a live view of the running tabs, ordered by memory usage")
+        )
         .arg(
             Arg::with_name("LIST")
                 .short("l")
                 .long("list")
+                .takes_value(true)
+                .min_values(0)
+                .multiple(true)
+                .value_name("TABS")
                 .display_order(0)
-                .help("Lists the active tabs"),
+                .help("Lists the active tabs.  Accepts tab names, prefixes like `proj/*`, or tags like `@backend`"),
         )
         .arg(
             Arg::with_name("SHUTDOWN")
@@ -91,8 +176,109 @@ fn app() -> App<'static, 'static> {
                 .takes_value(true)
                 .multiple(true)
                 .value_name("TABS")
+                .validator(validate_tab_selector)
+                .help("Closes the tabs with the given names, prefixes like `proj/*`, or tags like `@backend`.  Sends SIGHUP, then SIGTERM and SIGKILL after a grace period")
+        )
+        .arg(
+            Arg::with_name("FORCE")
+                .long("force")
+                .takes_value(false)
+                .requires("CLOSE-TAB")
+                .help("With --close, kills the tabs immediately with SIGKILL")
+        )
+        .arg(
+            Arg::with_name("GRACE")
+                .long("grace")
+                .takes_value(true)
+                .value_name("SECS")
+                .conflicts_with("FORCE")
+                .validator(validate_grace_period)
+                .help("With --close, --down, or --prune, the number of seconds to wait before escalating signals.  Defaults to `TAB_CLOSE_GRACE_SECS`, or 5")
+        )
+        .arg(
+            Arg::with_name("UP")
+                .long("up")
+                .takes_value(true)
+                .value_name("LAYOUT")
+                .conflicts_with("DOWN")
+                .help("Starts the tabs in a tab.yml layout, and runs their commands.  Switches to the TAB argument, if provided")
+        )
+        .arg(
+            Arg::with_name("DOWN")
+                .long("down")
+                .takes_value(true)
+                .value_name("LAYOUT")
+                .help("Closes the tabs in a tab.yml layout")
+        )
+        .arg(
+            Arg::with_name("PRUNE")
+                .long("prune")
+                .takes_value(false)
+                .help("Closes the tabs which have been marked stale by their idle timeout")
+        )
+        .arg(
+            Arg::with_name("RECORD")
+                .long("record")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["TAB", "FILE"])
+                .help("Records the tab session to a file, in asciicast v2 format")
+        )
+        .arg(
+            Arg::with_name("SEND")
+                .long("send")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["TABS", "TEXT"])
+                .help("Types the line of text into the tabs, without attaching.  Accepts a tab name, a prefix like `proj/*`, or a tag like `@backend`")
+        )
+        .arg(
+            Arg::with_name("RENAME")
+                .long("rename")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["TAB", "NAME"])
                 .validator(validate_tab_name)
-                .help("Closes the tabs with the given names")
+                .help("Renames a running tab, and moves its shell history")
+        )
+        .arg(
+            Arg::with_name("LOG-OUTPUT")
+                .long("log-output")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["TAB", "FILE"])
+                .help("Appends the tab output to a log file, with ANSI escape sequences removed")
+        )
+        .arg(
+            Arg::with_name("REPLAY")
+                .long("replay")
+                .takes_value(true)
+                .value_name("FILE")
+                .help("Replays a recorded tab session")
+        )
+        .arg(
+            Arg::with_name("GREP")
+                .long("grep")
+                .takes_value(true)
+                .value_name("REGEX")
+                .help("Searches the scrollback of running tabs, and prints matching lines")
+        )
+        .arg(
+            Arg::with_name("INTERACTIVE")
+                .short("i")
+                .long("interactive")
+                .requires("GREP")
+                .help("With --grep, prompts for a match, and switches to the tab")
+        )
+        .arg(
+            Arg::with_name("SIGNAL")
+                .long("signal")
+                .takes_value(true)
+                .value_name("SIGNAL")
+                .possible_values(&["HUP", "INT", "TERM"])
+                .case_insensitive(true)
+                .requires("TAB-NAME")
+                .help("Sends the signal to the foreground process of the tab, without attaching")
         )
         .arg(
             Arg::with_name("COMPLETION")
@@ -104,20 +290,55 @@ fn app() -> App<'static, 'static> {
         )
         .arg(
             Arg::with_name("TAB-NAME")
-                .help("Switches to the provided tab")
+                .help("Switches to the provided tab, or selects the tab for --signal and --up.  `-` selects the previous tab")
                 .required(false)
                 .value_name("TAB")
-                .conflicts_with_all(&["CLOSE-TAB", "LIST", "SHUTDOWN"])
-                .validator(validate_tab_name)
+                .conflicts_with_all(&["CLOSE-TAB", "LIST", "SHUTDOWN", "RECORD", "RENAME", "SEND", "LOG-OUTPUT", "REPLAY", "GREP", "DOWN", "PRUNE", "TOP", "STATUS", "LOGS", "DOCTOR"])
+                .validator(validate_select_tab)
                 .index(1),
         )
 }
 
+/// Accepts tab names, and `-`, which selects the previous tab
+fn validate_select_tab(name: String) -> Result<(), String> {
+    if name == "-" {
+        return Ok(());
+    }
+
+    validate_tab_name(name)
+}
+
+/// Accepts tab names, prefixes like `proj/*`, and tags like `@backend`
+fn validate_grace_period(secs: String) -> Result<(), String> {
+    secs.parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| "grace period must be a number of seconds".into())
+}
+
+fn validate_tab_selector(selector: String) -> Result<(), String> {
+    let selector = selector.trim();
+    let name = match selector.strip_prefix('@') {
+        Some(tag) => tag,
+        None => selector.strip_suffix('*').unwrap_or(selector),
+    };
+
+    validate_tab_name(name.to_string())
+}
+
 fn validate_tab_name(name: String) -> Result<(), String> {
     if name.starts_with('-') {
         return Err("tab name may not begin with a dash".into());
     }
 
+    // names like these would select a group of tabs, in `tab --close`
+    if name.starts_with('@') {
+        return Err("tab name may not begin with @, which selects tabs by tag".into());
+    }
+
+    if name.ends_with('*') {
+        return Err("tab name may not end with *, which selects tabs by prefix".into());
+    }
+
     if name.contains(' ') || name.contains('\t') {
         return Err("tab name may not contain whitespace".into());
     }
@@ -2,4 +2,17 @@
 # installation: 
 # - append the snippet to ~/.bashrc
 
-PS1="tab ${TAB:-/} ${PWD##*/} $ "
\ No newline at end of file
+# background tabs with unread output are listed after the directory (bell: name!, unread: name*)
+
+# $TAB is updated at the next prompt when the tab is renamed with `tab --rename`
+PROMPT_COMMAND='eval "$(tab --_rename_env bash)"'"${PROMPT_COMMAND:+; $PROMPT_COMMAND}"
+
+# the activity is followed by a space, only if there is any
+_tab_activity() {
+  local activity="$(tab --activity)"
+  if [ -n "$activity" ]; then
+    printf '%s ' "$activity"
+  fi
+}
+
+PS1='tab ${TAB:-/} ${PWD##*/} $(_tab_activity)$ '
\ No newline at end of file
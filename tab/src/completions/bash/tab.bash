@@ -19,11 +19,37 @@ _tab() {
         COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
         return 0
         ;;
-    --close)
+    --close|--rename)
         TABS=$(tab --_autocomplete_close_tab)
         COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
         return 0
         ;;
+    --record|--log-output)
+        TABS=$(tab --_autocomplete_tab)
+        COMPREPLY=( $(compgen -W "${TABS}" -- $cur) )
+        return 0
+        ;;
+    --up|--down)
+        LAYOUTS=$(tab --_autocomplete_layout)
+        COMPREPLY=( $(compgen -W "${LAYOUTS}" -- $cur) )
+        return 0
+        ;;
+    --logs)
+        TABS=$(tab --_autocomplete_close_tab)
+        COMPREPLY=( $(compgen -W "daemon ${TABS}" -- $cur) )
+        return 0
+        ;;
+    --grep)
+        return 0
+        ;;
+    --signal)
+        COMPREPLY=( $(compgen -W "HUP INT TERM" -- $cur) )
+        return 0
+        ;;
+    --replay)
+        COMPREPLY=( $(compgen -f -- $cur) )
+        return 0
+        ;;
     --completion)
         COMPREPLY=( $(compgen -W "bash elvish fish powershell zsh") )
         return 0
@@ -37,7 +63,7 @@ _tab() {
         return 0
         ;;
     -*)
-        opts=" -h --help -l --list -w --close -W --shutdown -V --version --completion <TAB> "
+        opts=" -h --help -l --list --recent --stats --top -w --close --force -W --shutdown --record --rename --log-output --replay --grep -i --interactive --signal --up --down --prune -V --version --completion --check-config --print-schema --status --logs --follow --doctor <TAB> "
         COMPREPLY=( $(compgen -W "${opts}") )
         return 0
         ;;
@@ -49,4 +75,5 @@ _tab() {
     esac
 }
 
-complete -F _tab tab
+# keeps the most recently selected tabs first, where supported
+complete -o nosort -F _tab tab 2>/dev/null || complete -F _tab tab
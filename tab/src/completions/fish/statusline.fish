@@ -2,7 +2,17 @@
 # installation: 
 # - append the snippet to ~/.config/fish/config.fish
 
+# background tabs with unread output are listed after the directory (bell: name!, unread: name*)
+
 function fish_prompt
+  # $TAB is updated when the tab is renamed with `tab --rename`
+  tab --_rename_env fish | source
+
+  set -l activity (tab --activity)
+  if test -n "$activity"
+    set activity "$activity "
+  end
+
   if test -n "$TAB"
     set_color $fish_color_cwd
     printf 'tab %s' "$TAB" 
@@ -11,11 +21,11 @@ function fish_prompt
     set_color $fish_color_cwd
     printf '%s' (basename $PWD)
     set_color normal
-    echo " \$ "
+    echo " $activity\$ "
   else
     set_color $fish_color_cwd
     printf '%s' (basename $PWD)
     set_color normal
-    echo " \$ "
+    echo " $activity\$ "
   end
 end
\ No newline at end of file
@@ -1,12 +1,32 @@
 # `tab` autocompletions for fish
 
-complete -c tab  -f -a '(tab --_autocomplete_tab)' 
+complete -c tab -k -f -a '(tab --_autocomplete_tab)' 
 # hack here with `-o w`, to get fish to insert a space after the `tab -w` completion
 complete -c tab -n "__fish_use_subcommand" -o w -l close -d 'closes the tab with the given name' -x -a '(tab --_autocomplete_close_tab)'
 
 complete -c tab -l completion -d 'prints raw autocomplete scripts' -x -a 'bash elvish fish powershell zsh'
+complete -c tab -l check-config -d 'checks the tab.yml configurations, and reports any problems'
+complete -c tab -n "__fish_use_subcommand" -l status -d 'prints the state of the daemon'
+complete -c tab -n "__fish_use_subcommand" -l logs -d 'prints the daemon log, or the pty log of a tab' -x -a 'daemon (tab --_autocomplete_close_tab)'
+complete -c tab -l follow -d 'with --logs, prints new log records as they are written'
+complete -c tab -n "__fish_use_subcommand" -l doctor -d 'checks the daemon, data directory, and shell history for problems'
+complete -c tab -l print-schema -d 'prints the JSON Schema for tab.yml configurations'
+complete -c tab -l force -d 'with --close, kills the tab immediately'
 complete -c tab -n "__fish_use_subcommand" -s l -l list -d 'lists the active tabs'
+complete -c tab -l recent -d 'with --list, orders the tabs by the most recent selection'
+complete -c tab -l stats -d 'with --list, shows the resource usage of the running tabs'
+complete -c tab -n "__fish_use_subcommand" -l top -d 'displays a live view of the running tabs, ordered by memory usage'
 complete -c tab -n "__fish_use_subcommand" -s W -l shutdown -d 'terminates the tab daemon and all active pty sessions'
+complete -c tab -n "__fish_use_subcommand" -l record -d 'records the tab session to a file' -x -a '(tab --_autocomplete_tab)'
+complete -c tab -n "__fish_use_subcommand" -l rename -d 'renames a running tab' -x -a '(tab --_autocomplete_close_tab)'
+complete -c tab -n "__fish_use_subcommand" -l log-output -d 'appends the tab output to a log file' -x -a '(tab --_autocomplete_tab)'
+complete -c tab -n "__fish_use_subcommand" -l grep -d 'searches the scrollback of running tabs' -x
+complete -c tab -n "__fish_use_subcommand" -s i -l interactive -d 'with --grep, prompts for a match and switches to the tab'
+complete -c tab -n "__fish_use_subcommand" -l replay -d 'replays a recorded tab session' -r
+complete -c tab -n "__fish_use_subcommand" -l signal -d 'sends the signal to the foreground process of the tab' -x -a 'HUP INT TERM'
+complete -c tab -n "__fish_use_subcommand" -l up -d 'starts the tabs in a layout' -x -a '(tab --_autocomplete_layout)'
+complete -c tab -n "__fish_use_subcommand" -l down -d 'closes the tabs in a layout' -x -a '(tab --_autocomplete_layout)'
+complete -c tab -n "__fish_use_subcommand" -l prune -d 'closes the tabs which are marked stale'
 complete -c tab -n "__fish_use_subcommand" -s h -l help -d 'Prints help information'
 complete -c tab -n "__fish_use_subcommand" -s V -l version -d 'Prints version information'
 
@@ -0,0 +1,379 @@
+//! Diagnoses problems with the tab installation, for `tab --doctor`.
+//!
+//! The checks run locally, without launching the daemon, so they work when the daemon is stale or unresponsive.
+
+use std::path::{Path, PathBuf};
+use tab_api::config::{
+    daemon_file, data_path, history_path, is_running, load_daemon_file, DaemonConfig,
+};
+
+enum Diagnosis {
+    Ok(String),
+    Problem(String),
+}
+
+/// Runs the checks, and prints the results.  Returns true if no problems were found.
+pub fn run(version: &str) -> bool {
+    let mut diagnoses = check_daemon(version);
+    diagnoses.push(check_data_path());
+
+    for shell in ["bash", "zsh", "fish"].iter() {
+        if which::which(shell).is_ok() {
+            diagnoses.push(check_history(shell));
+        }
+    }
+
+    let mut problems = 0;
+    for diagnosis in diagnoses.iter() {
+        match diagnosis {
+            Diagnosis::Ok(message) => println!("ok       {}", message),
+            Diagnosis::Problem(message) => {
+                println!("problem  {}", message);
+                problems += 1;
+            }
+        }
+    }
+
+    match problems {
+        0 => {
+            println!("No problems found.");
+            true
+        }
+        1 => {
+            println!("Found 1 problem.");
+            false
+        }
+        problems => {
+            println!("Found {} problems.", problems);
+            false
+        }
+    }
+}
+
+/// Checks for a stale daemon file, and a daemon launched by another version of tab
+fn check_daemon(version: &str) -> Vec<Diagnosis> {
+    let path = daemon_file()
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "daemon-pid.yml".to_string());
+
+    let config = match load_daemon_file() {
+        Ok(Some(config)) => config,
+        Ok(None) => return vec![Diagnosis::Ok("the daemon is not running".to_string())],
+        Err(err) => {
+            return vec![Diagnosis::Problem(format!(
+                "the daemon file {} could not be read: {}.  Remove it, and run `tab` to launch a new daemon",
+                path, err
+            ))]
+        }
+    };
+
+    diagnose_daemon(path.as_str(), &config, version)
+}
+
+/// Checks that the daemon in the daemon file is running, and was launched by this version of tab
+fn diagnose_daemon(path: &str, config: &DaemonConfig, version: &str) -> Vec<Diagnosis> {
+    if !is_running(config) {
+        return vec![Diagnosis::Problem(format!(
+            "the daemon file {} is stale, as pid {} is not running.  Remove it, and run `tab` to launch a new daemon",
+            path, config.pid
+        ))];
+    }
+
+    let running = Diagnosis::Ok(format!(
+        "the daemon is running with pid {}, on port {}",
+        config.pid, config.port
+    ));
+
+    let version = if config.version.is_empty() {
+        Diagnosis::Problem(format!(
+            "the daemon was launched by an older version of tab.  Run `tab --shutdown` to restart it with version {}, which closes the running tabs",
+            version
+        ))
+    } else if config.version != version {
+        Diagnosis::Problem(format!(
+            "the daemon is running version {}, but tab is version {}.  Run `tab --shutdown` to restart it, which closes the running tabs",
+            config.version, version
+        ))
+    } else {
+        Diagnosis::Ok(format!("the daemon version matches tab {}", version))
+    };
+
+    vec![running, version]
+}
+
+/// Checks that the data directory, which contains the daemon file, logs, and history, is writable
+fn check_data_path() -> Diagnosis {
+    let path = match data_path() {
+        Ok(path) => path,
+        Err(err) => {
+            return Diagnosis::Problem(format!("the data directory could not be found: {}", err))
+        }
+    };
+
+    match check_writable(path.as_path()) {
+        Ok(()) => Diagnosis::Ok(format!(
+            "the data directory {} is writable",
+            path.to_string_lossy()
+        )),
+        Err(err) => Diagnosis::Problem(format!(
+            "the data directory {} is not writable: {}.  Set TAB_RUNTIME_DIR to use another directory",
+            path.to_string_lossy(),
+            err
+        )),
+    }
+}
+
+/// Checks that the history directory is writable, and that the shell configuration doesn't replace the history file of each tab
+fn check_history(shell: &str) -> Diagnosis {
+    if shell != "fish" {
+        let dir = history_path(shell, "doctor")
+            .ok()
+            .and_then(|path| path.parent().map(Path::to_path_buf));
+
+        if let Some(dir) = dir {
+            if let Err(err) = check_writable(dir.as_path()) {
+                return Diagnosis::Problem(format!(
+                    "{} history is broken, as {} is not writable: {}",
+                    shell,
+                    dir.to_string_lossy(),
+                    err
+                ));
+            }
+        }
+    }
+
+    check_history_overrides(shell, rc_files(shell).as_slice())
+}
+
+/// Checks that the configuration files don't set the history file, or restore the tab history when they do
+fn check_history_overrides(shell: &str, files: &[PathBuf]) -> Diagnosis {
+    let variable = if shell == "fish" {
+        "fish_history"
+    } else {
+        "HISTFILE"
+    };
+
+    let mut overrides = Vec::new();
+    let mut restored = false;
+    for file in files {
+        let contents = match std::fs::read_to_string(file) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if sets_history(shell, line) {
+                overrides.push(format!("{}:{}", file.to_string_lossy(), index + 1));
+            }
+
+            if line.contains("--_histfile") || line.contains("zsh-history.zsh") {
+                restored = true;
+            }
+        }
+
+        // a guard such as `if [ -z "$TAB" ]` prevents the override within tabs
+        if contents.contains("$TAB") || contents.contains("${TAB") {
+            restored = true;
+        }
+    }
+
+    if overrides.is_empty() || restored {
+        return Diagnosis::Ok(format!("{} history integration is configured", shell));
+    }
+
+    let fix = match shell {
+        "zsh" => "Run `tab --install zsh` to restore it within tabs".to_string(),
+        _ => format!("Only set {} when $TAB is unset", variable),
+    };
+
+    Diagnosis::Problem(format!(
+        "{} history is broken, as {} sets {}, which replaces the history of each tab.  {}",
+        shell,
+        overrides.join(", "),
+        variable,
+        fix
+    ))
+}
+
+/// Whether the line of shell configuration assigns the history file
+fn sets_history(shell: &str, line: &str) -> bool {
+    if shell == "fish" {
+        return line.starts_with("set ") && line.split_whitespace().any(|w| w == "fish_history");
+    }
+
+    line.starts_with("HISTFILE=") || line.starts_with("export HISTFILE=")
+}
+
+/// The configuration files which are executed by interactive shells
+fn rc_files(shell: &str) -> Vec<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+
+    match shell {
+        "bash" => vec![
+            PathBuf::from("/etc/bash.bashrc"),
+            home.join(".bashrc"),
+            home.join(".bash_profile"),
+            home.join(".profile"),
+        ],
+        "zsh" => vec![
+            PathBuf::from("/etc/zshrc"),
+            PathBuf::from("/etc/zsh/zshrc"),
+            home.join(".zshenv"),
+            home.join(".zshrc"),
+        ],
+        "fish" => vec![home.join(".config").join("fish").join("config.fish")],
+        _ => Vec::new(),
+    }
+}
+
+/// Creates the directory if it doesn't exist, and writes & removes a file within it
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let file = dir.join(format!(".tab-doctor-{}", std::process::id()));
+    std::fs::write(&file, b"")?;
+    std::fs::remove_file(&file)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_history_overrides, diagnose_daemon, rc_files, sets_history, Diagnosis};
+    use std::path::PathBuf;
+    use tab_api::config::DaemonConfig;
+
+    fn config(pid: i32, version: &str) -> DaemonConfig {
+        DaemonConfig {
+            pid,
+            port: 1234,
+            auth_token: "token".into(),
+            version: version.into(),
+        }
+    }
+
+    fn problem(diagnosis: &Diagnosis) -> Option<&str> {
+        match diagnosis {
+            Diagnosis::Ok(_) => None,
+            Diagnosis::Problem(message) => Some(message.as_str()),
+        }
+    }
+
+    fn rc_file(dir: &tempfile::TempDir, contents: &str) -> PathBuf {
+        let path = dir.path().join("rc");
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn daemon_stale_pid() {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id() as i32;
+        child.wait().unwrap();
+
+        let diagnoses = diagnose_daemon("daemon-pid.yml", &config(pid, "1.0.0"), "1.0.0");
+
+        assert_eq!(1, diagnoses.len());
+        let message = problem(&diagnoses[0]).expect("a stale pid is a problem");
+        assert!(message.contains("is stale"));
+        assert!(message.contains(&pid.to_string()));
+    }
+
+    #[test]
+    fn daemon_version_matches() {
+        let pid = std::process::id() as i32;
+        let diagnoses = diagnose_daemon("daemon-pid.yml", &config(pid, "1.0.0"), "1.0.0");
+
+        assert_eq!(2, diagnoses.len());
+        assert!(diagnoses
+            .iter()
+            .all(|diagnosis| problem(diagnosis).is_none()));
+    }
+
+    #[test]
+    fn daemon_version_mismatch() {
+        let pid = std::process::id() as i32;
+        let diagnoses = diagnose_daemon("daemon-pid.yml", &config(pid, "0.9.0"), "1.0.0");
+
+        assert_eq!(2, diagnoses.len());
+        assert!(problem(&diagnoses[0]).is_none());
+
+        let message = problem(&diagnoses[1]).expect("a version mismatch is a problem");
+        assert!(message.contains("running version 0.9.0, but tab is version 1.0.0"));
+    }
+
+    #[test]
+    fn daemon_version_missing() {
+        let pid = std::process::id() as i32;
+        let diagnoses = diagnose_daemon("daemon-pid.yml", &config(pid, ""), "1.0.0");
+
+        let message = problem(&diagnoses[1]).expect("a missing version is a problem");
+        assert!(message.contains("older version of tab"));
+    }
+
+    #[test]
+    fn sets_history_bash() {
+        assert!(sets_history("bash", "HISTFILE=~/.bash_history"));
+        assert!(sets_history("bash", "export HISTFILE=~/.bash_history"));
+        assert!(!sets_history("bash", "export HISTSIZE=1000"));
+        assert!(!sets_history("bash", "# HISTFILE=~/.bash_history"));
+    }
+
+    #[test]
+    fn sets_history_fish() {
+        assert!(sets_history("fish", "set -g fish_history default"));
+        assert!(!sets_history("fish", "set -g fish_greeting"));
+        assert!(!sets_history("fish", "echo fish_history"));
+    }
+
+    #[test]
+    fn rc_files_by_shell() {
+        let home = dirs::home_dir().unwrap_or_default();
+
+        assert!(rc_files("bash").contains(&home.join(".bashrc")));
+        assert!(rc_files("zsh").contains(&home.join(".zshrc")));
+        assert_eq!(
+            vec![home.join(".config").join("fish").join("config.fish")],
+            rc_files("fish")
+        );
+        assert!(rc_files("sh").is_empty());
+    }
+
+    #[test]
+    fn history_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = rc_file(&dir, "export HISTSIZE=1000\nexport HISTFILE=~/.history\n");
+
+        let diagnosis = check_history_overrides("bash", std::slice::from_ref(&file));
+        let message = problem(&diagnosis).expect("the override is a problem");
+        assert!(message.contains(&format!("{}:2", file.to_string_lossy())));
+        assert!(message.contains("Only set HISTFILE when $TAB is unset"));
+
+        let diagnosis = check_history_overrides("zsh", &[file]);
+        let message = problem(&diagnosis).expect("the override is a problem");
+        assert!(message.contains("tab --install zsh"));
+    }
+
+    #[test]
+    fn history_override_guarded() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = rc_file(
+            &dir,
+            "if [ -z \"$TAB\" ]; then\n  export HISTFILE=~/.history\nfi\n",
+        );
+
+        let diagnosis = check_history_overrides("bash", &[file]);
+        assert!(problem(&diagnosis).is_none());
+    }
+
+    #[test]
+    fn history_without_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = rc_file(&dir, "export HISTSIZE=1000\n");
+        let missing = dir.path().join("missing");
+
+        let diagnosis = check_history_overrides("bash", &[file, missing]);
+        assert!(problem(&diagnosis).is_none());
+    }
+}
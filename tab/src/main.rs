@@ -2,11 +2,16 @@
 //!
 
 pub mod cli;
+mod doctor;
 mod install;
 
 use anyhow::Context;
 use cli::init;
-use tab_api::{config::history_path, log::set_level, tab::normalize_name};
+use tab_api::{
+    config::{activity_path, fish_history, history_path, tab_name_path},
+    log::set_level,
+    tab::{normalize_name, TabId},
+};
 
 pub fn main() -> anyhow::Result<()> {
     let args = init();
@@ -55,21 +60,134 @@ pub fn main() -> anyhow::Result<()> {
         let histfile = history_path(shell, &normalize_name(tab))?;
         print!("{}", histfile.to_string_lossy());
 
+        Ok(())
+    } else if let Some(shell) = args.value_of("RENAME-ENV") {
+        // used by statusline snippets, to update $TAB after the tab is renamed
+        print!("{}", rename_env(shell));
+
         Ok(())
     } else if let Some(args) = args.values_of("INSTALL") {
         install::run(args)
     } else if args.is_present("STARSHIP") {
         // used for the starship prompt
-        let tab = std::env::var("TAB");
+        let tab = current_tab();
 
-        if let Err(_) = tab {
+        if tab.is_none() {
             std::process::exit(1);
         }
 
         print!("tab {}", tab.unwrap());
 
+        let activity = activity_summary();
+        if !activity.is_empty() {
+            print!(" {}", activity);
+        }
+
+        Ok(())
+    } else if args.is_present("CHECK-CONFIG") {
+        if !tab_command::check_config_main() {
+            std::process::exit(1);
+        }
+
+        Ok(())
+    } else if args.is_present("DOCTOR") {
+        if !doctor::run(cli::VERSION) {
+            std::process::exit(1);
+        }
+
+        Ok(())
+    } else if args.is_present("PRINT-SCHEMA") {
+        tab_command::print_schema_main();
+
+        Ok(())
+    } else if args.is_present("ACTIVITY") {
+        // used for statusline snippets
+        print!("{}", activity_summary());
+
         Ok(())
     } else {
         tab_command::command_main(args)
     }
 }
+
+/// Prints shell commands which update `$TAB` and the shell history, if the current tab has been renamed.
+/// The new name is written by the pty, and read at each prompt.
+fn rename_env(shell: &str) -> String {
+    let current = std::env::var("TAB").ok();
+    let name = match (current, renamed_tab()) {
+        (Some(current), Some(name)) if name != current => name,
+        _ => return String::new(),
+    };
+
+    if shell == "fish" {
+        return format!(
+            "set -gx TAB {}; set -gx fish_history {}\n",
+            quote(name.as_str()),
+            quote(fish_history(name.as_str()).as_str())
+        );
+    }
+
+    let mut env = format!("export TAB={}\n", quote(name.as_str()));
+    if let Ok(histfile) = history_path(shell, name.as_str()) {
+        let histfile = histfile.to_string_lossy();
+        env += format!("export HISTFILE={}\n", quote(histfile.as_ref())).as_str();
+    }
+
+    env
+}
+
+/// The new name of the current tab, if it has been renamed since the shell was launched
+fn renamed_tab() -> Option<String> {
+    let id = std::env::var("TAB_ID").ok()?.parse().ok()?;
+    let path = tab_name_path(TabId(id)).ok()?;
+    let name = std::fs::read_to_string(path).ok()?;
+
+    Some(name.trim().to_string())
+}
+
+/// The name of the current tab, including renames which have not yet been applied to `$TAB`
+fn current_tab() -> Option<String> {
+    if std::env::var("TAB").is_err() {
+        return None;
+    }
+
+    renamed_tab().or_else(|| std::env::var("TAB").ok())
+}
+
+/// Single-quotes the value for bash, fish, sh, and zsh
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\"'\"'"))
+}
+
+/// Summarizes background tabs with unread output, as written by the daemon.
+/// Tabs which rang the bell are suffixed with `!`, and other unread tabs with `*`.
+/// The current tab is omitted.
+fn activity_summary() -> String {
+    let current = current_tab();
+    let activity = activity_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .unwrap_or_default();
+
+    let mut tabs = Vec::new();
+    for line in activity.lines() {
+        let mut fields = line.split_whitespace();
+        let name = match fields.next() {
+            Some(name) => name,
+            None => continue,
+        };
+
+        if current.as_deref() == Some(name) {
+            continue;
+        }
+
+        let flags: Vec<&str> = fields.collect();
+        if flags.contains(&"bell") {
+            tabs.push(format!("{}!", name));
+        } else if flags.contains(&"unread") {
+            tabs.push(format!("{}*", name));
+        }
+    }
+
+    tabs.join(" ")
+}